@@ -0,0 +1,75 @@
+//! Резервная копия приватного ключа для хранения на сервере.
+//!
+//! Приватный X25519-ключ шифруется мастер-ключом, деривированным из пароля (`kdf::PasswordKdf`).
+//! Параметры KDF и соль лежат в той же структуре, поэтому клиент на новом устройстве восстанавливает
+//! ключ, зная только пароль. Публичный ключ и версия входят в AAD: подменить их на сервере нельзя.
+
+use serde::{Deserialize, Serialize};
+
+use super::kdf::{KdfParams, PasswordKdf};
+use super::keys::{Nonce, PrivateKey, PublicKey};
+use super::types::{CryptoError, KeyPair};
+use zeroize::Zeroizing;
+
+use super::{aead_open, aead_seal, b64_decode, b64_encode};
+
+/// Текущая версия формата `PrivateKeyBackup`.
+pub const PRIVATE_KEY_BACKUP_VERSION: u8 = 1;
+const BACKUP_AAD_LABEL: &[u8] = b"ren-sdk-private-key-backup";
+
+/// Зашифрованный приватный ключ вместе с публичным ключом и параметрами KDF (serde, Base64-поля).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PrivateKeyBackup {
+    pub version: u8,
+    pub public_key: PublicKey,
+    pub kdf: PasswordKdf,
+    pub nonce: Nonce,
+    pub ciphertext: String,
+}
+
+/// Шифрует приватный ключ `key_pair` паролем с параметрами KDF по умолчанию (Argon2id).
+pub fn seal_private_key(key_pair: &KeyPair, password: &str) -> Result<PrivateKeyBackup, CryptoError> {
+    seal_private_key_with_params(key_pair, password, KdfParams::default())
+}
+
+/// Как `seal_private_key`, но с явными параметрами KDF; соль выбирается заново через `generate_salt`.
+pub fn seal_private_key_with_params(key_pair: &KeyPair, password: &str, params: KdfParams) -> Result<PrivateKeyBackup, CryptoError> {
+    let sk = checked_private_key(key_pair)?;
+    let (kdf, master) = PasswordKdf::create(password, params)?;
+    let aad = backup_aad(PRIVATE_KEY_BACKUP_VERSION, &key_pair.public_key);
+    let nonce = Nonce::generate()?;
+    let ciphertext = aead_seal(&master, &nonce, sk.as_bytes(), &aad)?;
+    Ok(PrivateKeyBackup {
+        version: PRIVATE_KEY_BACKUP_VERSION,
+        public_key: key_pair.public_key,
+        kdf,
+        nonce,
+        ciphertext: b64_encode(&ciphertext),
+    })
+}
+
+/// Восстанавливает пару ключей из резервной копии; неверный пароль — `CryptoError::WrongPassword`.
+pub fn open_private_key(backup: &PrivateKeyBackup, password: &str) -> Result<KeyPair, CryptoError> {
+    if backup.version != PRIVATE_KEY_BACKUP_VERSION {
+        return Err(CryptoError::UnsupportedVersion(backup.version));
+    }
+    let master = backup.kdf.derive(password)?;
+    let aad = backup_aad(backup.version, &backup.public_key);
+    let sk_bytes = Zeroizing::new(aead_open(&master, &backup.nonce, &b64_decode(&backup.ciphertext)?, &aad)?);
+    let key_pair = KeyPair { public_key: backup.public_key, private_key: PrivateKey::from_bytes(&sk_bytes)? };
+    checked_private_key(&key_pair)?;
+    Ok(key_pair)
+}
+
+// Проверяет, что публичный ключ пары получен из её приватного ключа.
+fn checked_private_key(key_pair: &KeyPair) -> Result<&PrivateKey, CryptoError> {
+    if key_pair.private_key.public_key() != key_pair.public_key {
+        return Err(CryptoError::KeyPairMismatch);
+    }
+    Ok(&key_pair.private_key)
+}
+
+// AAD = метка || версия || публичный ключ (RAW).
+fn backup_aad(version: u8, public_key: &PublicKey) -> Vec<u8> {
+    [BACKUP_AAD_LABEL, &[version], public_key.as_bytes()].concat()
+}
@@ -1,18 +1,63 @@
 use base64::{engine::general_purpose, Engine as _};
-use chacha20poly1305::aead::Aead;
+use chacha20poly1305::aead::{Aead, Payload};
 use chacha20poly1305::KeyInit;
-use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use chacha20poly1305::ChaCha20Poly1305;
+#[cfg(feature = "crypto_p256_aesgcm")]
+use aes_gcm::Aes256Gcm;
 use hkdf::Hkdf;
 use pbkdf2::pbkdf2_hmac;
 use sha2::{Digest, Sha256};
 use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+use zeroize::{Zeroize, Zeroizing};
 
 #[path = "types/mod.rs"]
 pub mod types;
+#[path = "envelope.rs"]
+pub mod envelope;
+#[path = "identity.rs"]
+pub mod identity;
+#[path = "x3dh.rs"]
+pub mod x3dh;
+#[path = "ratchet.rs"]
+pub mod ratchet;
+#[path = "sender_keys.rs"]
+pub mod sender_keys;
+#[path = "treekem.rs"]
+pub mod treekem;
+#[path = "stream.rs"]
+pub mod stream;
+#[path = "kdf.rs"]
+pub mod kdf;
+#[path = "backup.rs"]
+pub mod backup;
+#[path = "keys.rs"]
+pub mod keys;
+#[path = "rng.rs"]
+pub mod rng;
+#[path = "vectors.rs"]
+pub mod vectors;
+#[path = "fingerprint.rs"]
+pub mod fingerprint;
+#[path = "recipients.rs"]
+pub mod recipients;
+#[path = "devices.rs"]
+pub mod devices;
+#[path = "provisioning.rs"]
+pub mod provisioning;
+#[cfg(feature = "crypto_p256_aesgcm")]
+#[path = "webcrypto.rs"]
+pub mod webcrypto;
+use envelope::{CipherSuite, open_envelope_or_legacy, seal_envelope};
 pub use types::{
     AeadKey, CryptoError, DecryptedFileWithMessage, EncryptedFile, EncryptedFileWithMessage,
-    EncryptedMessage, KeyPair,
+    EncryptedMessage, KeyPair, SecretString, WRAPPED_KEY_VERSION, WrappedKey,
 };
+pub use keys::{Nonce, PrivateKey, PublicKey, Salt, SymmetricKey};
+
+/// Версия формата `EncryptedFileWithMessage` с раздельными подключами (0 — legacy с общим ключом и nonce).
+pub const FILE_WITH_MESSAGE_VERSION: u8 = 1;
+const FILE_PART_INFO: &[u8] = b"ren-sdk-file-with-message-file";
+const MESSAGE_PART_INFO: &[u8] = b"ren-sdk-file-with-message-message";
 
 // Helpers for base64
 fn b64_encode(data: &[u8]) -> String {
@@ -21,45 +66,71 @@ fn b64_encode(data: &[u8]) -> String {
 fn b64_decode(s: &str) -> Result<Vec<u8>, CryptoError> {
     Ok(general_purpose::STANDARD.decode(s)?)
 }
+// Base64 секрета сразу в `SecretString`, без промежуточной незатираемой копии.
+fn secret_b64(data: &[u8]) -> SecretString {
+    SecretString::from(b64_encode(data))
+}
+
+// Сравнение без раннего выхода, чтобы время не зависело от позиции первого различия.
+fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
 
-fn nonce_from_slice(n: &[u8]) -> Result<Nonce, CryptoError> {
-    if n.len() != 12 { return Err(CryptoError::InvalidKeyLen("nonce".into())); }
-    let mut arr = [0u8; 12];
-    arr.copy_from_slice(n);
-    Ok(Nonce::from(arr))
+// Случайные байты из текущего источника (по умолчанию ОС, в тестах — `rng::with_rng`); сбой — ошибка, а не паника.
+fn fill_random(buf: &mut [u8]) -> Result<(), CryptoError> {
+    rng::fill_bytes(buf)
 }
 
-fn nonce_from_b64(b64: &str) -> Result<Nonce, CryptoError> {
-    let n = b64_decode(b64)?;
-    nonce_from_slice(&n)
+// HKDF-SHA256 expand в `out`; превышение длины (255 * 32 байт) — `CryptoError::Kdf`.
+fn hkdf_expand(hk: &Hkdf<Sha256>, info: &[u8], out: &mut [u8]) -> Result<(), CryptoError> {
+    hk.expand(info, out).map_err(|_| CryptoError::Kdf("hkdf expand".into()))
 }
 
-// Nonce: 12 bytes as in WebCrypto examples
-pub fn generate_nonce() -> String {
-    let mut nonce = [0u8; 12];
-    getrandom::getrandom(&mut nonce).expect("rand");
-    b64_encode(&nonce)
+// Единственная точка AEAD для модулей крейта: ChaCha20-Poly1305, nonce 12 байт.
+// Пустые `aad` дают тот же шифртекст, что и вызов без AAD.
+fn aead_seal(key: &AeadKey, nonce: &Nonce, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    aead_seal_with_suite(CipherSuite::ChaCha20Poly1305, key, nonce, plaintext, aad)
 }
 
-// Salt: 16 bytes
-pub fn generate_salt() -> String {
-    let mut salt = [0u8; 16];
-    getrandom::getrandom(&mut salt).expect("rand");
-    b64_encode(&salt)
+fn aead_open(key: &AeadKey, nonce: &Nonce, ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    aead_open_with_suite(CipherSuite::ChaCha20Poly1305, key, nonce, ciphertext, aad)
+}
+
+// То же для явно выбранного набора (конверт, `webcrypto`).
+fn aead_seal_with_suite(suite: CipherSuite, key: &AeadKey, nonce: &Nonce, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let payload = Payload { msg: plaintext, aad };
+    Ok(match suite {
+        CipherSuite::ChaCha20Poly1305 => ChaCha20Poly1305::new(key.as_key()).encrypt(&(*nonce).into(), payload)?,
+        #[cfg(feature = "crypto_p256_aesgcm")]
+        CipherSuite::Aes256Gcm => Aes256Gcm::new(key.as_key()).encrypt(&(*nonce).into(), payload)?,
+    })
+}
+
+fn aead_open_with_suite(suite: CipherSuite, key: &AeadKey, nonce: &Nonce, ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let payload = Payload { msg: ciphertext, aad };
+    Ok(match suite {
+        CipherSuite::ChaCha20Poly1305 => ChaCha20Poly1305::new(key.as_key()).decrypt(&(*nonce).into(), payload)?,
+        #[cfg(feature = "crypto_p256_aesgcm")]
+        CipherSuite::Aes256Gcm => Aes256Gcm::new(key.as_key()).decrypt(&(*nonce).into(), payload)?,
+    })
+}
+
+// Nonce: 12 bytes as in WebCrypto examples (Base64; типизированный вариант — `Nonce::generate`)
+pub fn generate_nonce() -> Result<String, CryptoError> {
+    Ok(Nonce::generate()?.to_string())
+}
+
+// Salt: 16 bytes (Base64; типизированный вариант — `Salt::generate`)
+pub fn generate_salt() -> Result<String, CryptoError> {
+    Ok(Salt::generate()?.to_string())
 }
 
 // types moved to self::types
 
 /// Generates an X25519 key pair for ECDH (RAW 32 bytes, Base64 when exported).
-pub fn generate_key_pair(_extractable_private_key: bool) -> KeyPair {
-    let mut sk_bytes = [0u8; 32];
-    getrandom::getrandom(&mut sk_bytes).expect("rand");
-    let sk = StaticSecret::from(sk_bytes);
-    let pk = X25519PublicKey::from(&sk);
-    KeyPair {
-        public_key: b64_encode(pk.as_bytes()),
-        private_key: b64_encode(sk.to_bytes().as_slice()),
-    }
+pub fn generate_key_pair(_extractable_private_key: bool) -> Result<KeyPair, CryptoError> {
+    let private_key = PrivateKey::generate()?;
+    Ok(KeyPair { public_key: private_key.public_key(), private_key })
 }
 
 /// Экспортирует публичный X25519-ключ в Base64 (RAW 32 байта).
@@ -68,8 +139,8 @@ pub fn export_public_key_b64(public_key: &X25519PublicKey) -> String {
 }
 
 /// Экспортирует приватный X25519-ключ в Base64 (RAW 32 байта).
-pub fn export_private_key_b64(private_key: &StaticSecret) -> String {
-    b64_encode(private_key.to_bytes().as_slice())
+pub fn export_private_key_b64(private_key: &StaticSecret) -> SecretString {
+    secret_b64(private_key.as_bytes())
 }
 
 /// Импортирует публичный X25519-ключ из Base64 (ожидается 32 байта RAW).
@@ -90,173 +161,296 @@ pub fn import_private_key_b64(b64: &str) -> Result<StaticSecret, CryptoError> {
     Ok(StaticSecret::from(arr))
 }
 
-/// Деривирует 32-байтный мастер-ключ по PBKDF2-HMAC-SHA256(100k) из пароля и соли (Base64-16).
+/// Деривирует 32-байтный мастер-ключ по PBKDF2-HMAC-SHA256(100k) из пароля и соли (16 байт).
 /// Замечание: используется для расшифровки приватного ключа с сервера, не для шифрования сообщений/файлов.
-pub fn derive_key_from_password(password: &str, salt_b64: &str) -> Result<AeadKey, CryptoError> {
-    let mut out = [0u8; 32];
-    let salt = b64_decode(salt_b64)?;
-    pbkdf2_hmac::<Sha256>(password.as_bytes(), &salt, 100_000, &mut out);
-    AeadKey::from_bytes(&out)
+/// Новые записи стоит создавать через `kdf::PasswordKdf` (Argon2id); эта функция — для legacy-записей.
+pub fn derive_key_from_password(password: &str, salt: &Salt) -> Result<AeadKey, CryptoError> {
+    let mut out = Zeroizing::new([0u8; 32]);
+    pbkdf2_hmac::<Sha256>(password.as_bytes(), salt.as_bytes(), 100_000, out.as_mut_slice());
+    AeadKey::from_bytes(out.as_slice())
 }
 
 /// Деривирует 32-байтный ключ из произвольной строки: SHA-256(secret)[0..32].
+/// Без соли, растяжения и разделения доменов; оставлена для чтения старых данных.
+#[deprecated(note = "use kdf::derive_key_from_secret or kdf::derive_key_from_human_secret with a context label")]
 pub fn derive_key_from_string(secret: &str) -> Result<AeadKey, CryptoError> {
+    legacy_key_from_string(secret)
+}
+
+// SHA-256(secret) — деривация `derive_key_from_string`, нужна путям совместимости.
+pub(crate) fn legacy_key_from_string(secret: &str) -> Result<AeadKey, CryptoError> {
     let mut hasher = Sha256::new();
     hasher.update(secret.as_bytes());
-    let digest = hasher.finalize();
-    let mut out = [0u8; 32];
-    out.copy_from_slice(&digest[..32]);
-    AeadKey::from_bytes(&out)
+    let mut digest = hasher.finalize();
+    let key = AeadKey::from_bytes(&digest[..32]);
+    digest.as_mut_slice().zeroize();
+    key
 }
 
 /// Шифрует строку и возвращает Base64-последовательность: nonce(12) || ciphertext.
 pub fn encrypt_data(data: &str, key: &AeadKey) -> Result<String, CryptoError> {
-    let cipher = ChaCha20Poly1305::new(&key.0);
-    let mut nonce_bytes = [0u8; 12];
-    getrandom::getrandom(&mut nonce_bytes).expect("rand");
-    let nonce = Nonce::from(nonce_bytes);
-    let ciphertext = cipher.encrypt(&nonce, data.as_bytes())?;
+    encrypt_data_with_aad(data, key, &[])
+}
+
+/// Как `encrypt_data`, но связывает с тегом Poly1305 associated data (id чата, отправителя, сообщения).
+/// Формат вывода тот же; расшифровка возможна только через `decrypt_data_with_aad` с теми же `aad`.
+pub fn encrypt_data_with_aad(data: &str, key: &AeadKey, aad: &[u8]) -> Result<String, CryptoError> {
+    encrypt_data_with_nonce(data, key, aad, &Nonce::generate()?)
+}
+
+// Детерминированные части `encrypt_data`/`encrypt_message`/`wrap_symmetric_key`: вся случайность передаётся
+// явно, поэтому тест-векторы воспроизводят шифрование без подмены источника `rng`.
+pub(crate) fn encrypt_data_with_nonce(data: &str, key: &AeadKey, aad: &[u8], nonce: &Nonce) -> Result<String, CryptoError> {
+    let ciphertext = aead_seal(key, nonce, data.as_bytes(), aad)?;
     let mut out = Vec::with_capacity(12 + ciphertext.len());
-    out.extend_from_slice(&nonce_bytes);
+    out.extend_from_slice(nonce.as_bytes());
     out.extend_from_slice(&ciphertext);
     Ok(b64_encode(&out))
 }
 
 /// Дешифрует результат `encrypt_data` (Base64: nonce(12)||ciphertext) в строку.
 pub fn decrypt_data(b64_combined: &str, key: &AeadKey) -> Result<String, CryptoError> {
+    decrypt_data_with_aad(b64_combined, key, &[])
+}
+
+/// Дешифрует результат `encrypt_data_with_aad` (или конверт с теми же `aad`).
+pub fn decrypt_data_with_aad(b64_combined: &str, key: &AeadKey, aad: &[u8]) -> Result<String, CryptoError> {
     let data = b64_decode(b64_combined)?;
-    if data.len() < 12 { return Err(CryptoError::Aead); }
-    let (nonce_bytes, ct) = data.split_at(12);
-    let nonce = nonce_from_slice(nonce_bytes)?;
-    let cipher = ChaCha20Poly1305::new(&key.0);
-    let pt = cipher.decrypt(&nonce, ct)?;
-    let s = String::from_utf8(pt).map_err(|_| CryptoError::Aead)?;
-    Ok(s)
+    let pt = open_envelope_or_legacy(&data, key, aad, || {
+        if data.len() < 12 { return Err(CryptoError::InvalidCiphertext("shorter than nonce".into())); }
+        let (nonce_bytes, ct) = data.split_at(12);
+        aead_open(key, &Nonce::from_bytes(nonce_bytes)?, ct, aad)
+    })?;
+    Ok(String::from_utf8(pt)?)
 }
 
-pub fn generate_message_encryption_key() -> AeadKey {
-    let mut key_bytes = [0u8; 32];
-    getrandom::getrandom(&mut key_bytes).expect("rand");
-    AeadKey::from_bytes(&key_bytes).expect("key size")
+pub fn generate_message_encryption_key() -> Result<AeadKey, CryptoError> {
+    let mut key_bytes = Zeroizing::new([0u8; 32]);
+    fill_random(key_bytes.as_mut_slice())?;
+    AeadKey::from_bytes(key_bytes.as_slice())
 }
 
 // Wrap symmetric key using X25519 ECDH + HKDF-SHA256 -> ChaCha20-Poly1305
 /// Оборачивает симметричный ключ `key_to_wrap` для получателя (X25519 ECDH + HKDF + AEAD).
 pub fn wrap_symmetric_key(
     key_to_wrap: &AeadKey,
-    receiver_public_key_b64: &str,
-) -> Result<(String /*wrappedKey*/, String /*ephemeralPublicKey*/, String /*nonce*/ ), CryptoError> {
-    let receiver_pk = import_public_key_b64(receiver_public_key_b64)?;
+    receiver_public_key: &PublicKey,
+) -> Result<WrappedKey, CryptoError> {
     // ephemeral keypair
-    let mut eph_bytes = [0u8; 32];
-    getrandom::getrandom(&mut eph_bytes).expect("rand");
-    let eph_sk = StaticSecret::from(eph_bytes);
-    let eph_pk = X25519PublicKey::from(&eph_sk);
+    let eph_sk = PrivateKey::generate()?;
+    let nonce = Nonce::generate()?;
+    wrap_symmetric_key_with(key_to_wrap, receiver_public_key, &eph_sk, nonce)
+}
+
+pub(crate) fn wrap_symmetric_key_with(
+    key_to_wrap: &AeadKey,
+    receiver_public_key: &PublicKey,
+    eph_sk: &PrivateKey,
+    nonce: Nonce,
+) -> Result<WrappedKey, CryptoError> {
     // shared secret
-    let shared = eph_sk.diffie_hellman(&receiver_pk);
+    let shared = eph_sk.diffie_hellman(receiver_public_key);
     // derive wrapping key
-    let hk = Hkdf::<Sha256>::new(None, shared.as_bytes());
-    let mut wrap_key_bytes = [0u8; 32];
-    hk.expand(b"ren-sdk-wrap", &mut wrap_key_bytes).map_err(|_| CryptoError::Aead)?;
-    let wrap_key = AeadKey::from_bytes(&wrap_key_bytes)?;
+    let wrap_key = wrap_key(&shared)?;
     // encrypt raw key bytes
-    let mut nonce_bytes = [0u8; 12];
-    getrandom::getrandom(&mut nonce_bytes).expect("rand");
-    let cipher = ChaCha20Poly1305::new(&wrap_key.0);
-    let nonce = Nonce::from(nonce_bytes);
-    let ct = cipher.encrypt(&nonce, &key_to_wrap.to_bytes()[..])?;
-    Ok((
-        b64_encode(&ct),
-        export_public_key_b64(&eph_pk),
-        b64_encode(&nonce_bytes),
-    ))
+    let ct = aead_seal(&wrap_key, &nonce, Zeroizing::new(key_to_wrap.to_bytes()).as_slice(), &[])?;
+    Ok(WrappedKey { version: WRAPPED_KEY_VERSION, wrapped_key: b64_encode(&ct), ephemeral_public_key: eph_sk.public_key(), nonce })
+}
+
+// Ключ обёртки `wrap_symmetric_key`: HKDF-SHA256(salt = пусто, IKM = DH, info = `ren-sdk-wrap`).
+fn wrap_key(shared: &[u8; 32]) -> Result<AeadKey, CryptoError> {
+    let hk = Hkdf::<Sha256>::new(None, shared);
+    let mut wrap_key_bytes = Zeroizing::new([0u8; 32]);
+    hkdf_expand(&hk, b"ren-sdk-wrap", wrap_key_bytes.as_mut_slice())?;
+    AeadKey::from_bytes(wrap_key_bytes.as_slice())
+}
+
+fn check_wrapped_key_version(wrapped: &WrappedKey) -> Result<(), CryptoError> {
+    if wrapped.version != WRAPPED_KEY_VERSION {
+        return Err(CryptoError::UnsupportedVersion(wrapped.version));
+    }
+    Ok(())
 }
 
 /// Разворачивает симметричный ключ, ранее обёрнутый `wrap_symmetric_key`.
-pub fn unwrap_symmetric_key(
-    wrapped_key_b64: &str,
-    ephemeral_public_key_b64: &str,
-    nonce_b64: &str,
-    receiver_private_key_b64: &str,
+pub fn unwrap_symmetric_key(wrapped: &WrappedKey, receiver_private_key: &PrivateKey) -> Result<AeadKey, CryptoError> {
+    check_wrapped_key_version(wrapped)?;
+    let ct = b64_decode(&wrapped.wrapped_key)?;
+    let shared = receiver_private_key.diffie_hellman(&wrapped.ephemeral_public_key);
+    let pt = Zeroizing::new(aead_open(&wrap_key(&shared)?, &wrapped.nonce, &ct, &[])?);
+    AeadKey::from_bytes(&pt)
+}
+
+// IKM = DH(eph, R) || DH(S, R); info = label || eph_pub || S_pub || R_pub; AAD = S_pub || R_pub.
+fn authenticated_wrap_key(
+    eph_dh: &[u8; 32],
+    static_dh: &[u8; 32],
+    eph_pk: &PublicKey,
+    sender_pk: &PublicKey,
+    receiver_pk: &PublicKey,
+) -> Result<(AeadKey, Vec<u8>), CryptoError> {
+    let mut ikm = Zeroizing::new([0u8; 64]);
+    ikm[..32].copy_from_slice(eph_dh);
+    ikm[32..].copy_from_slice(static_dh);
+    let mut info = b"ren-sdk-wrap-auth".to_vec();
+    info.extend_from_slice(eph_pk.as_bytes());
+    info.extend_from_slice(sender_pk.as_bytes());
+    info.extend_from_slice(receiver_pk.as_bytes());
+    let hk = Hkdf::<Sha256>::new(None, ikm.as_slice());
+    let mut wrap_key_bytes = Zeroizing::new([0u8; 32]);
+    hkdf_expand(&hk, &info, wrap_key_bytes.as_mut_slice())?;
+    let mut aad = sender_pk.as_bytes().to_vec();
+    aad.extend_from_slice(receiver_pk.as_bytes());
+    Ok((AeadKey::from_bytes(wrap_key_bytes.as_slice())?, aad))
+}
+
+/// Оборачивает ключ с аутентификацией отправителя: к эфемерному DH добавляется DH статического
+/// ключа отправителя с ключом получателя, а оба публичных ключа связываются в HKDF info и AAD.
+/// Формат результата совпадает с `wrap_symmetric_key` (`WrappedKey`).
+pub fn wrap_symmetric_key_authenticated(
+    key_to_wrap: &AeadKey,
+    sender_private_key: &PrivateKey,
+    receiver_public_key: &PublicKey,
+) -> Result<WrappedKey, CryptoError> {
+    let sender_pk = sender_private_key.public_key();
+    let eph_sk = PrivateKey::generate()?;
+    let eph_pk = eph_sk.public_key();
+    let eph_dh = eph_sk.diffie_hellman(receiver_public_key);
+    let static_dh = sender_private_key.diffie_hellman(receiver_public_key);
+    let (wrap_key, aad) = authenticated_wrap_key(&eph_dh, &static_dh, &eph_pk, &sender_pk, receiver_public_key)?;
+    let nonce = Nonce::generate()?;
+    let ct = aead_seal(&wrap_key, &nonce, Zeroizing::new(key_to_wrap.to_bytes()).as_slice(), &aad)?;
+    Ok(WrappedKey { version: WRAPPED_KEY_VERSION, wrapped_key: b64_encode(&ct), ephemeral_public_key: eph_pk, nonce })
+}
+
+/// Разворачивает ключ из `wrap_symmetric_key_authenticated`, проверяя, что его обернул владелец
+/// `expected_sender_public_key`; иначе возвращается ошибка AEAD.
+pub fn unwrap_symmetric_key_authenticated(
+    wrapped: &WrappedKey,
+    receiver_private_key: &PrivateKey,
+    expected_sender_public_key: &PublicKey,
 ) -> Result<AeadKey, CryptoError> {
-    let ct = b64_decode(wrapped_key_b64)?;
-    let nonce = nonce_from_b64(nonce_b64)?;
-    let eph_pk = import_public_key_b64(ephemeral_public_key_b64)?;
-    let recv_sk = import_private_key_b64(receiver_private_key_b64)?;
-    let shared = recv_sk.diffie_hellman(&eph_pk);
-    let hk = Hkdf::<Sha256>::new(None, shared.as_bytes());
-    let mut wrap_key_bytes = [0u8; 32];
-    hk.expand(b"ren-sdk-wrap", &mut wrap_key_bytes).map_err(|_| CryptoError::Aead)?;
-    let wrap_key = AeadKey::from_bytes(&wrap_key_bytes)?;
-    let cipher = ChaCha20Poly1305::new(&wrap_key.0);
-    let pt = cipher.decrypt(&nonce, ct.as_ref())?;
+    check_wrapped_key_version(wrapped)?;
+    let ct = b64_decode(&wrapped.wrapped_key)?;
+    let eph_pk = &wrapped.ephemeral_public_key;
+    let receiver_pk = receiver_private_key.public_key();
+    let eph_dh = receiver_private_key.diffie_hellman(eph_pk);
+    let static_dh = receiver_private_key.diffie_hellman(expected_sender_public_key);
+    let (wrap_key, aad) = authenticated_wrap_key(&eph_dh, &static_dh, eph_pk, expected_sender_public_key, &receiver_pk)?;
+    let pt = Zeroizing::new(aead_open(&wrap_key, &wrapped.nonce, &ct, &aad)?);
     AeadKey::from_bytes(&pt)
 }
 
 /// AEAD-шифрование короткого сообщения (возвращает Base64 ciphertext + nonce).
 pub fn encrypt_message(data: &str, key: &AeadKey) -> Result<EncryptedMessage, CryptoError> {
-    let cipher = ChaCha20Poly1305::new(&key.0);
-    let mut nonce_bytes = [0u8; 12];
-    getrandom::getrandom(&mut nonce_bytes).expect("rand");
-    let nonce = Nonce::from(nonce_bytes);
-    let ct = cipher.encrypt(&nonce, data.as_bytes())?;
-    Ok(EncryptedMessage { ciphertext: b64_encode(&ct), nonce: b64_encode(&nonce_bytes) })
+    encrypt_message_with_aad(data, key, &[])
+}
+
+/// Как `encrypt_message`, но `aad` (например, id чата || id отправителя || id сообщения) входят в тег.
+pub fn encrypt_message_with_aad(data: &str, key: &AeadKey, aad: &[u8]) -> Result<EncryptedMessage, CryptoError> {
+    encrypt_message_with_nonce(data, key, aad, &Nonce::generate()?)
+}
+
+pub(crate) fn encrypt_message_with_nonce(data: &str, key: &AeadKey, aad: &[u8], nonce: &Nonce) -> Result<EncryptedMessage, CryptoError> {
+    let ct = aead_seal(key, nonce, data.as_bytes(), aad)?;
+    Ok(EncryptedMessage { ciphertext: b64_encode(&ct), nonce: nonce.to_string() })
 }
 
 /// AEAD-дешифрование сообщения по Base64 `ciphertext` и `nonce`.
 pub fn decrypt_message(ciphertext_b64: &str, nonce_b64: &str, key: &AeadKey) -> Result<String, CryptoError> {
+    decrypt_message_with_aad(ciphertext_b64, nonce_b64, key, &[])
+}
+
+/// Дешифрует результат `encrypt_message_with_aad`; несовпадение `aad` даёт `CryptoError::AuthenticationFailed`.
+pub fn decrypt_message_with_aad(ciphertext_b64: &str, nonce_b64: &str, key: &AeadKey, aad: &[u8]) -> Result<String, CryptoError> {
     let ct = b64_decode(ciphertext_b64)?;
-    let nonce = nonce_from_b64(nonce_b64)?;
-    let cipher = ChaCha20Poly1305::new(&key.0);
-    let pt = cipher.decrypt(&nonce, ct.as_ref())?;
-    let s = String::from_utf8(pt).map_err(|_| CryptoError::Aead)?;
-    Ok(s)
+    let pt = open_envelope_or_legacy(&ct, key, aad, || aead_open(key, &nonce_b64.parse()?, &ct, aad))?;
+    Ok(String::from_utf8(pt)?)
 }
 
 /// AEAD-шифрование произвольных байт файла. Возвращает Base64 ciphertext и nonce.
 pub fn encrypt_file(bytes: &[u8], filename: &str, mimetype: &str, key: &AeadKey) -> Result<EncryptedFile, CryptoError> {
-    let cipher = ChaCha20Poly1305::new(&key.0);
-    let mut nonce_bytes = [0u8; 12];
-    getrandom::getrandom(&mut nonce_bytes).expect("rand");
-    let nonce = Nonce::from(nonce_bytes);
-    let ct = cipher.encrypt(&nonce, bytes)?;
-    Ok(EncryptedFile { ciphertext: b64_encode(&ct), nonce: b64_encode(&nonce_bytes), filename: filename.to_string(), mimetype: mimetype.to_string() })
+    encrypt_file_with_aad(bytes, filename, mimetype, key, &[])
+}
+
+/// Как `encrypt_file`, но `aad` входят в тег. `filename`/`mimetype` в тег не входят — при необходимости
+/// добавьте их в `aad`.
+pub fn encrypt_file_with_aad(bytes: &[u8], filename: &str, mimetype: &str, key: &AeadKey, aad: &[u8]) -> Result<EncryptedFile, CryptoError> {
+    let nonce = Nonce::generate()?;
+    let ct = aead_seal(key, &nonce, bytes, aad)?;
+    Ok(EncryptedFile { ciphertext: b64_encode(&ct), nonce: nonce.to_string(), filename: filename.to_string(), mimetype: mimetype.to_string() })
 }
 
 /// AEAD-дешифрование файла по Base64 `ciphertext` и `nonce`.
 pub fn decrypt_file(ciphertext_b64: &str, nonce_b64: &str, key: &AeadKey) -> Result<Vec<u8>, CryptoError> {
+    decrypt_file_with_aad(ciphertext_b64, nonce_b64, key, &[])
+}
+
+/// Дешифрует результат `encrypt_file_with_aad`.
+pub fn decrypt_file_with_aad(ciphertext_b64: &str, nonce_b64: &str, key: &AeadKey, aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
     let ct = b64_decode(ciphertext_b64)?;
-    let nonce = nonce_from_b64(nonce_b64)?;
-    let cipher = ChaCha20Poly1305::new(&key.0);
-    let pt = cipher.decrypt(&nonce, ct.as_ref())?;
-    Ok(pt)
+    open_envelope_or_legacy(&ct, key, aad, || aead_open(key, &nonce_b64.parse()?, &ct, aad))
+}
+
+/// Как `encrypt_data`, но возвращает Base64 версионированного конверта с `key_id`.
+/// `decrypt_data` распознаёт оба формата.
+pub fn encrypt_data_envelope(data: &str, key: &AeadKey, key_id: &[u8]) -> Result<String, CryptoError> {
+    seal_envelope(data.as_bytes(), key, key_id, &[])?.to_b64()
+}
+
+/// Как `encrypt_message`, но `ciphertext` содержит Base64 конверта (`nonce` дублирует nonce конверта
+/// для совместимости структуры). `decrypt_message` распознаёт оба формата.
+pub fn encrypt_message_envelope(data: &str, key: &AeadKey, key_id: &[u8]) -> Result<EncryptedMessage, CryptoError> {
+    let env = seal_envelope(data.as_bytes(), key, key_id, &[])?;
+    Ok(EncryptedMessage { ciphertext: env.to_b64()?, nonce: b64_encode(&env.nonce) })
 }
 
-/// Удобный вариант: шифрует файл и сообщение под один nonce/ключ.
+/// Как `encrypt_file`, но `ciphertext` содержит Base64 конверта. `decrypt_file` распознаёт оба формата.
+pub fn encrypt_file_envelope(bytes: &[u8], filename: &str, mimetype: &str, key: &AeadKey, key_id: &[u8]) -> Result<EncryptedFile, CryptoError> {
+    let env = seal_envelope(bytes, key, key_id, &[])?;
+    Ok(EncryptedFile { ciphertext: env.to_b64()?, nonce: b64_encode(&env.nonce), filename: filename.to_string(), mimetype: mimetype.to_string() })
+}
+
+/// Удобный вариант: шифрует файл и сообщение одним ключом и общим nonce.
+/// Для каждой части из ключа выводится свой подключ (HKDF, salt = nonce), поэтому keystream не повторяется.
 pub fn encrypt_file_with_message(bytes: &[u8], message: &str, key: &AeadKey, filename: &str, mimetype: &str) -> Result<EncryptedFileWithMessage, CryptoError> {
-    let cipher = ChaCha20Poly1305::new(&key.0);
-    let mut nonce_bytes = [0u8; 12];
-    getrandom::getrandom(&mut nonce_bytes).expect("rand");
-    let nonce = Nonce::from(nonce_bytes);
-    let enc_file = cipher.encrypt(&nonce, bytes)?;
-    let enc_msg = cipher.encrypt(&nonce, message.as_bytes())?;
+    let nonce = Nonce::generate()?;
+    let enc_file = aead_seal(&file_with_message_key(key, &nonce, FILE_PART_INFO)?, &nonce, bytes, &[])?;
+    let enc_msg = aead_seal(&file_with_message_key(key, &nonce, MESSAGE_PART_INFO)?, &nonce, message.as_bytes(), &[])?;
     Ok(EncryptedFileWithMessage {
         enc_file: b64_encode(&enc_file),
         ciphertext: b64_encode(&enc_msg),
-        nonce: b64_encode(&nonce_bytes),
+        nonce: nonce.to_string(),
         filename: filename.to_string(),
         mimetype: mimetype.to_string(),
+        version: FILE_WITH_MESSAGE_VERSION,
     })
 }
 
 /// Дешифрует результат `encrypt_file_with_message` и возвращает байты файла и строку сообщения.
-pub fn decrypt_file_with_message(enc_file_b64: &str, ciphertext_b64: &str, nonce_b64: &str, key: &AeadKey, filename: &str, mimetype: &str) -> Result<DecryptedFileWithMessage, CryptoError> {
-    let nonce = nonce_from_b64(nonce_b64)?;
-    let cipher = ChaCha20Poly1305::new(&key.0);
-    let file_ct = b64_decode(enc_file_b64)?;
-    let msg_ct = b64_decode(ciphertext_b64)?;
-    let file = cipher.decrypt(&nonce, file_ct.as_ref())?;
-    let msg = cipher.decrypt(&nonce, msg_ct.as_ref())?;
-    let message = String::from_utf8(msg).map_err(|_| CryptoError::Aead)?;
-    Ok(DecryptedFileWithMessage { file, message, filename: filename.to_string(), mimetype: mimetype.to_string() })
+/// Схема выбирается по `version`: 0 — legacy (один ключ и nonce на обе части), `FILE_WITH_MESSAGE_VERSION` —
+/// подключи на часть; иные версии — `UnsupportedVersion`.
+pub fn decrypt_file_with_message(efm: &EncryptedFileWithMessage, key: &AeadKey) -> Result<DecryptedFileWithMessage, CryptoError> {
+    let nonce: Nonce = efm.nonce.parse()?;
+    let file_ct = b64_decode(&efm.enc_file)?;
+    let msg_ct = b64_decode(&efm.ciphertext)?;
+    let (file, msg) = match efm.version {
+        0 => (aead_open(key, &nonce, &file_ct, &[])?, aead_open(key, &nonce, &msg_ct, &[])?),
+        FILE_WITH_MESSAGE_VERSION => {
+            let file = aead_open(&file_with_message_key(key, &nonce, FILE_PART_INFO)?, &nonce, &file_ct, &[])?;
+            let msg = aead_open(&file_with_message_key(key, &nonce, MESSAGE_PART_INFO)?, &nonce, &msg_ct, &[])?;
+            (file, msg)
+        }
+        v => return Err(CryptoError::UnsupportedVersion(v)),
+    };
+    let message = String::from_utf8(msg)?;
+    Ok(DecryptedFileWithMessage { file, message, filename: efm.filename.clone(), mimetype: efm.mimetype.clone() })
+}
+
+// Подключ части `encrypt_file_with_message`: HKDF(salt = nonce, IKM = key, info = метка части).
+fn file_with_message_key(key: &AeadKey, nonce: &Nonce, info: &[u8]) -> Result<AeadKey, CryptoError> {
+    let hk = Hkdf::<Sha256>::new(Some(nonce.as_bytes()), key.as_key().as_slice());
+    let mut subkey = Zeroizing::new([0u8; 32]);
+    hkdf_expand(&hk, info, subkey.as_mut_slice())?;
+    AeadKey::from_bytes(subkey.as_slice())
 }
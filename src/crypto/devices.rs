@@ -0,0 +1,252 @@
+//! Несколько устройств одного аккаунта.
+//!
+//! У каждого устройства свои X25519-ключ (обёртка ключей) и Ed25519-ключ подписи; ключ идентичности аккаунта
+//! подписывает список устройств `DeviceList`. Добавление и отзыв устройства увеличивают `generation` и
+//! переподписывают список. Получатель принимает список через `DeviceDirectory::accept`: проверяет подпись,
+//! что ключ аккаунта не сменился и что `generation` растёт (старый список с отозванным устройством не
+//! подсунуть повторно). Отправитель оборачивает ключ сообщения для всех устройств получателей через
+//! `wrap_for_recipients`; id записи — `device_recipient_id(account_id, device_id)`.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::generate_key_pair;
+use super::identity::{IdentityKeyPair, generate_identity_key_pair, verify_signature};
+use super::keys::{PublicKey, SigningPublicKey};
+use super::recipients::{Recipient, RecipientKeys, unwrap_for_recipient, wrap_for_recipients};
+use super::types::{AeadKey, CryptoError, KeyPair};
+
+/// Текущая версия формата `DeviceList`.
+pub const DEVICE_LIST_VERSION: u8 = 1;
+const DEVICE_LIST_CONTEXT: &[u8] = b"ren-sdk-device-list";
+
+/// Публичные ключи устройства, как они попадают в список устройств.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DeviceInfo {
+    pub device_id: String,
+    pub public_key: PublicKey,
+    /// Публичный Ed25519-ключ подписи устройства.
+    pub signing_public_key: SigningPublicKey,
+}
+
+/// Ключи устройства вместе с приватными частями (хранятся только на самом устройстве).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    pub device_id: String,
+    pub key_pair: KeyPair,
+    pub signing_key: IdentityKeyPair,
+}
+
+impl Device {
+    pub fn generate(device_id: &str) -> Result<Self, CryptoError> {
+        check_device_id(device_id)?;
+        Ok(Device {
+            device_id: device_id.to_string(),
+            key_pair: generate_key_pair(false)?,
+            signing_key: generate_identity_key_pair()?,
+        })
+    }
+
+    pub fn info(&self) -> DeviceInfo {
+        DeviceInfo {
+            device_id: self.device_id.clone(),
+            public_key: self.key_pair.public_key,
+            signing_public_key: self.signing_key.public_key,
+        }
+    }
+}
+
+/// Список устройств аккаунта, подписанный ключом идентичности аккаунта (serde).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DeviceList {
+    pub version: u8,
+    pub account_id: String,
+    /// Публичный Ed25519-ключ идентичности аккаунта.
+    pub identity_key: SigningPublicKey,
+    /// Номер редакции: растёт при каждом добавлении или отзыве устройства.
+    pub generation: u64,
+    pub devices: Vec<DeviceInfo>,
+    pub signature: String,
+}
+
+/// id записи устройства в `RecipientKeys`: `account_id/device_id`.
+pub fn device_recipient_id(account_id: &str, device_id: &str) -> String {
+    format!("{account_id}/{device_id}")
+}
+
+// '/' в device_id сделал бы `device_recipient_id` неоднозначным.
+fn check_device_id(device_id: &str) -> Result<(), CryptoError> {
+    if device_id.is_empty() || device_id.contains('/') {
+        return Err(CryptoError::Devices(format!("invalid device id {device_id:?}")));
+    }
+    Ok(())
+}
+
+fn check_devices(devices: &[DeviceInfo]) -> Result<(), CryptoError> {
+    for (i, d) in devices.iter().enumerate() {
+        check_device_id(&d.device_id)?;
+        if devices[..i].iter().any(|other| other.device_id == d.device_id) {
+            return Err(CryptoError::Devices(format!("duplicate device {}", d.device_id)));
+        }
+    }
+    Ok(())
+}
+
+impl DeviceList {
+    /// Первая редакция списка (`generation` = 1), подписанная `identity`.
+    pub fn new(account_id: &str, identity: &IdentityKeyPair, devices: Vec<DeviceInfo>) -> Result<Self, CryptoError> {
+        check_devices(&devices)?;
+        let mut list = DeviceList {
+            version: DEVICE_LIST_VERSION,
+            account_id: account_id.to_string(),
+            identity_key: identity.public_key,
+            generation: 1,
+            devices,
+            signature: String::new(),
+        };
+        list.signature = identity.sign(&list.signed_data()?);
+        Ok(list)
+    }
+
+    // Подписываемые данные: метка || детерминированный serde_json всех полей, кроме подписи.
+    fn signed_data(&self) -> Result<Vec<u8>, CryptoError> {
+        let mut data = DEVICE_LIST_CONTEXT.to_vec();
+        data.extend(serde_json::to_vec(&(self.version, &self.account_id, &self.identity_key, self.generation, &self.devices))?);
+        Ok(data)
+    }
+
+    fn resign(&mut self, identity: &IdentityKeyPair) -> Result<(), CryptoError> {
+        self.generation += 1;
+        self.signature = identity.sign(&self.signed_data()?);
+        Ok(())
+    }
+
+    fn check_identity(&self, identity: &IdentityKeyPair) -> Result<(), CryptoError> {
+        if identity.public_key != self.identity_key {
+            return Err(CryptoError::KeyPairMismatch);
+        }
+        Ok(())
+    }
+
+    /// Добавляет устройство и переподписывает список.
+    pub fn add_device(&mut self, device: DeviceInfo, identity: &IdentityKeyPair) -> Result<(), CryptoError> {
+        self.check_identity(identity)?;
+        check_device_id(&device.device_id)?;
+        if self.device(&device.device_id).is_some() {
+            return Err(CryptoError::Devices(format!("duplicate device {}", device.device_id)));
+        }
+        self.devices.push(device);
+        self.resign(identity)
+    }
+
+    /// Отзывает устройство и переподписывает список; возвращает удалённую запись.
+    pub fn revoke_device(&mut self, device_id: &str, identity: &IdentityKeyPair) -> Result<DeviceInfo, CryptoError> {
+        self.check_identity(identity)?;
+        let pos = self
+            .devices
+            .iter()
+            .position(|d| d.device_id == device_id)
+            .ok_or_else(|| CryptoError::Devices(format!("unknown device {device_id}")))?;
+        let removed = self.devices.remove(pos);
+        self.resign(identity)?;
+        Ok(removed)
+    }
+
+    /// Проверяет версию, уникальность устройств и подпись ключом `identity_key`.
+    pub fn verify(&self) -> Result<(), CryptoError> {
+        if self.version != DEVICE_LIST_VERSION {
+            return Err(CryptoError::UnsupportedVersion(self.version));
+        }
+        check_devices(&self.devices)?;
+        verify_signature(&self.signed_data()?, &self.signature, &self.identity_key)
+    }
+
+    pub fn device(&self, device_id: &str) -> Option<&DeviceInfo> {
+        self.devices.iter().find(|d| d.device_id == device_id)
+    }
+
+    /// Устройства как получатели для `wrap_for_recipients` (id — `device_recipient_id`).
+    pub fn members(&self) -> Vec<Recipient> {
+        self.devices
+            .iter()
+            .map(|d| Recipient { id: device_recipient_id(&self.account_id, &d.device_id), public_key: d.public_key })
+            .collect()
+    }
+}
+
+/// Изменения относительно ранее принятой редакции списка.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceListUpdate {
+    pub added: Vec<DeviceInfo>,
+    pub revoked: Vec<DeviceInfo>,
+}
+
+/// Проверенные списки устройств контактов (serde — хранится на устройстве).
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceDirectory {
+    lists: BTreeMap<String, DeviceList>,
+}
+
+impl DeviceDirectory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Принимает список, полученный от сервера или контакта. Отклоняет неверную подпись, смену ключа
+    /// идентичности аккаунта и редакцию не новее сохранённой. Возвращает добавленные и отозванные устройства.
+    pub fn accept(&mut self, list: DeviceList) -> Result<DeviceListUpdate, CryptoError> {
+        list.verify()?;
+        let mut update = DeviceListUpdate::default();
+        match self.lists.get(&list.account_id) {
+            Some(known) => {
+                if known.identity_key != list.identity_key {
+                    return Err(CryptoError::Devices(format!("identity key changed for {}", list.account_id)));
+                }
+                if list.generation <= known.generation {
+                    return Err(CryptoError::Devices(format!("stale device list generation {}", list.generation)));
+                }
+                update.added = list.devices.iter().filter(|d| !known.devices.contains(d)).cloned().collect();
+                update.revoked = known.devices.iter().filter(|d| !list.devices.contains(d)).cloned().collect();
+            }
+            None => update.added = list.devices.clone(),
+        }
+        self.lists.insert(list.account_id.clone(), list);
+        Ok(update)
+    }
+
+    pub fn list(&self, account_id: &str) -> Option<&DeviceList> {
+        self.lists.get(account_id)
+    }
+
+    /// Оборачивает `key` для всех устройств аккаунтов `account_ids` (включая свои другие устройства,
+    /// если передан свой аккаунт).
+    pub fn wrap_for_accounts(&self, key: &AeadKey, account_ids: &[&str]) -> Result<RecipientKeys, CryptoError> {
+        let mut members = Vec::new();
+        for account_id in account_ids {
+            let list = self
+                .list(account_id)
+                .ok_or_else(|| CryptoError::Devices(format!("unknown account {account_id}")))?;
+            members.extend(list.members());
+        }
+        wrap_for_devices(key, &members)
+    }
+}
+
+/// Оборачивает `key` для устройств из одного или нескольких списков (каждый должен быть проверен заранее).
+pub fn wrap_for_device_lists(key: &AeadKey, lists: &[&DeviceList]) -> Result<RecipientKeys, CryptoError> {
+    let members: Vec<Recipient> = lists.iter().flat_map(|l| l.members()).collect();
+    wrap_for_devices(key, &members)
+}
+
+fn wrap_for_devices(key: &AeadKey, members: &[Recipient]) -> Result<RecipientKeys, CryptoError> {
+    if members.is_empty() {
+        return Err(CryptoError::Devices("no devices to wrap for".into()));
+    }
+    wrap_for_recipients(key, members)
+}
+
+/// Разворачивает ключ на устройстве `device` аккаунта `account_id`.
+pub fn unwrap_for_device(keys: &RecipientKeys, account_id: &str, device: &Device) -> Result<AeadKey, CryptoError> {
+    unwrap_for_recipient(keys, &device_recipient_id(account_id, &device.device_id), &device.key_pair.private_key)
+}
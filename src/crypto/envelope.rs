@@ -0,0 +1,199 @@
+//! Версионированный бинарный конверт для шифртекстов.
+//!
+//! Формат (все длины — big-endian):
+//!
+//! ```text
+//! magic "RENE" (4) | version (1) | suite id (1) | key id len (1) | key id | nonce len (1) | nonce
+//!                  | aad len (4) | ciphertext || tag
+//! ```
+//!
+//! Заголовок целиком (от magic до `aad len`) вместе с внешними associated data входит в AAD шифра,
+//! поэтому подменить версию, набор алгоритмов или идентификатор ключа нельзя. Сами associated data
+//! в конверт не записываются — хранится только их длина, данные передаёт вызывающая сторона.
+
+use super::keys::Nonce;
+use super::types::{AeadKey, CryptoError};
+use super::{aead_open_with_suite, aead_seal_with_suite, b64_decode, b64_encode, fill_random};
+
+/// Префикс, по которому конверт отличается от legacy-форматов (`nonce || ciphertext`).
+pub const ENVELOPE_MAGIC: [u8; 4] = *b"RENE";
+/// Текущая версия формата конверта.
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// Идентификатор набора алгоритмов в конверте.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CipherSuite {
+    /// ChaCha20-Poly1305, ключ 32 байта, nonce 12 байт.
+    ChaCha20Poly1305 = 1,
+    /// AES-256-GCM (WebCrypto `AES-GCM`), ключ 32 байта, nonce 12 байт; фича `crypto_p256_aesgcm`.
+    #[cfg(feature = "crypto_p256_aesgcm")]
+    Aes256Gcm = 2,
+}
+
+impl CipherSuite {
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_id(id: u8) -> Result<Self, CryptoError> {
+        match id {
+            1 => Ok(CipherSuite::ChaCha20Poly1305),
+            #[cfg(feature = "crypto_p256_aesgcm")]
+            2 => Ok(CipherSuite::Aes256Gcm),
+            other => Err(CryptoError::UnsupportedSuite(other)),
+        }
+    }
+
+    pub fn nonce_len(self) -> usize {
+        match self {
+            CipherSuite::ChaCha20Poly1305 => 12,
+            #[cfg(feature = "crypto_p256_aesgcm")]
+            CipherSuite::Aes256Gcm => 12,
+        }
+    }
+}
+
+/// Разобранный конверт; `aad_len` — длина внешних associated data, которые нужно передать при расшифровке.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    pub version: u8,
+    pub suite: CipherSuite,
+    pub key_id: Vec<u8>,
+    pub nonce: Vec<u8>,
+    pub aad_len: u32,
+    pub ciphertext: Vec<u8>,
+}
+
+impl Envelope {
+    /// Заголовок конверта (всё, кроме ciphertext); он же — префикс AAD шифра.
+    /// `InvalidEnvelope`, если key id или nonce не помещаются в однобайтовую длину.
+    pub fn header_bytes(&self) -> Result<Vec<u8>, CryptoError> {
+        let mut out = Vec::with_capacity(12 + self.key_id.len() + self.nonce.len());
+        out.extend_from_slice(&ENVELOPE_MAGIC);
+        out.push(self.version);
+        out.push(self.suite.id());
+        out.push(len_u8(self.key_id.len(), "key id")?);
+        out.extend_from_slice(&self.key_id);
+        out.push(len_u8(self.nonce.len(), "nonce")?);
+        out.extend_from_slice(&self.nonce);
+        out.extend_from_slice(&self.aad_len.to_be_bytes());
+        Ok(out)
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, CryptoError> {
+        let mut out = self.header_bytes()?;
+        out.extend_from_slice(&self.ciphertext);
+        Ok(out)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let mut r = Reader(bytes);
+        if r.take(4)? != ENVELOPE_MAGIC {
+            return Err(CryptoError::InvalidEnvelope("missing magic".into()));
+        }
+        let version = r.byte()?;
+        if version != ENVELOPE_VERSION {
+            return Err(CryptoError::UnsupportedVersion(version));
+        }
+        let suite = CipherSuite::from_id(r.byte()?)?;
+        let key_id_len = r.byte()? as usize;
+        let key_id = r.take(key_id_len)?.to_vec();
+        let nonce_len = r.byte()? as usize;
+        if nonce_len != suite.nonce_len() {
+            return Err(CryptoError::InvalidEnvelope(format!("nonce length {nonce_len}")));
+        }
+        let nonce = r.take(nonce_len)?.to_vec();
+        let aad_len = r.u32()?;
+        Ok(Envelope { version, suite, key_id, nonce, aad_len, ciphertext: r.0.to_vec() })
+    }
+
+    pub fn to_b64(&self) -> Result<String, CryptoError> {
+        Ok(b64_encode(&self.encode()?))
+    }
+
+    pub fn from_b64(b64: &str) -> Result<Self, CryptoError> {
+        Self::decode(&b64_decode(b64)?)
+    }
+}
+
+/// Начинаются ли байты с magic конверта (быстрая проверка перед `Envelope::decode`).
+pub fn is_envelope(bytes: &[u8]) -> bool {
+    bytes.starts_with(&ENVELOPE_MAGIC)
+}
+
+/// Шифрует `plaintext` в конверт текущей версии; `key_id` — до 255 байт, `aad` связывается с тегом.
+pub fn seal_envelope(plaintext: &[u8], key: &AeadKey, key_id: &[u8], aad: &[u8]) -> Result<Envelope, CryptoError> {
+    seal_envelope_with_suite(plaintext, key, key_id, aad, CipherSuite::ChaCha20Poly1305)
+}
+
+/// Как `seal_envelope`, но с явно выбранным набором алгоритмов (`open_envelope` выбирает его по suite id).
+pub fn seal_envelope_with_suite(plaintext: &[u8], key: &AeadKey, key_id: &[u8], aad: &[u8], suite: CipherSuite) -> Result<Envelope, CryptoError> {
+    let aad_len = u32::try_from(aad.len()).map_err(|_| CryptoError::InvalidEnvelope("aad longer than u32::MAX bytes".into()))?;
+    len_u8(key_id.len(), "key id")?;
+    let mut nonce = vec![0u8; suite.nonce_len()];
+    fill_random(&mut nonce)?;
+    let mut env = Envelope {
+        version: ENVELOPE_VERSION,
+        suite,
+        key_id: key_id.to_vec(),
+        nonce,
+        aad_len,
+        ciphertext: Vec::new(),
+    };
+    let full_aad = [env.header_bytes()?.as_slice(), aad].concat();
+    env.ciphertext = aead_seal_with_suite(suite, key, &Nonce::from_bytes(&env.nonce)?, plaintext, &full_aad)?;
+    Ok(env)
+}
+
+/// Дешифрует конверт; `aad` должны совпадать с переданными при шифровании.
+pub fn open_envelope(env: &Envelope, key: &AeadKey, aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if env.aad_len as usize != aad.len() {
+        return Err(CryptoError::AuthenticationFailed);
+    }
+    let full_aad = [env.header_bytes()?.as_slice(), aad].concat();
+    aead_open_with_suite(env.suite, key, &Nonce::from_bytes(&env.nonce)?, &env.ciphertext, &full_aad)
+}
+
+// Данные без magic читаются `legacy`. С magic сначала пробуется конверт; если разбор или тег не прошли,
+// пробуется и `legacy` (nonce legacy-шифртекста случайно начинается с "RENE" с вероятностью 2^-32),
+// а при неудаче обоих возвращается ошибка конверта (`UnsupportedVersion`, `UnsupportedSuite`, ...).
+pub(crate) fn open_envelope_or_legacy(
+    bytes: &[u8],
+    key: &AeadKey,
+    aad: &[u8],
+    legacy: impl FnOnce() -> Result<Vec<u8>, CryptoError>,
+) -> Result<Vec<u8>, CryptoError> {
+    if !is_envelope(bytes) {
+        return legacy();
+    }
+    Envelope::decode(bytes)
+        .and_then(|env| open_envelope(&env, key, aad))
+        .or_else(|err| legacy().map_err(|_| err))
+}
+
+fn len_u8(len: usize, what: &str) -> Result<u8, CryptoError> {
+    u8::try_from(len).map_err(|_| CryptoError::InvalidEnvelope(format!("{what} longer than 255 bytes")))
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], CryptoError> {
+        if self.0.len() < n {
+            return Err(CryptoError::InvalidEnvelope("truncated".into()));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn byte(&mut self) -> Result<u8, CryptoError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, CryptoError> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
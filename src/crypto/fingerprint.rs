@@ -0,0 +1,187 @@
+//! Номера безопасности (safety numbers) для проверки ключей собеседника.
+//!
+//! Отпечаток стороны — `FINGERPRINT_ITERATIONS` раундов SHA-512 над версией, публичным X25519-ключом и
+//! стабильным идентификатором (id пользователя), как в Signal. Номер безопасности — 60 цифр: по 30 от каждого
+//! отпечатка, меньший идёт первым, поэтому обе стороны видят одно и то же число. QR-код содержит оба отпечатка
+//! целиком; при сканировании они сверяются в обратном порядке.
+//!
+//! `VerificationStore` хранит (serde) подтверждённый ключ каждого контакта: если ключ, полученный от сервера
+//! (`import_public_key_b64`), отличается от подтверждённого, состояние становится `Changed`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+
+use super::keys::PublicKey;
+use super::types::CryptoError;
+use super::{b64_decode, b64_encode, ct_eq};
+
+/// Версия формата отпечатка и QR-кода.
+pub const FINGERPRINT_VERSION: u8 = 0;
+/// Число раундов SHA-512 при вычислении отпечатка.
+pub const FINGERPRINT_ITERATIONS: usize = 5200;
+const FINGERPRINT_LEN: usize = 32;
+
+fn fingerprint(stable_id: &str, public_key: &PublicKey) -> [u8; FINGERPRINT_LEN] {
+    let mut hash = Sha512::new()
+        .chain_update([0, FINGERPRINT_VERSION])
+        .chain_update(public_key.as_bytes())
+        .chain_update(stable_id.as_bytes())
+        .finalize();
+    for _ in 1..FINGERPRINT_ITERATIONS {
+        hash = Sha512::new().chain_update(hash).chain_update(public_key.as_bytes()).finalize();
+    }
+    let mut out = [0u8; FINGERPRINT_LEN];
+    out.copy_from_slice(&hash[..FINGERPRINT_LEN]);
+    out
+}
+
+// 30 цифр: шесть 5-байтовых блоков (big-endian) по модулю 100000.
+fn digits(fingerprint: &[u8; FINGERPRINT_LEN]) -> String {
+    fingerprint[..30]
+        .chunks(5)
+        .map(|c| {
+            let n = c.iter().fold(0u64, |acc, b| (acc << 8) | u64::from(*b));
+            format!("{:05}", n % 100_000)
+        })
+        .collect()
+}
+
+/// Номер безопасности пары (мы, собеседник).
+#[derive(Clone, PartialEq, Eq)]
+pub struct SafetyNumber {
+    local: [u8; FINGERPRINT_LEN],
+    remote: [u8; FINGERPRINT_LEN],
+}
+
+impl SafetyNumber {
+    pub fn new(local_id: &str, local_key: &PublicKey, remote_id: &str, remote_key: &PublicKey) -> Self {
+        SafetyNumber { local: fingerprint(local_id, local_key), remote: fingerprint(remote_id, remote_key) }
+    }
+
+    /// 60 цифр без разделителей (одинаковы у обеих сторон).
+    pub fn digits(&self) -> String {
+        let (local, remote) = (digits(&self.local), digits(&self.remote));
+        if local <= remote { local + &remote } else { remote + &local }
+    }
+
+    /// Сверка с номером, который собеседник продиктовал или показал; пробелы и прочие не-цифры игнорируются.
+    pub fn matches(&self, other: &str) -> bool {
+        let other: String = other.chars().filter(char::is_ascii_digit).collect();
+        ct_eq(self.digits().as_bytes(), other.as_bytes())
+    }
+
+    /// Полезная нагрузка QR-кода: Base64(version (1) || наш отпечаток (32) || отпечаток собеседника (32)).
+    pub fn qr_payload(&self) -> String {
+        let mut out = Vec::with_capacity(1 + 2 * FINGERPRINT_LEN);
+        out.push(FINGERPRINT_VERSION);
+        out.extend_from_slice(&self.local);
+        out.extend_from_slice(&self.remote);
+        b64_encode(&out)
+    }
+
+    /// Проверяет QR-код, отсканированный с устройства собеседника: его «наш» отпечаток должен совпасть
+    /// с нашим отпечатком собеседника и наоборот. `Ok(false)` — ключи не совпадают.
+    pub fn verify_qr_payload(&self, payload_b64: &str) -> Result<bool, CryptoError> {
+        let payload = b64_decode(payload_b64)?;
+        let (&version, rest) = payload.split_first().ok_or_else(|| CryptoError::InvalidFingerprint("empty payload".into()))?;
+        if version != FINGERPRINT_VERSION {
+            return Err(CryptoError::UnsupportedVersion(version));
+        }
+        if rest.len() != 2 * FINGERPRINT_LEN {
+            return Err(CryptoError::InvalidFingerprint(format!("payload length {}", rest.len())));
+        }
+        let (their_local, their_remote) = rest.split_at(FINGERPRINT_LEN);
+        Ok(ct_eq(their_local, &self.remote) & ct_eq(their_remote, &self.local))
+    }
+}
+
+/// Группы по 5 цифр через пробел, как номер показывается пользователю.
+impl fmt::Display for SafetyNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.digits();
+        let groups: Vec<&str> = (0..digits.len()).step_by(5).map(|i| &digits[i..i + 5]).collect();
+        f.write_str(&groups.join(" "))
+    }
+}
+
+impl fmt::Debug for SafetyNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SafetyNumber({self})")
+    }
+}
+
+/// Состояние проверки ключа контакта.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationState {
+    /// Ключ ни разу не подтверждался.
+    Unverified,
+    /// Текущий ключ совпадает с подтверждённым.
+    Verified,
+    /// Текущий ключ отличается от подтверждённого — нужно проверить номер безопасности заново.
+    Changed,
+}
+
+/// Известный ключ контакта и ключ, подтверждённый пользователем (если был).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ContactKey {
+    pub public_key: PublicKey,
+    pub verified_key: Option<PublicKey>,
+}
+
+impl ContactKey {
+    pub fn state(&self) -> VerificationState {
+        match self.verified_key {
+            None => VerificationState::Unverified,
+            Some(verified) if verified == self.public_key => VerificationState::Verified,
+            Some(_) => VerificationState::Changed,
+        }
+    }
+}
+
+/// Состояние проверки всех контактов (serde — хранится на устройстве).
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerificationStore {
+    contacts: BTreeMap<String, ContactKey>,
+}
+
+impl VerificationStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Запоминает ключ контакта, полученный от сервера, и возвращает состояние проверки.
+    /// Принимает и результат `import_public_key_b64`: `store.observe_key(id, import_public_key_b64(b64)?)`.
+    pub fn observe_key(&mut self, contact_id: &str, public_key: impl Into<PublicKey>) -> VerificationState {
+        let public_key = public_key.into();
+        let contact = self
+            .contacts
+            .entry(contact_id.to_string())
+            .or_insert(ContactKey { public_key, verified_key: None });
+        contact.public_key = public_key;
+        contact.state()
+    }
+
+    /// Отмечает ключ контакта подтверждённым (после сверки номера или QR-кода).
+    pub fn mark_verified(&mut self, contact_id: &str, public_key: &PublicKey) {
+        self.contacts.insert(contact_id.to_string(), ContactKey { public_key: *public_key, verified_key: Some(*public_key) });
+    }
+
+    /// Снимает подтверждение: контакт снова `Unverified`.
+    pub fn clear_verification(&mut self, contact_id: &str) {
+        if let Some(contact) = self.contacts.get_mut(contact_id) {
+            contact.verified_key = None;
+        }
+    }
+
+    pub fn state(&self, contact_id: &str) -> Option<VerificationState> {
+        self.contacts.get(contact_id).map(ContactKey::state)
+    }
+
+    pub fn contact(&self, contact_id: &str) -> Option<&ContactKey> {
+        self.contacts.get(contact_id)
+    }
+}
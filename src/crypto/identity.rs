@@ -0,0 +1,42 @@
+//! Ключи идентичности Ed25519: подпись пре-ключей, списков устройств и сообщений.
+//!
+//! Связь с X25519: ключ идентичности детерминированно конвертируется в X25519-пару
+//! (секрет — `SigningKey::to_scalar_bytes`, публичный ключ — точка Монтгомери `VerifyingKey::to_montgomery`).
+//! Полученный `KeyPair` можно передавать в `wrap_symmetric_key`/`unwrap_symmetric_key` и X3DH,
+//! поэтому пользователю достаточно хранить и публиковать один ключ идентичности.
+
+use serde::{Deserialize, Serialize};
+
+use super::keys::{SigningPrivateKey, SigningPublicKey};
+use super::types::{CryptoError, KeyPair};
+
+/// Пара ключей идентичности Ed25519 (в serde — Base64: публичный ключ 32 байта, seed приватного ключа 32 байта).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct IdentityKeyPair {
+    pub public_key: SigningPublicKey,
+    pub private_key: SigningPrivateKey,
+}
+
+impl IdentityKeyPair {
+    /// Подписывает `data` и возвращает подпись Ed25519 в Base64 (64 байта).
+    pub fn sign(&self, data: &[u8]) -> String {
+        self.private_key.sign(data)
+    }
+
+    /// X25519-пара, соответствующая этому ключу идентичности (для ECDH/обёртки ключей).
+    pub fn to_x25519_key_pair(&self) -> KeyPair {
+        let private_key = self.private_key.to_x25519();
+        KeyPair { public_key: private_key.public_key(), private_key }
+    }
+}
+
+/// Генерирует новую пару ключей идентичности Ed25519.
+pub fn generate_identity_key_pair() -> Result<IdentityKeyPair, CryptoError> {
+    let private_key = SigningPrivateKey::generate()?;
+    Ok(IdentityKeyPair { public_key: private_key.public_key(), private_key })
+}
+
+/// Проверяет подпись Ed25519 (Base64) над `data` публичным ключом идентичности.
+pub fn verify_signature(data: &[u8], signature_b64: &str, public_key: &SigningPublicKey) -> Result<(), CryptoError> {
+    public_key.verify(data, signature_b64)
+}
@@ -0,0 +1,228 @@
+//! Деривация мастер-ключа из пароля с настраиваемыми параметрами.
+//!
+//! Параметры KDF (`KdfParams`) хранятся на сервере вместе с солью и проверочным значением в `PasswordKdf`,
+//! поэтому их можно усиливать со временем: клиент всегда деривирует ключ по сохранённым параметрам,
+//! а `migrate_password_kdf` переводит запись со старых параметров (PBKDF2) на текущие (Argon2id).
+//!
+//! Ключи из произвольных секретов деривируются с меткой контекста: `derive_key_from_secret` (HKDF) —
+//! для секретов с высокой энтропией, `derive_key_from_human_secret` (KDF с растяжением) — для придуманных
+//! людьми. Один и тот же секрет в разных контекстах даёт независимые ключи.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use hkdf::Hkdf;
+use pbkdf2::pbkdf2_hmac;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use super::types::{AeadKey, CryptoError};
+use super::keys::Salt;
+use super::{b64_encode, ct_eq, hkdf_expand};
+
+/// Число итераций PBKDF2 в `derive_key_from_password` (записи, созданные до Argon2id).
+pub const LEGACY_PBKDF2_ITERATIONS: u32 = 100_000;
+/// Нижняя граница числа итераций PBKDF2: слабее legacy-записей параметры не принимаются.
+pub const MIN_PBKDF2_ITERATIONS: u32 = LEGACY_PBKDF2_ITERATIONS;
+/// Нижняя граница памяти Argon2id (минимум OWASP: 19 МиБ).
+pub const MIN_ARGON2_MEMORY_KIB: u32 = 19 * 1024;
+/// Нижняя граница числа проходов Argon2id (минимум OWASP при 19 МиБ).
+pub const MIN_ARGON2_ITERATIONS: u32 = 2;
+/// Верхняя граница памяти Argon2id, которую клиент согласится потратить по сохранённым параметрам (1 ГиБ).
+pub const MAX_ARGON2_MEMORY_KIB: u32 = 1024 * 1024;
+/// Верхняя граница числа проходов Argon2id по сохранённым параметрам.
+pub const MAX_ARGON2_ITERATIONS: u32 = 64;
+/// Верхняя граница числа потоков Argon2id по сохранённым параметрам.
+pub const MAX_ARGON2_PARALLELISM: u32 = 16;
+/// Верхняя граница числа итераций PBKDF2 по сохранённым параметрам.
+pub const MAX_PBKDF2_ITERATIONS: u32 = 10_000_000;
+const VERIFIER_INFO: &[u8] = b"ren-sdk-kdf-verifier";
+const CONTEXT_INFO_PREFIX: &[u8] = b"ren-sdk-kdf-context:";
+const CONTEXT_SALT_PREFIX: &[u8] = b"ren-sdk-kdf-salt:";
+
+/// Алгоритм и параметры деривации ключа из пароля.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "alg", rename_all = "snake_case")]
+pub enum KdfParams {
+    Pbkdf2Sha256 { iterations: u32 },
+    /// `memory_kib` — память в КиБ, `iterations` — число проходов, `parallelism` — число потоков.
+    Argon2id { memory_kib: u32, iterations: u32, parallelism: u32 },
+}
+
+impl Default for KdfParams {
+    /// Argon2id с 64 МиБ памяти, 3 проходами и 1 потоком (второй рекомендуемый вариант RFC 9106).
+    fn default() -> Self {
+        KdfParams::Argon2id { memory_kib: 64 * 1024, iterations: 3, parallelism: 1 }
+    }
+}
+
+impl KdfParams {
+    /// Параметры `derive_key_from_password`: PBKDF2-HMAC-SHA256, 100k итераций.
+    pub fn legacy_pbkdf2() -> Self {
+        KdfParams::Pbkdf2Sha256 { iterations: LEGACY_PBKDF2_ITERATIONS }
+    }
+
+    /// Деривирует 32-байтный ключ из пароля и соли по этим параметрам.
+    /// Параметры вне `MIN_*`..`MAX_*` отклоняются с `CryptoError::Kdf`.
+    pub fn derive(&self, password: &str, salt: &Salt) -> Result<AeadKey, CryptoError> {
+        let mut out = Zeroizing::new([0u8; 32]);
+        match *self {
+            KdfParams::Pbkdf2Sha256 { iterations } => {
+                if iterations < MIN_PBKDF2_ITERATIONS {
+                    return Err(CryptoError::Kdf(format!("pbkdf2 iterations {iterations} below minimum")));
+                }
+                if iterations > MAX_PBKDF2_ITERATIONS {
+                    return Err(CryptoError::Kdf(format!("pbkdf2 iterations {iterations} exceed limit")));
+                }
+                pbkdf2_hmac::<Sha256>(password.as_bytes(), salt.as_bytes(), iterations, out.as_mut_slice());
+            }
+            KdfParams::Argon2id { memory_kib, iterations, parallelism } => {
+                if memory_kib < MIN_ARGON2_MEMORY_KIB {
+                    return Err(CryptoError::Kdf(format!("argon2 memory {memory_kib} KiB below minimum")));
+                }
+                if iterations < MIN_ARGON2_ITERATIONS {
+                    return Err(CryptoError::Kdf(format!("argon2 iterations {iterations} below minimum")));
+                }
+                if memory_kib > MAX_ARGON2_MEMORY_KIB {
+                    return Err(CryptoError::Kdf(format!("argon2 memory {memory_kib} KiB exceeds limit")));
+                }
+                if iterations > MAX_ARGON2_ITERATIONS {
+                    return Err(CryptoError::Kdf(format!("argon2 iterations {iterations} exceed limit")));
+                }
+                if parallelism > MAX_ARGON2_PARALLELISM {
+                    return Err(CryptoError::Kdf(format!("argon2 parallelism {parallelism} exceeds limit")));
+                }
+                let params = Params::new(memory_kib, iterations, parallelism, Some(out.len()))
+                    .map_err(|e| CryptoError::Kdf(e.to_string()))?;
+                Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+                    .hash_password_into(password.as_bytes(), salt.as_bytes(), out.as_mut_slice())
+                    .map_err(|e| CryptoError::Kdf(e.to_string()))?;
+            }
+        }
+        AeadKey::from_bytes(out.as_slice())
+    }
+}
+
+/// Серверная запись KDF пользователя: соль, параметры и проверочное значение.
+/// `verifier` отсутствует у legacy-записей, где хранилась только соль PBKDF2.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PasswordKdf {
+    pub salt: Salt,
+    pub params: KdfParams,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verifier: Option<String>,
+}
+
+impl PasswordKdf {
+    /// Новая запись со свежей солью; возвращает запись и деривированный ключ.
+    pub fn create(password: &str, params: KdfParams) -> Result<(Self, AeadKey), CryptoError> {
+        let salt = Salt::generate()?;
+        let key = params.derive(password, &salt)?;
+        let verifier = Some(verifier_for(&key)?);
+        Ok((PasswordKdf { salt, params, verifier }, key))
+    }
+
+    /// Запись для существующего пользователя `derive_key_from_password`, у которого есть только соль.
+    pub fn legacy_pbkdf2(salt: Salt) -> Self {
+        PasswordKdf { salt, params: KdfParams::legacy_pbkdf2(), verifier: None }
+    }
+
+    /// Деривирует ключ по сохранённым параметрам и сверяет его с `verifier` (если он есть).
+    /// Неверный пароль даёт `CryptoError::WrongPassword`; без verifier он обнаружится только при расшифровке.
+    pub fn derive(&self, password: &str) -> Result<AeadKey, CryptoError> {
+        let key = self.params.derive(password, &self.salt)?;
+        if let Some(expected) = &self.verifier {
+            let actual = verifier_for(&key)?;
+            if !ct_eq(actual.as_bytes(), expected.as_bytes()) {
+                return Err(CryptoError::WrongPassword);
+            }
+        }
+        Ok(key)
+    }
+
+    /// Деривирует ключ по записи и, если запись устарела относительно `target`, сразу готовит миграцию.
+    pub fn derive_and_migrate(&self, password: &str, target: KdfParams) -> Result<MasterKey, CryptoError> {
+        let key = self.derive(password)?;
+        let migration = if self.needs_migration(&target) {
+            let (kdf, new_key) = PasswordKdf::create(password, target)?;
+            Some(KdfMigration { old_key: key.clone(), new_key, kdf })
+        } else {
+            None
+        };
+        Ok(MasterKey { key, migration })
+    }
+
+    /// Нужна ли миграция на `target`: другие параметры или нет verifier.
+    pub fn needs_migration(&self, target: &KdfParams) -> bool {
+        self.params != *target || self.verifier.is_none()
+    }
+}
+
+/// Результат миграции: старым ключом расшифровывается приватный ключ, новым — шифруется заново,
+/// после чего `kdf` заменяет запись на сервере.
+#[derive(Clone)]
+pub struct KdfMigration {
+    pub old_key: AeadKey,
+    pub new_key: AeadKey,
+    pub kdf: PasswordKdf,
+}
+
+/// Мастер-ключ по сохранённой записи: `key` открывает существующие данные; `migration` есть,
+/// если запись устарела — тогда данные перешифровываются `migration.new_key`, а запись на сервере заменяется.
+#[derive(Clone)]
+pub struct MasterKey {
+    pub key: AeadKey,
+    pub migration: Option<KdfMigration>,
+}
+
+/// Переводит запись на параметры `target` со свежей солью; `None`, если запись уже актуальна.
+/// Старый ключ деривируется по сохранённым параметрам, поэтому неверный пароль не приводит к миграции
+/// (для legacy-записей без verifier это проверяет расшифровка приватного ключа старым ключом).
+pub fn migrate_password_kdf(password: &str, stored: &PasswordKdf, target: KdfParams) -> Result<Option<KdfMigration>, CryptoError> {
+    if !stored.needs_migration(&target) {
+        return Ok(None);
+    }
+    let old_key = stored.derive(password)?;
+    let (kdf, new_key) = PasswordKdf::create(password, target)?;
+    Ok(Some(KdfMigration { old_key, new_key, kdf }))
+}
+
+/// Ключ из секрета с высокой энтропией (случайный токен, общий секрет): HKDF-SHA256
+/// с `salt` (необязательна) и info = метка || `context`. Растяжения нет — не для паролей.
+pub fn derive_key_from_secret(secret: &str, context: &str, salt: Option<&Salt>) -> Result<AeadKey, CryptoError> {
+    expand_with_context(salt.map(Salt::as_bytes), secret.as_bytes(), context)
+}
+
+/// Ключ из секрета, придуманного человеком: растяжение по `params` (Argon2id/PBKDF2), затем HKDF с `context`.
+/// Без `salt` соль выводится из контекста — это защищает от общих таблиц, но не от перебора по контексту,
+/// поэтому по возможности передавайте случайную соль (`Salt::generate`) и храните её рядом с данными.
+pub fn derive_key_from_human_secret(secret: &str, context: &str, salt: Option<&Salt>, params: &KdfParams) -> Result<AeadKey, CryptoError> {
+    check_context(context)?;
+    let salt = match salt {
+        Some(salt) => salt.clone(),
+        None => Salt::from_bytes(&Sha256::digest([CONTEXT_SALT_PREFIX, context.as_bytes()].concat())[..16])?,
+    };
+    let stretched = params.derive(secret, &salt)?;
+    expand_with_context(None, stretched.as_key().as_slice(), context)
+}
+
+fn check_context(context: &str) -> Result<(), CryptoError> {
+    if context.is_empty() {
+        return Err(CryptoError::Kdf("context label must not be empty".into()));
+    }
+    Ok(())
+}
+
+fn expand_with_context(salt: Option<&[u8]>, ikm: &[u8], context: &str) -> Result<AeadKey, CryptoError> {
+    check_context(context)?;
+    let hk = Hkdf::<Sha256>::new(salt, ikm);
+    let mut out = Zeroizing::new([0u8; 32]);
+    hkdf_expand(&hk, &[CONTEXT_INFO_PREFIX, context.as_bytes()].concat(), out.as_mut_slice())?;
+    AeadKey::from_bytes(out.as_slice())
+}
+
+fn verifier_for(key: &AeadKey) -> Result<String, CryptoError> {
+    let hk = Hkdf::<Sha256>::new(None, key.as_key().as_slice());
+    let mut out = Zeroizing::new([0u8; 32]);
+    hkdf_expand(&hk, VERIFIER_INFO, out.as_mut_slice())?;
+    Ok(b64_encode(out.as_slice()))
+}
@@ -0,0 +1,358 @@
+//! Типизированные ключи, nonce и соль вместо Base64-строк.
+//!
+//! Все типы разбираются из Base64 через `FromStr` (с проверкой длины) и сериализуются serde как
+//! Base64-строка, поэтому JSON-форматы не меняются. `PublicKey`, `SigningPublicKey`, `Nonce` и `Salt`
+//! печатаются через `Display` в Base64. Секретные ключи (`PrivateKey`, `SigningPrivateKey`, `SymmetricKey`)
+//! затираются при drop и скрыты в `Debug`; `Display` у них нет намеренно — Base64 секрета доступен только
+//! явно (`to_secret_b64`, serde).
+
+use std::fmt;
+use std::str::FromStr;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use x25519_dalek::{PublicKey as X25519PublicKey, StaticSecret};
+use zeroize::Zeroizing;
+
+use super::types::{CryptoError, SecretString};
+use super::{b64_decode, b64_encode, ct_eq, fill_random, secret_b64};
+
+/// Минимальная длина соли (требование Argon2).
+pub const MIN_SALT_LEN: usize = 8;
+
+fn to_array<const N: usize>(bytes: &[u8], what: &str) -> Result<[u8; N], CryptoError> {
+    bytes.try_into().map_err(|_| CryptoError::InvalidKeyLen(format!("{what}: {}", bytes.len())))
+}
+
+/// Публичный X25519-ключ (32 байта).
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PublicKey([u8; 32]);
+
+impl PublicKey {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        Ok(PublicKey(to_array(bytes, "public key")?))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<X25519PublicKey> for PublicKey {
+    fn from(pk: X25519PublicKey) -> Self {
+        PublicKey(pk.to_bytes())
+    }
+}
+
+impl From<&PublicKey> for X25519PublicKey {
+    fn from(pk: &PublicKey) -> Self {
+        X25519PublicKey::from(pk.0)
+    }
+}
+
+/// Приватный X25519-ключ (32 байта).
+#[derive(Clone)]
+pub struct PrivateKey(Zeroizing<[u8; 32]>);
+
+impl PrivateKey {
+    /// Новый случайный ключ.
+    pub fn generate() -> Result<Self, CryptoError> {
+        let mut bytes = Zeroizing::new([0u8; 32]);
+        fill_random(bytes.as_mut_slice())?;
+        Ok(PrivateKey(bytes))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        Ok(PrivateKey(Zeroizing::new(to_array(bytes, "private key")?)))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::from(X25519PublicKey::from(&self.to_static_secret()))
+    }
+
+    /// X25519 DH с публичным ключом собеседника.
+    pub fn diffie_hellman(&self, public_key: &PublicKey) -> Zeroizing<[u8; 32]> {
+        Zeroizing::new(self.to_static_secret().diffie_hellman(&public_key.into()).to_bytes())
+    }
+
+    /// Base64 ключа (для хранения и string-обёрток).
+    pub fn to_secret_b64(&self) -> SecretString {
+        secret_b64(self.0.as_slice())
+    }
+
+    pub(crate) fn to_static_secret(&self) -> StaticSecret {
+        StaticSecret::from(*self.0)
+    }
+}
+
+impl From<StaticSecret> for PrivateKey {
+    fn from(sk: StaticSecret) -> Self {
+        PrivateKey(Zeroizing::new(sk.to_bytes()))
+    }
+}
+
+impl PartialEq for PrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        ct_eq(self.0.as_slice(), other.0.as_slice())
+    }
+}
+
+impl Eq for PrivateKey {}
+
+impl fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PrivateKey([REDACTED])")
+    }
+}
+
+/// Публичный Ed25519-ключ подписи (32 байта сжатой точки); при разборе проверяется, что это точка кривой.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SigningPublicKey([u8; 32]);
+
+impl SigningPublicKey {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let bytes = to_array(bytes, "signing public key")?;
+        VerifyingKey::from_bytes(&bytes).map_err(|_| CryptoError::InvalidSignature)?;
+        Ok(SigningPublicKey(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Проверяет подпись Ed25519 (Base64, 64 байта) над `data`.
+    pub fn verify(&self, data: &[u8], signature_b64: &str) -> Result<(), CryptoError> {
+        let sig = Signature::from_slice(&b64_decode(signature_b64)?).map_err(|_| CryptoError::InvalidSignature)?;
+        self.verifying_key().verify(data, &sig).map_err(|_| CryptoError::InvalidSignature)
+    }
+
+    /// Публичный X25519-ключ той же точки (форма Монтгомери).
+    pub fn to_x25519(&self) -> PublicKey {
+        PublicKey(self.verifying_key().to_montgomery().to_bytes())
+    }
+
+    // Байты проверены в `from_bytes`/`SigningPrivateKey::public_key`, поэтому распаковка точки не падает.
+    fn verifying_key(&self) -> VerifyingKey {
+        VerifyingKey::from_bytes(&self.0).expect("validated in constructor")
+    }
+}
+
+/// Приватный Ed25519-ключ подписи (seed, 32 байта).
+#[derive(Clone)]
+pub struct SigningPrivateKey(Zeroizing<[u8; 32]>);
+
+impl SigningPrivateKey {
+    /// Новый случайный ключ.
+    pub fn generate() -> Result<Self, CryptoError> {
+        let mut bytes = Zeroizing::new([0u8; 32]);
+        fill_random(bytes.as_mut_slice())?;
+        Ok(SigningPrivateKey(bytes))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        Ok(SigningPrivateKey(Zeroizing::new(to_array(bytes, "signing private key")?)))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub fn public_key(&self) -> SigningPublicKey {
+        SigningPublicKey(self.to_signing_key().verifying_key().to_bytes())
+    }
+
+    /// Подпись Ed25519 над `data` в Base64 (64 байта).
+    pub fn sign(&self, data: &[u8]) -> String {
+        b64_encode(&self.to_signing_key().sign(data).to_bytes())
+    }
+
+    /// Приватный X25519-ключ, соответствующий `public_key().to_x25519()`.
+    pub fn to_x25519(&self) -> PrivateKey {
+        PrivateKey(Zeroizing::new(self.to_signing_key().to_scalar_bytes()))
+    }
+
+    /// Base64 seed (для хранения и string-обёрток).
+    pub fn to_secret_b64(&self) -> SecretString {
+        secret_b64(self.0.as_slice())
+    }
+
+    fn to_signing_key(&self) -> SigningKey {
+        SigningKey::from_bytes(&self.0)
+    }
+}
+
+impl PartialEq for SigningPrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        ct_eq(self.0.as_slice(), other.0.as_slice())
+    }
+}
+
+impl Eq for SigningPrivateKey {}
+
+impl fmt::Debug for SigningPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SigningPrivateKey([REDACTED])")
+    }
+}
+
+/// Симметричный секрет протоколов (32 байта): корневой ключ и ключи цепочек Double Ratchet, цепочки Sender Keys,
+/// секреты эпохи TreeKEM.
+#[derive(Clone)]
+pub struct SymmetricKey(Zeroizing<[u8; 32]>);
+
+impl SymmetricKey {
+    /// Новый случайный ключ.
+    pub fn generate() -> Result<Self, CryptoError> {
+        let mut bytes = Zeroizing::new([0u8; 32]);
+        fill_random(bytes.as_mut_slice())?;
+        Ok(SymmetricKey(bytes))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        Ok(SymmetricKey(Zeroizing::new(to_array(bytes, "symmetric key")?)))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl From<Zeroizing<[u8; 32]>> for SymmetricKey {
+    fn from(bytes: Zeroizing<[u8; 32]>) -> Self {
+        SymmetricKey(bytes)
+    }
+}
+
+impl PartialEq for SymmetricKey {
+    fn eq(&self, other: &Self) -> bool {
+        ct_eq(self.0.as_slice(), other.0.as_slice())
+    }
+}
+
+impl Eq for SymmetricKey {}
+
+impl fmt::Debug for SymmetricKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SymmetricKey([REDACTED])")
+    }
+}
+
+/// Nonce ChaCha20-Poly1305 (12 байт).
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Nonce([u8; 12]);
+
+impl Nonce {
+    /// Новый случайный nonce.
+    pub fn generate() -> Result<Self, CryptoError> {
+        let mut bytes = [0u8; 12];
+        fill_random(&mut bytes)?;
+        Ok(Nonce(bytes))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let bytes = bytes.try_into().map_err(|_| CryptoError::InvalidNonceLen(bytes.len()))?;
+        Ok(Nonce(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 12] {
+        &self.0
+    }
+}
+
+impl From<Nonce> for chacha20poly1305::Nonce {
+    fn from(nonce: Nonce) -> Self {
+        chacha20poly1305::Nonce::from(nonce.0)
+    }
+}
+
+/// Соль KDF (не короче `MIN_SALT_LEN`; `generate` даёт 16 байт, как `generate_salt`).
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Salt(Vec<u8>);
+
+impl Salt {
+    pub fn generate() -> Result<Self, CryptoError> {
+        let mut bytes = vec![0u8; 16];
+        fill_random(&mut bytes)?;
+        Ok(Salt(bytes))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        if bytes.len() < MIN_SALT_LEN {
+            return Err(CryptoError::InvalidKeyLen(format!("salt: {}", bytes.len())));
+        }
+        Ok(Salt(bytes.to_vec()))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+// Display / Debug / FromStr / serde через Base64 для публичных значений.
+macro_rules! impl_b64_public {
+    ($($ty:ident),*) => {$(
+        impl fmt::Display for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&b64_encode(&self.as_bytes()[..]))
+            }
+        }
+
+        impl fmt::Debug for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, concat!(stringify!($ty), "({})"), self)
+            }
+        }
+
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+    )*};
+}
+
+impl_b64_public!(PublicKey, SigningPublicKey, Nonce, Salt);
+
+macro_rules! impl_from_str_b64 {
+    ($($ty:ident),*) => {$(
+        impl FromStr for $ty {
+            type Err = CryptoError;
+
+            fn from_str(b64: &str) -> Result<Self, Self::Err> {
+                let bytes = Zeroizing::new(b64_decode(b64)?);
+                $ty::from_bytes(&bytes)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let b64 = Zeroizing::new(String::deserialize(deserializer)?);
+                b64.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    )*};
+}
+
+impl_from_str_b64!(PublicKey, PrivateKey, SigningPublicKey, SigningPrivateKey, SymmetricKey, Nonce, Salt);
+
+impl Serialize for PrivateKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.to_secret_b64().expose_secret())
+    }
+}
+
+impl Serialize for SigningPrivateKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.to_secret_b64().expose_secret())
+    }
+}
+
+impl Serialize for SymmetricKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(secret_b64(self.0.as_slice()).expose_secret())
+    }
+}
@@ -0,0 +1,251 @@
+//! Подключение нового устройства к аккаунту по QR-коду.
+//!
+//! 1. Новое устройство (`NewDeviceLink`) генерирует свои ключи (`Device`) и эфемерную пару `generate_key_pair`,
+//!    показывает QR: Base64(version (1) || эфемерный публичный ключ (32)).
+//! 2. Старое устройство (`ExistingDeviceLink::start`) сканирует QR и отправляет `ProvisioningMessage`: ключ
+//!    идентичности аккаунта, список устройств и ключ истории, зашифрованные ключом, обёрнутым для эфемерного ключа
+//!    (`wrap_symmetric_key`); AAD связывает шифртекст с этим эфемерным ключом.
+//! 3. Новое устройство (`install`) проверяет список, добавляет себя, переподписывает его ключом идентичности и
+//!    отвечает `ProvisioningAck`; старое устройство (`complete`) проверяет, что добавлено ровно одно устройство.
+//!
+//! Сообщения — serde-структуры, канал доставки (сервер, локальная сеть) может быть любым.
+
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use super::devices::{Device, DeviceList};
+use super::identity::IdentityKeyPair;
+use super::keys::PublicKey;
+use super::types::{AeadKey, CryptoError, KeyPair, SecretString, WrappedKey};
+use super::{
+    b64_decode, b64_encode, decrypt_message_with_aad, encrypt_message_with_aad, generate_key_pair,
+    generate_message_encryption_key, secret_b64, unwrap_symmetric_key, wrap_symmetric_key,
+};
+
+/// Версия QR-кода и сообщений подключения.
+pub const PROVISIONING_VERSION: u8 = 1;
+const PROVISIONING_CONTEXT: &[u8] = b"ren-sdk-provisioning";
+
+/// Этап подключения на одной из сторон.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvisioningState {
+    /// Новое устройство показывает QR и ждёт `ProvisioningMessage`.
+    AwaitingMessage,
+    /// Старое устройство отправило `ProvisioningMessage` и ждёт `ProvisioningAck`.
+    AwaitingAck,
+    Complete,
+}
+
+/// Сообщение старого устройства новому (serde).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProvisioningMessage {
+    pub version: u8,
+    pub wrapped_key: WrappedKey,
+    pub ciphertext: String,
+    pub nonce: String,
+}
+
+/// Ответ нового устройства: список устройств с ним самим, подписанный ключом идентичности аккаунта (serde).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProvisioningAck {
+    pub version: u8,
+    pub device_list: DeviceList,
+}
+
+// Открытый текст `ProvisioningMessage`; `history_key` — Base64 ключа истории.
+#[derive(Serialize, Deserialize)]
+struct ProvisioningPayload {
+    identity: IdentityKeyPair,
+    device_list: DeviceList,
+    history_key: SecretString,
+}
+
+/// Всё, что новое устройство получило при подключении.
+#[derive(Debug, Clone)]
+pub struct LinkedAccount {
+    pub account_id: String,
+    pub identity: IdentityKeyPair,
+    pub device: Device,
+    pub device_list: DeviceList,
+    pub history_key: AeadKey,
+}
+
+fn unexpected(state: ProvisioningState) -> CryptoError {
+    CryptoError::Provisioning(format!("unexpected message in state {state:?}"))
+}
+
+fn check_version(version: u8) -> Result<(), CryptoError> {
+    if version != PROVISIONING_VERSION {
+        return Err(CryptoError::UnsupportedVersion(version));
+    }
+    Ok(())
+}
+
+fn message_aad(ephemeral_public_key: &PublicKey) -> Vec<u8> {
+    let mut aad = PROVISIONING_CONTEXT.to_vec();
+    aad.push(PROVISIONING_VERSION);
+    aad.extend_from_slice(ephemeral_public_key.as_bytes());
+    aad
+}
+
+/// Разбирает QR-код нового устройства и возвращает его эфемерный ключ.
+pub fn parse_provisioning_qr(payload_b64: &str) -> Result<PublicKey, CryptoError> {
+    let payload = b64_decode(payload_b64)?;
+    let (&version, key) = payload.split_first().ok_or_else(|| CryptoError::Provisioning("empty qr payload".into()))?;
+    check_version(version)?;
+    if key.len() != 32 {
+        return Err(CryptoError::Provisioning(format!("qr key length {}", key.len())));
+    }
+    PublicKey::from_bytes(key)
+}
+
+/// Сторона нового устройства.
+#[derive(Debug)]
+pub struct NewDeviceLink {
+    device: Device,
+    ephemeral: Option<KeyPair>,
+    state: ProvisioningState,
+    account: Option<LinkedAccount>,
+}
+
+impl NewDeviceLink {
+    pub fn new(device_id: &str) -> Result<Self, CryptoError> {
+        Ok(NewDeviceLink {
+            device: Device::generate(device_id)?,
+            ephemeral: Some(generate_key_pair(false)?),
+            state: ProvisioningState::AwaitingMessage,
+            account: None,
+        })
+    }
+
+    pub fn state(&self) -> ProvisioningState {
+        self.state
+    }
+
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Полезная нагрузка QR-кода; после подключения эфемерный ключ уничтожен и QR больше не показывается.
+    pub fn qr_payload(&self) -> Result<String, CryptoError> {
+        let ephemeral = self.ephemeral.as_ref().ok_or_else(|| unexpected(self.state))?;
+        let mut out = Vec::with_capacity(33);
+        out.push(PROVISIONING_VERSION);
+        out.extend_from_slice(ephemeral.public_key.as_bytes());
+        Ok(b64_encode(&out))
+    }
+
+    /// Расшифровывает `ProvisioningMessage`, добавляет себя в список устройств и возвращает ответ старому устройству.
+    pub fn install(&mut self, msg: &ProvisioningMessage) -> Result<ProvisioningAck, CryptoError> {
+        if self.state != ProvisioningState::AwaitingMessage {
+            return Err(unexpected(self.state));
+        }
+        check_version(msg.version)?;
+        let ephemeral = self.ephemeral.as_ref().ok_or_else(|| unexpected(self.state))?;
+        let payload_key = unwrap_symmetric_key(&msg.wrapped_key, &ephemeral.private_key)?;
+        let json = Zeroizing::new(decrypt_message_with_aad(
+            &msg.ciphertext,
+            &msg.nonce,
+            &payload_key,
+            &message_aad(&ephemeral.public_key),
+        )?);
+        let payload: ProvisioningPayload = serde_json::from_str(&json)?;
+
+        let mut device_list = payload.device_list;
+        device_list.verify()?;
+        if device_list.identity_key != payload.identity.public_key {
+            return Err(CryptoError::Provisioning("device list does not belong to the identity".into()));
+        }
+        // повторная проверка подписи отклоняет приватный ключ, не соответствующий `identity_key`
+        device_list.add_device(self.device.info(), &payload.identity)?;
+        device_list.verify()?;
+        let history_key = AeadKey::from_bytes(&Zeroizing::new(b64_decode(payload.history_key.expose_secret())?))?;
+
+        self.ephemeral = None;
+        self.state = ProvisioningState::Complete;
+        self.account = Some(LinkedAccount {
+            account_id: device_list.account_id.clone(),
+            identity: payload.identity,
+            device: self.device.clone(),
+            device_list: device_list.clone(),
+            history_key,
+        });
+        Ok(ProvisioningAck { version: PROVISIONING_VERSION, device_list })
+    }
+
+    /// Данные аккаунта после успешного `install`.
+    pub fn account(&self) -> Option<&LinkedAccount> {
+        self.account.as_ref()
+    }
+
+    pub fn into_account(self) -> Option<LinkedAccount> {
+        self.account
+    }
+}
+
+/// Сторона уже подключённого устройства.
+#[derive(Debug, Clone)]
+pub struct ExistingDeviceLink {
+    device_list: DeviceList,
+    state: ProvisioningState,
+}
+
+impl ExistingDeviceLink {
+    /// Сканирует QR нового устройства и готовит `ProvisioningMessage` с данными аккаунта.
+    pub fn start(
+        qr_payload_b64: &str,
+        identity: &IdentityKeyPair,
+        device_list: &DeviceList,
+        history_key: &AeadKey,
+    ) -> Result<(Self, ProvisioningMessage), CryptoError> {
+        let ephemeral_public_key = parse_provisioning_qr(qr_payload_b64)?;
+        device_list.verify()?;
+        if device_list.identity_key != identity.public_key {
+            return Err(CryptoError::KeyPairMismatch);
+        }
+        let payload = ProvisioningPayload {
+            identity: identity.clone(),
+            device_list: device_list.clone(),
+            history_key: secret_b64(Zeroizing::new(history_key.to_bytes()).as_slice()),
+        };
+        let json = Zeroizing::new(serde_json::to_string(&payload)?);
+        let payload_key = generate_message_encryption_key()?;
+        let enc = encrypt_message_with_aad(&json, &payload_key, &message_aad(&ephemeral_public_key))?;
+        let msg = ProvisioningMessage {
+            version: PROVISIONING_VERSION,
+            wrapped_key: wrap_symmetric_key(&payload_key, &ephemeral_public_key)?,
+            ciphertext: enc.ciphertext,
+            nonce: enc.nonce,
+        };
+        Ok((ExistingDeviceLink { device_list: device_list.clone(), state: ProvisioningState::AwaitingAck }, msg))
+    }
+
+    pub fn state(&self) -> ProvisioningState {
+        self.state
+    }
+
+    /// Проверяет ответ нового устройства: следующая редакция того же списка, в которую добавлено ровно одно
+    /// устройство. Возвращает новый список для публикации.
+    pub fn complete(&mut self, ack: &ProvisioningAck) -> Result<DeviceList, CryptoError> {
+        if self.state != ProvisioningState::AwaitingAck {
+            return Err(unexpected(self.state));
+        }
+        check_version(ack.version)?;
+        let list = &ack.device_list;
+        list.verify()?;
+        let old = &self.device_list;
+        let kept = old.devices.iter().all(|d| list.devices.contains(d));
+        if list.account_id != old.account_id
+            || list.identity_key != old.identity_key
+            || list.generation != old.generation + 1
+            || list.devices.len() != old.devices.len() + 1
+            || !kept
+        {
+            return Err(CryptoError::Provisioning("ack is not the expected device list".into()));
+        }
+        self.device_list = list.clone();
+        self.state = ProvisioningState::Complete;
+        Ok(list.clone())
+    }
+}
@@ -0,0 +1,218 @@
+//! Double Ratchet для чатов 1:1 поверх X25519 + HKDF-SHA256 + ChaCha20-Poly1305.
+//!
+//! Сессия инициализируется общим секретом (обычно `X3dhOutput::shared_secret`):
+//! инициатор использует SPK получателя как первый ratchet-ключ собеседника,
+//! получатель — пару ключей своего SPK. Состояние сериализуемо (serde) для хранения в приложении.
+
+use std::collections::VecDeque;
+
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use super::{aead_open, aead_seal, b64_decode, b64_encode, generate_key_pair, hkdf_expand};
+use super::keys::{Nonce, PublicKey, SymmetricKey};
+use super::types::{AeadKey, CryptoError, KeyPair};
+
+/// Максимум ключей, пропускаемых за один шаг цепочки (защита от DoS большим `message_number`).
+pub const MAX_SKIP: u32 = 1000;
+/// Максимальный размер кэша пропущенных ключей; самые старые вытесняются первыми.
+pub const MAX_SKIPPED_KEYS: usize = 2000;
+
+const ROOT_INFO: &[u8] = b"ren-sdk-ratchet-root";
+const CHAIN_INFO: &[u8] = b"ren-sdk-ratchet-chain";
+const MESSAGE_INFO: &[u8] = b"ren-sdk-ratchet-message";
+
+/// Заголовок сообщения: текущий ratchet-ключ отправителя, длина предыдущей цепочки и номер сообщения.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RatchetHeader {
+    pub dh_public_key: PublicKey,
+    pub previous_chain_length: u32,
+    pub message_number: u32,
+}
+
+/// Конверт сообщения: заголовок (аутентифицирован как AD) + Base64 ciphertext и nonce.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RatchetMessage {
+    pub header: RatchetHeader,
+    pub ciphertext: String,
+    pub nonce: Nonce,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct SkippedMessageKey {
+    dh_public_key: PublicKey,
+    message_number: u32,
+    message_key: SymmetricKey,
+}
+
+/// Состояние Double Ratchet (в serde все ключи — Base64 RAW 32 байта); секреты хранятся в `SymmetricKey`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RatchetSession {
+    dh_self: KeyPair,
+    dh_remote: Option<PublicKey>,
+    root_key: SymmetricKey,
+    sending_chain_key: Option<SymmetricKey>,
+    receiving_chain_key: Option<SymmetricKey>,
+    send_count: u32,
+    recv_count: u32,
+    previous_send_count: u32,
+    skipped: VecDeque<SkippedMessageKey>,
+    associated_data: String,
+}
+
+impl RatchetSession {
+    /// Сессия инициатора: сразу выполняет DH-шаг с ratchet-ключом собеседника и может отправлять.
+    pub fn init_initiator(shared_secret: &AeadKey, remote_ratchet_key: &PublicKey, associated_data: &[u8]) -> Result<Self, CryptoError> {
+        let dh_self = generate_key_pair(false)?;
+        let dh_out = dh_self.private_key.diffie_hellman(remote_ratchet_key);
+        let (root_key, sending_chain_key) = kdf_root(shared_secret.as_key().as_ref(), &dh_out)?;
+        Ok(RatchetSession {
+            dh_self,
+            dh_remote: Some(*remote_ratchet_key),
+            root_key,
+            sending_chain_key: Some(sending_chain_key),
+            receiving_chain_key: None,
+            send_count: 0,
+            recv_count: 0,
+            previous_send_count: 0,
+            skipped: VecDeque::new(),
+            associated_data: b64_encode(associated_data),
+        })
+    }
+
+    /// Сессия получателя: отправка возможна после первого входящего сообщения.
+    pub fn init_responder(shared_secret: &AeadKey, own_ratchet_key: KeyPair, associated_data: &[u8]) -> Self {
+        RatchetSession {
+            dh_self: own_ratchet_key,
+            dh_remote: None,
+            root_key: SymmetricKey::from(Zeroizing::new(shared_secret.to_bytes())),
+            sending_chain_key: None,
+            receiving_chain_key: None,
+            send_count: 0,
+            recv_count: 0,
+            previous_send_count: 0,
+            skipped: VecDeque::new(),
+            associated_data: b64_encode(associated_data),
+        }
+    }
+
+    /// Шифрует сообщение следующим ключом отправляющей цепочки.
+    pub fn encrypt(&mut self, plaintext: &str) -> Result<RatchetMessage, CryptoError> {
+        let ck = self.sending_chain_key.as_ref().ok_or(CryptoError::RatchetNotReady)?;
+        let (next_ck, mk) = kdf_chain(ck)?;
+        let header = RatchetHeader {
+            dh_public_key: self.dh_self.public_key,
+            previous_chain_length: self.previous_send_count,
+            message_number: self.send_count,
+        };
+        let aad = self.header_aad(&header)?;
+        let nonce = Nonce::generate()?;
+        let ciphertext = aead_seal(&AeadKey::from_bytes(mk.as_bytes())?, &nonce, plaintext.as_bytes(), &aad)?;
+        self.sending_chain_key = Some(next_ck);
+        self.send_count += 1;
+        Ok(RatchetMessage { header, ciphertext: b64_encode(&ciphertext), nonce })
+    }
+
+    /// Дешифрует входящее сообщение; состояние меняется только при успешной аутентификации.
+    pub fn decrypt(&mut self, msg: &RatchetMessage) -> Result<String, CryptoError> {
+        let mut next = self.clone();
+        let pt = next.decrypt_in_place(msg)?;
+        *self = next;
+        Ok(String::from_utf8(pt)?)
+    }
+
+    /// Количество ключей в кэше пропущенных сообщений.
+    pub fn skipped_keys_len(&self) -> usize {
+        self.skipped.len()
+    }
+
+    fn decrypt_in_place(&mut self, msg: &RatchetMessage) -> Result<Vec<u8>, CryptoError> {
+        let aad = self.header_aad(&msg.header)?;
+        let ct = b64_decode(&msg.ciphertext)?;
+        let h = &msg.header;
+        let pos = self
+            .skipped
+            .iter()
+            .position(|k| k.dh_public_key == h.dh_public_key && k.message_number == h.message_number);
+        if let Some(entry) = pos.and_then(|pos| self.skipped.remove(pos)) {
+            return aead_open(&AeadKey::from_bytes(entry.message_key.as_bytes())?, &msg.nonce, &ct, &aad);
+        }
+        if self.dh_remote != Some(h.dh_public_key) {
+            self.skip_message_keys(h.previous_chain_length)?;
+            self.dh_ratchet(&h.dh_public_key)?;
+        }
+        self.skip_message_keys(h.message_number)?;
+        let ck = self.receiving_chain_key.as_ref().ok_or(CryptoError::RatchetNotReady)?;
+        let (next_ck, mk) = kdf_chain(ck)?;
+        self.receiving_chain_key = Some(next_ck);
+        self.recv_count += 1;
+        aead_open(&AeadKey::from_bytes(mk.as_bytes())?, &msg.nonce, &ct, &aad)
+    }
+
+    fn skip_message_keys(&mut self, until: u32) -> Result<(), CryptoError> {
+        let (Some(mut ck), Some(remote)) = (self.receiving_chain_key.clone(), self.dh_remote) else { return Ok(()) };
+        if until > self.recv_count.saturating_add(MAX_SKIP) {
+            return Err(CryptoError::TooManySkippedMessages);
+        }
+        while self.recv_count < until {
+            let (next_ck, mk) = kdf_chain(&ck)?;
+            self.skipped.push_back(SkippedMessageKey {
+                dh_public_key: remote,
+                message_number: self.recv_count,
+                message_key: mk,
+            });
+            if self.skipped.len() > MAX_SKIPPED_KEYS {
+                self.skipped.pop_front();
+            }
+            ck = next_ck;
+            self.recv_count += 1;
+        }
+        self.receiving_chain_key = Some(ck);
+        Ok(())
+    }
+
+    fn dh_ratchet(&mut self, remote: &PublicKey) -> Result<(), CryptoError> {
+        self.previous_send_count = self.send_count;
+        self.send_count = 0;
+        self.recv_count = 0;
+        self.dh_remote = Some(*remote);
+        let dh_out = self.dh_self.private_key.diffie_hellman(remote);
+        let (root, recv_ck) = kdf_root(self.root_key.as_bytes(), &dh_out)?;
+        self.dh_self = generate_key_pair(false)?;
+        let dh_out = self.dh_self.private_key.diffie_hellman(remote);
+        let (root, send_ck) = kdf_root(root.as_bytes(), &dh_out)?;
+        self.root_key = root;
+        self.receiving_chain_key = Some(recv_ck);
+        self.sending_chain_key = Some(send_ck);
+        Ok(())
+    }
+
+    // AD сессии || dh_public_key(32) || previous_chain_length(BE) || message_number(BE)
+    fn header_aad(&self, header: &RatchetHeader) -> Result<Vec<u8>, CryptoError> {
+        let mut aad = b64_decode(&self.associated_data)?;
+        aad.extend_from_slice(header.dh_public_key.as_bytes());
+        aad.extend_from_slice(&header.previous_chain_length.to_be_bytes());
+        aad.extend_from_slice(&header.message_number.to_be_bytes());
+        Ok(aad)
+    }
+}
+
+// KDF_RK: HKDF(salt = root_key, IKM = dh_out) -> (root_key', chain_key)
+fn kdf_root(root_key: &[u8; 32], dh_out: &[u8; 32]) -> Result<(SymmetricKey, SymmetricKey), CryptoError> {
+    let hk = Hkdf::<Sha256>::new(Some(root_key), dh_out);
+    let mut okm = Zeroizing::new([0u8; 64]);
+    hkdf_expand(&hk, ROOT_INFO, okm.as_mut_slice())?;
+    Ok((SymmetricKey::from_bytes(&okm[..32])?, SymmetricKey::from_bytes(&okm[32..])?))
+}
+
+// KDF_CK: chain_key используется как PRK HKDF -> (chain_key', message_key)
+fn kdf_chain(chain_key: &SymmetricKey) -> Result<(SymmetricKey, SymmetricKey), CryptoError> {
+    let hk = Hkdf::<Sha256>::from_prk(chain_key.as_bytes()).map_err(|_| CryptoError::Kdf("hkdf prk length".into()))?;
+    let mut ck = Zeroizing::new([0u8; 32]);
+    let mut mk = Zeroizing::new([0u8; 32]);
+    hkdf_expand(&hk, CHAIN_INFO, ck.as_mut_slice())?;
+    hkdf_expand(&hk, MESSAGE_INFO, mk.as_mut_slice())?;
+    Ok((SymmetricKey::from(ck), SymmetricKey::from(mk)))
+}
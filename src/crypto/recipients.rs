@@ -0,0 +1,130 @@
+//! Обёртка одного симметричного ключа сразу для нескольких получателей (вложения в группах).
+//!
+//! `wrap_for_recipients` оборачивает ключ для каждого получателя отдельным эфемерным ключом — каждая запись
+//! совпадает с результатом `wrap_symmetric_key`. `wrap_for_recipients_shared` использует один эфемерный ключ на
+//! всех: ключ обёртки — HKDF(DH(eph, R), info = метка || eph || R || id получателя), поэтому записи разных
+//! получателей не взаимозаменяемы. Получатель находит свою запись по id через `unwrap_for_recipient`.
+
+use std::collections::BTreeMap;
+
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use super::keys::{Nonce, PrivateKey, PublicKey};
+use super::types::{AeadKey, CryptoError, WRAPPED_KEY_VERSION, WrappedKey};
+use super::{aead_open, aead_seal, b64_decode, b64_encode, hkdf_expand, unwrap_symmetric_key, wrap_symmetric_key};
+
+/// Текущая версия формата `RecipientKeys`.
+pub const RECIPIENT_KEYS_VERSION: u8 = 1;
+const SHARED_WRAP_INFO: &[u8] = b"ren-sdk-wrap-multi";
+
+/// Ключ, обёрнутый для одного получателя. `ephemeral_public_key` отсутствует, если эфемерный ключ общий.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecipientKey {
+    pub wrapped_key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ephemeral_public_key: Option<PublicKey>,
+    pub nonce: Nonce,
+}
+
+/// Обёрнутый ключ для всех получателей: id получателя → запись (serde).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RecipientKeys {
+    pub version: u8,
+    /// Общий эфемерный ключ (только для `wrap_for_recipients_shared`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ephemeral_public_key: Option<PublicKey>,
+    pub recipients: BTreeMap<String, RecipientKey>,
+}
+
+/// Получатель ключа: идентификатор (ключ в `RecipientKeys::recipients`) и публичный X25519-ключ.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Recipient {
+    pub id: String,
+    pub public_key: PublicKey,
+}
+
+fn shared_wrap_key(
+    private_key: &PrivateKey,
+    public_key: &PublicKey,
+    eph_pk: &PublicKey,
+    recipient_pk: &PublicKey,
+    recipient_id: &str,
+) -> Result<AeadKey, CryptoError> {
+    let shared = private_key.diffie_hellman(public_key);
+    let mut info = SHARED_WRAP_INFO.to_vec();
+    info.extend_from_slice(eph_pk.as_bytes());
+    info.extend_from_slice(recipient_pk.as_bytes());
+    info.extend_from_slice(recipient_id.as_bytes());
+    let hk = Hkdf::<Sha256>::new(None, shared.as_slice());
+    let mut wrap_key_bytes = Zeroizing::new([0u8; 32]);
+    hkdf_expand(&hk, &info, wrap_key_bytes.as_mut_slice())?;
+    AeadKey::from_bytes(wrap_key_bytes.as_slice())
+}
+
+fn check_unique(recipients: &[Recipient]) -> Result<(), CryptoError> {
+    for (i, r) in recipients.iter().enumerate() {
+        if recipients[..i].iter().any(|other| other.id == r.id) {
+            return Err(CryptoError::Recipients(format!("duplicate recipient {}", r.id)));
+        }
+    }
+    Ok(())
+}
+
+/// Оборачивает `key` для каждого получателя отдельным эфемерным ключом (как `wrap_symmetric_key`).
+pub fn wrap_for_recipients(key: &AeadKey, recipients: &[Recipient]) -> Result<RecipientKeys, CryptoError> {
+    check_unique(recipients)?;
+    let mut out = BTreeMap::new();
+    for r in recipients {
+        let WrappedKey { wrapped_key, ephemeral_public_key, nonce, .. } = wrap_symmetric_key(key, &r.public_key)?;
+        out.insert(r.id.clone(), RecipientKey { wrapped_key, ephemeral_public_key: Some(ephemeral_public_key), nonce });
+    }
+    Ok(RecipientKeys { version: RECIPIENT_KEYS_VERSION, ephemeral_public_key: None, recipients: out })
+}
+
+/// Оборачивает `key` для всех получателей с одним эфемерным ключом: меньше данных и DH-генераций у отправителя.
+pub fn wrap_for_recipients_shared(key: &AeadKey, recipients: &[Recipient]) -> Result<RecipientKeys, CryptoError> {
+    check_unique(recipients)?;
+    let eph_sk = PrivateKey::generate()?;
+    let eph_pk = eph_sk.public_key();
+    let plaintext = Zeroizing::new(key.to_bytes());
+    let mut out = BTreeMap::new();
+    for r in recipients {
+        let wrap_key = shared_wrap_key(&eph_sk, &r.public_key, &eph_pk, &r.public_key, &r.id)?;
+        let nonce = Nonce::generate()?;
+        let ct = aead_seal(&wrap_key, &nonce, plaintext.as_slice(), &[])?;
+        out.insert(r.id.clone(), RecipientKey { wrapped_key: b64_encode(&ct), ephemeral_public_key: None, nonce });
+    }
+    Ok(RecipientKeys { version: RECIPIENT_KEYS_VERSION, ephemeral_public_key: Some(eph_pk), recipients: out })
+}
+
+/// Находит запись `own_id` и разворачивает ключ своим приватным ключом.
+pub fn unwrap_for_recipient(keys: &RecipientKeys, own_id: &str, own_private_key: &PrivateKey) -> Result<AeadKey, CryptoError> {
+    if keys.version != RECIPIENT_KEYS_VERSION {
+        return Err(CryptoError::UnsupportedVersion(keys.version));
+    }
+    let entry = keys
+        .recipients
+        .get(own_id)
+        .ok_or_else(|| CryptoError::Recipients(format!("no entry for {own_id}")))?;
+    match (&keys.ephemeral_public_key, &entry.ephemeral_public_key) {
+        (None, Some(eph_pk)) => {
+            let wrapped = WrappedKey {
+                version: WRAPPED_KEY_VERSION,
+                wrapped_key: entry.wrapped_key.clone(),
+                ephemeral_public_key: *eph_pk,
+                nonce: entry.nonce,
+            };
+            unwrap_symmetric_key(&wrapped, own_private_key)
+        }
+        (Some(eph_pk), None) => {
+            let own_pk = own_private_key.public_key();
+            let wrap_key = shared_wrap_key(own_private_key, eph_pk, eph_pk, &own_pk, own_id)?;
+            let pt = Zeroizing::new(aead_open(&wrap_key, &entry.nonce, &b64_decode(&entry.wrapped_key)?, &[])?);
+            AeadKey::from_bytes(&pt)
+        }
+        _ => Err(CryptoError::Recipients("exactly one ephemeral key per entry expected".into())),
+    }
+}
@@ -0,0 +1,98 @@
+//! Источник случайности для модуля crypto.
+//!
+//! Все ключи, nonce и соли берут байты через один хук. По умолчанию это `OsRng` (getrandom). С фичей
+//! `test-rng` источник можно подменить на время замыкания через `with_rng` — например, на детерминированный
+//! `SeededRng` для known-answer тестов и воспроизведения ошибок. Без фичи подменить источник нельзя. Подмена действует только в текущем потоке
+//! (async-задача, перешедшая на другой поток, её не видит) и снимается по выходе из замыкания, в том числе
+//! при панике.
+
+#[cfg(feature = "test-rng")]
+use std::cell::RefCell;
+
+use super::types::CryptoError;
+
+/// Криптографически стойкий источник случайных байт.
+pub trait CryptoRng {
+    fn fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), CryptoError>;
+}
+
+/// Генератор ОС (getrandom) — источник по умолчанию.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OsRng;
+
+impl CryptoRng for OsRng {
+    fn fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        getrandom::getrandom(dest)?;
+        Ok(())
+    }
+}
+
+/// Детерминированный ChaCha20 с заданным seed. Только для тестов: одинаковый seed даёт одинаковые ключи.
+#[cfg(feature = "test-rng")]
+pub struct SeededRng(rand_chacha::ChaCha20Rng);
+
+#[cfg(feature = "test-rng")]
+impl SeededRng {
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        use rand_chacha::rand_core::SeedableRng;
+        SeededRng(rand_chacha::ChaCha20Rng::from_seed(seed))
+    }
+
+    /// Seed из числа: младшие 8 байт seed — `n` в little-endian, остальные нули.
+    pub fn from_u64(n: u64) -> Self {
+        let mut seed = [0u8; 32];
+        seed[..8].copy_from_slice(&n.to_le_bytes());
+        Self::from_seed(seed)
+    }
+}
+
+#[cfg(feature = "test-rng")]
+impl CryptoRng for SeededRng {
+    fn fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+        use rand_chacha::rand_core::RngCore;
+        self.0.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "test-rng")]
+thread_local! {
+    static RNG: RefCell<Option<Box<dyn CryptoRng>>> = const { RefCell::new(None) };
+}
+
+// Возвращает источник в слот при выходе из `with_rng` и из `fill_bytes`, в том числе при панике.
+#[cfg(feature = "test-rng")]
+struct Restore(Option<Box<dyn CryptoRng>>);
+
+#[cfg(feature = "test-rng")]
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let prev = self.0.take();
+        RNG.with(|cell| *cell.borrow_mut() = prev);
+    }
+}
+
+/// Выполняет `f`, беря всю случайность crypto в текущем потоке из `rng`. Вызовы можно вкладывать.
+/// Только для тестов (фича `test-rng`).
+#[cfg(feature = "test-rng")]
+pub fn with_rng<T>(rng: impl CryptoRng + 'static, f: impl FnOnce() -> T) -> T {
+    let prev = RNG.with(|cell| cell.borrow_mut().replace(Box::new(rng)));
+    let _restore = Restore(prev);
+    f()
+}
+
+// Источник вынимается на время вызова: если он сам обратится к crypto, вложенный вызов уйдёт в `OsRng`.
+#[cfg(feature = "test-rng")]
+pub(crate) fn fill_bytes(dest: &mut [u8]) -> Result<(), CryptoError> {
+    let Some(rng) = RNG.with(|cell| cell.borrow_mut().take()) else { return OsRng.fill_bytes(dest) };
+    let mut slot = Restore(Some(rng));
+    match slot.0.as_mut() {
+        Some(rng) => rng.fill_bytes(dest),
+        None => OsRng.fill_bytes(dest),
+    }
+}
+
+#[cfg(not(feature = "test-rng"))]
+pub(crate) fn fill_bytes(dest: &mut [u8]) -> Result<(), CryptoError> {
+    OsRng.fill_bytes(dest)
+}
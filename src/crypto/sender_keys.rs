@@ -0,0 +1,309 @@
+//! Sender Keys для групповых чатов: каждое сообщение шифруется один раз ключом из цепочки отправителя.
+//!
+//! Каждый участник генерирует цепочку (chain key) и ключ подписи Ed25519 и рассылает их всем участникам:
+//! полезная нагрузка шифруется ключом сообщения, который оборачивается для каждого получателя через
+//! `wrap_symmetric_key_authenticated` (получатель проверяет, что рассылку сделал именно этот участник).
+//! При удалении участника его цепочка забывается, а собственная цепочка перегенерируется и рассылается заново.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use super::identity::verify_signature;
+use super::ratchet::{MAX_SKIP, MAX_SKIPPED_KEYS};
+use super::keys::{Nonce, PrivateKey, PublicKey, SigningPrivateKey, SigningPublicKey, SymmetricKey};
+use super::recipients::Recipient;
+use super::types::{AeadKey, CryptoError, WrappedKey};
+use super::{
+    aead_open, aead_seal, b64_decode, b64_encode, decrypt_message, hkdf_expand, encrypt_message, generate_message_encryption_key,
+    unwrap_symmetric_key_authenticated, wrap_symmetric_key_authenticated,
+};
+
+const CHAIN_INFO: &[u8] = b"ren-sdk-sender-key-chain";
+const MESSAGE_INFO: &[u8] = b"ren-sdk-sender-key-message";
+
+/// Состояние цепочки одного отправителя. У собственной цепочки есть приватный ключ подписи.
+/// Ключ цепочки и пропущенные ключи сообщений — `SymmetricKey` (в serde Base64).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SenderKeyState {
+    pub key_id: u32,
+    pub iteration: u32,
+    pub chain_key: SymmetricKey,
+    pub signing_public_key: SigningPublicKey,
+    pub signing_private_key: Option<SigningPrivateKey>,
+    skipped: VecDeque<(u32, SymmetricKey)>,
+}
+
+/// Рассылка цепочки отправителя одному получателю.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SenderKeyDistribution {
+    pub group_id: String,
+    pub sender_id: String,
+    pub recipient_id: String,
+    pub wrapped_key: WrappedKey,
+    pub ciphertext: String,
+    pub nonce: Nonce,
+}
+
+/// Групповое сообщение: зашифровано ключом итерации цепочки и подписано ключом подписи отправителя.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SenderKeyMessage {
+    pub group_id: String,
+    pub sender_id: String,
+    pub key_id: u32,
+    pub iteration: u32,
+    pub ciphertext: String,
+    pub nonce: Nonce,
+    pub signature: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SenderKeyPayload {
+    group_id: String,
+    sender_id: String,
+    key_id: u32,
+    iteration: u32,
+    chain_key: SymmetricKey,
+    signing_public_key: SigningPublicKey,
+}
+
+/// Групповая сессия участника: список участников, своя цепочка и цепочки остальных (serde).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GroupSession {
+    pub group_id: String,
+    pub own_id: String,
+    members: BTreeMap<String, PublicKey>,
+    own: SenderKeyState,
+    received: BTreeMap<String, SenderKeyState>,
+}
+
+impl SenderKeyState {
+    fn generate(key_id: u32) -> Result<Self, CryptoError> {
+        let signing = SigningPrivateKey::generate()?;
+        Ok(SenderKeyState {
+            key_id,
+            iteration: 0,
+            chain_key: SymmetricKey::generate()?,
+            signing_public_key: signing.public_key(),
+            signing_private_key: Some(signing),
+            skipped: VecDeque::new(),
+        })
+    }
+
+    // Продвигает цепочку до `iteration` и возвращает ключ сообщения этой итерации.
+    fn message_key(&mut self, iteration: u32) -> Result<SymmetricKey, CryptoError> {
+        if iteration < self.iteration {
+            let pos = self.skipped.iter().position(|(i, _)| *i == iteration);
+            let (_, mk) = pos.and_then(|pos| self.skipped.remove(pos)).ok_or(CryptoError::AuthenticationFailed)?;
+            return Ok(mk);
+        }
+        if iteration > self.iteration.saturating_add(MAX_SKIP) {
+            return Err(CryptoError::TooManySkippedMessages);
+        }
+        loop {
+            let (next_ck, mk) = kdf_chain(&self.chain_key)?;
+            self.chain_key = next_ck;
+            let current = self.iteration;
+            self.iteration += 1;
+            if current == iteration {
+                return Ok(mk);
+            }
+            self.skipped.push_back((current, mk));
+            if self.skipped.len() > MAX_SKIPPED_KEYS {
+                self.skipped.pop_front();
+            }
+        }
+    }
+}
+
+impl GroupSession {
+    /// Создаёт сессию участника `own_id` с собственной новой цепочкой.
+    pub fn new(group_id: &str, own_id: &str, members: &[Recipient]) -> Result<Self, CryptoError> {
+        Ok(GroupSession {
+            group_id: group_id.to_string(),
+            own_id: own_id.to_string(),
+            members: members.iter().map(|m| (m.id.clone(), m.public_key)).collect(),
+            own: SenderKeyState::generate(0)?,
+            received: BTreeMap::new(),
+        })
+    }
+
+    /// Идентификаторы текущих участников группы.
+    pub fn member_ids(&self) -> Vec<String> {
+        self.members.keys().cloned().collect()
+    }
+
+    /// Текущий идентификатор собственной цепочки (растёт при каждой ротации).
+    pub fn own_key_id(&self) -> u32 {
+        self.own.key_id
+    }
+
+    /// Рассылка собственной цепочки (с текущей итерации) одному участнику.
+    pub fn create_distribution(&self, recipient_id: &str, own_private_key: &PrivateKey) -> Result<SenderKeyDistribution, CryptoError> {
+        let recipient_pk = self
+            .members
+            .get(recipient_id)
+            .ok_or_else(|| CryptoError::UnknownSenderKey(recipient_id.to_string()))?;
+        let payload = SenderKeyPayload {
+            group_id: self.group_id.clone(),
+            sender_id: self.own_id.clone(),
+            key_id: self.own.key_id,
+            iteration: self.own.iteration,
+            chain_key: self.own.chain_key.clone(),
+            signing_public_key: self.own.signing_public_key,
+        };
+        let json = serde_json::to_string(&payload)?;
+        let payload_key = generate_message_encryption_key()?;
+        let enc = encrypt_message(&json, &payload_key)?;
+        let wrapped_key = wrap_symmetric_key_authenticated(&payload_key, own_private_key, recipient_pk)?;
+        Ok(SenderKeyDistribution {
+            group_id: self.group_id.clone(),
+            sender_id: self.own_id.clone(),
+            recipient_id: recipient_id.to_string(),
+            wrapped_key,
+            ciphertext: enc.ciphertext,
+            nonce: enc.nonce.parse()?,
+        })
+    }
+
+    /// Рассылки собственной цепочки всем остальным участникам.
+    pub fn distribute(&self, own_private_key: &PrivateKey) -> Result<Vec<SenderKeyDistribution>, CryptoError> {
+        self.members
+            .keys()
+            .filter(|id| **id != self.own_id)
+            .map(|id| self.create_distribution(id, own_private_key))
+            .collect()
+    }
+
+    /// Принимает рассылку цепочки от участника группы (отправитель проверяется по его X25519-ключу).
+    /// Рассылка с меньшим `key_id` или, при том же `key_id`, с меньшей `iteration`, чем у принятой цепочки,
+    /// отклоняется (`StaleSenderKey`): повтор старой рассылки не откатывает цепочку.
+    pub fn process_distribution(&mut self, dist: &SenderKeyDistribution, own_private_key: &PrivateKey) -> Result<(), CryptoError> {
+        if dist.group_id != self.group_id || dist.recipient_id != self.own_id {
+            return Err(CryptoError::UnknownSenderKey(dist.sender_id.clone()));
+        }
+        let sender_pk = self
+            .members
+            .get(&dist.sender_id)
+            .ok_or_else(|| CryptoError::UnknownSenderKey(dist.sender_id.clone()))?;
+        let payload_key = unwrap_symmetric_key_authenticated(&dist.wrapped_key, own_private_key, sender_pk)?;
+        let json = Zeroizing::new(decrypt_message(&dist.ciphertext, &dist.nonce.to_string(), &payload_key)?);
+        let payload: SenderKeyPayload = serde_json::from_str(&json)?;
+        if payload.group_id != dist.group_id || payload.sender_id != dist.sender_id {
+            return Err(CryptoError::UnknownSenderKey(dist.sender_id.clone()));
+        }
+        if let Some(current) = self.received.get(&payload.sender_id) {
+            let stale = payload.key_id < current.key_id
+                || (payload.key_id == current.key_id && payload.iteration < current.iteration);
+            if stale {
+                return Err(CryptoError::StaleSenderKey(format!(
+                    "{}: key {} iteration {} is older than key {} iteration {}",
+                    payload.sender_id, payload.key_id, payload.iteration, current.key_id, current.iteration
+                )));
+            }
+        }
+        self.received.insert(
+            payload.sender_id,
+            SenderKeyState {
+                key_id: payload.key_id,
+                iteration: payload.iteration,
+                chain_key: payload.chain_key,
+                signing_public_key: payload.signing_public_key,
+                signing_private_key: None,
+                skipped: VecDeque::new(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Шифрует сообщение для всей группы один раз и подписывает его.
+    pub fn encrypt(&mut self, plaintext: &str) -> Result<SenderKeyMessage, CryptoError> {
+        let signing = self.own.signing_private_key.clone().ok_or(CryptoError::InvalidSignature)?;
+        let iteration = self.own.iteration;
+        let mk = self.own.message_key(iteration)?;
+        let mut msg = SenderKeyMessage {
+            group_id: self.group_id.clone(),
+            sender_id: self.own_id.clone(),
+            key_id: self.own.key_id,
+            iteration,
+            ciphertext: String::new(),
+            nonce: Nonce::generate()?,
+            signature: String::new(),
+        };
+        let ct = aead_seal(&AeadKey::from_bytes(mk.as_bytes())?, &msg.nonce, plaintext.as_bytes(), &message_aad(&msg))?;
+        msg.ciphertext = b64_encode(&ct);
+        msg.signature = signing.sign(&signed_data(&msg));
+        Ok(msg)
+    }
+
+    /// Проверяет подпись и дешифрует сообщение участника; состояние меняется только при успехе.
+    pub fn decrypt(&mut self, msg: &SenderKeyMessage) -> Result<String, CryptoError> {
+        if msg.group_id != self.group_id {
+            return Err(CryptoError::UnknownSenderKey(msg.sender_id.clone()));
+        }
+        let state = self
+            .received
+            .get(&msg.sender_id)
+            .filter(|s| s.key_id == msg.key_id)
+            .ok_or_else(|| CryptoError::UnknownSenderKey(msg.sender_id.clone()))?;
+        verify_signature(&signed_data(msg), &msg.signature, &state.signing_public_key)?;
+        let mut state = state.clone();
+        let mk = state.message_key(msg.iteration)?;
+        let pt = aead_open(&AeadKey::from_bytes(mk.as_bytes())?, &msg.nonce, &b64_decode(&msg.ciphertext)?, &message_aad(msg))?;
+        let s = String::from_utf8(pt)?;
+        self.received.insert(msg.sender_id.clone(), state);
+        Ok(s)
+    }
+
+    /// Добавляет участника и возвращает рассылку текущей цепочки для него
+    /// (прошлые сообщения новому участнику недоступны).
+    pub fn add_member(&mut self, member: Recipient, own_private_key: &PrivateKey) -> Result<SenderKeyDistribution, CryptoError> {
+        let id = member.id.clone();
+        self.members.insert(member.id, member.public_key);
+        self.create_distribution(&id, own_private_key)
+    }
+
+    /// Удаляет участника: забывает его цепочку, перегенерирует собственную и возвращает рассылки
+    /// оставшимся участникам. Каждый оставшийся участник должен выполнить то же самое у себя.
+    /// `key_id` не переполняется: при `u32::MAX` возвращается `SenderKeyIdExhausted`, сессия не меняется.
+    pub fn remove_member(&mut self, member_id: &str, own_private_key: &PrivateKey) -> Result<Vec<SenderKeyDistribution>, CryptoError> {
+        let key_id = self.own.key_id.checked_add(1).ok_or_else(|| CryptoError::SenderKeyIdExhausted(self.own_id.clone()))?;
+        let own = SenderKeyState::generate(key_id)?;
+        self.members.remove(member_id);
+        self.received.remove(member_id);
+        self.own = own;
+        self.distribute(own_private_key)
+    }
+}
+
+fn kdf_chain(chain_key: &SymmetricKey) -> Result<(SymmetricKey, SymmetricKey), CryptoError> {
+    let hk = Hkdf::<Sha256>::from_prk(chain_key.as_bytes()).map_err(|_| CryptoError::Kdf("hkdf prk length".into()))?;
+    let mut ck = Zeroizing::new([0u8; 32]);
+    let mut mk = Zeroizing::new([0u8; 32]);
+    hkdf_expand(&hk, CHAIN_INFO, ck.as_mut_slice())?;
+    hkdf_expand(&hk, MESSAGE_INFO, mk.as_mut_slice())?;
+    Ok((SymmetricKey::from(ck), SymmetricKey::from(mk)))
+}
+
+// AAD = len(group_id) || group_id || len(sender_id) || sender_id || key_id || iteration
+fn message_aad(msg: &SenderKeyMessage) -> Vec<u8> {
+    let mut aad = Vec::new();
+    for part in [msg.group_id.as_bytes(), msg.sender_id.as_bytes()] {
+        aad.extend_from_slice(&(part.len() as u32).to_be_bytes());
+        aad.extend_from_slice(part);
+    }
+    aad.extend_from_slice(&msg.key_id.to_be_bytes());
+    aad.extend_from_slice(&msg.iteration.to_be_bytes());
+    aad
+}
+
+fn signed_data(msg: &SenderKeyMessage) -> Vec<u8> {
+    let mut data = message_aad(msg);
+    // nonce и шифртекст входят в подпись в Base64, как они передаются
+    data.extend_from_slice(msg.nonce.to_string().as_bytes());
+    data.extend_from_slice(msg.ciphertext.as_bytes());
+    data
+}
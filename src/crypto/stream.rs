@@ -0,0 +1,459 @@
+//! Потоковое (чанковое) шифрование больших вложений по схеме STREAM.
+//!
+//! Открытый текст режется на чанки по `chunk_size` байт, каждый шифруется ChaCha20-Poly1305 отдельно:
+//!
+//! ```text
+//! nonce(i) = prefix (7) | i как u32 big-endian (4) | last flag (1: 0x01 у последнего чанка, иначе 0x00)
+//! ciphertext = chunk_0 || tag_0 || chunk_1 || tag_1 || ... || chunk_n || tag_n
+//! ```
+//!
+//! Номер чанка в nonce не даёт переставить чанки, а флаг последнего чанка — незаметно обрезать поток
+//! по границе чанка. Ключ шифра — подключ HKDF(salt = prefix, IKM = key), поэтому один ключ вложения
+//! можно использовать для нескольких потоков. Метаданные (`EncryptedStream`) передаются отдельно,
+//! как у `EncryptedFile`.
+
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit, Nonce};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use super::types::{AeadKey, CryptoError};
+use super::{b64_decode, b64_encode, fill_random, hkdf_expand};
+
+/// Размер чанка открытого текста по умолчанию (64 КиБ).
+pub const DEFAULT_CHUNK_SIZE: u32 = 64 * 1024;
+/// Максимальный размер чанка (16 МиБ): `chunk_size` приходит из метаданных, а буферы выделяются под него.
+pub const MAX_CHUNK_SIZE: u32 = 16 * 1024 * 1024;
+/// Длина тега Poly1305, добавляемого к каждому чанку.
+pub const STREAM_TAG_LEN: usize = 16;
+const NONCE_PREFIX_LEN: usize = 7;
+const STREAM_KEY_INFO: &[u8] = b"ren-sdk-stream";
+
+/// Метаданные потокового шифртекста; сам шифртекст пишется в `Write` отдельно.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedStream {
+    /// Base64 7-байтного префикса nonce.
+    pub nonce_prefix: String,
+    pub chunk_size: u32,
+    pub chunk_count: u32,
+    pub plaintext_len: u64,
+    pub filename: String,
+    pub mimetype: String,
+}
+
+impl EncryptedStream {
+    /// Ожидаемая длина шифртекста: открытый текст плюс тег на каждый чанк.
+    pub fn ciphertext_len(&self) -> u64 {
+        self.plaintext_len + self.chunk_count as u64 * STREAM_TAG_LEN as u64
+    }
+
+    // Длина открытого текста чанка `index` (все чанки полные, кроме последнего).
+    fn chunk_plain_len(&self, index: u32) -> usize {
+        if index + 1 < self.chunk_count {
+            self.chunk_size as usize
+        } else {
+            (self.plaintext_len - (self.chunk_count as u64 - 1) * self.chunk_size as u64) as usize
+        }
+    }
+
+    fn chunk_offset(&self, index: u32) -> u64 {
+        index as u64 * (self.chunk_size as u64 + STREAM_TAG_LEN as u64)
+    }
+
+    // Согласованность метаданных: по длине и размеру чанка однозначно определяется число чанков.
+    fn check_layout(&self) -> Result<(), CryptoError> {
+        check_chunk_size(self.chunk_size)?;
+        let expected = self.plaintext_len.div_ceil(self.chunk_size as u64).max(1);
+        if expected != self.chunk_count as u64 {
+            return Err(CryptoError::Stream("chunk count does not match plaintext length".into()));
+        }
+        Ok(())
+    }
+}
+
+/// Диапазон шифртекста, который нужно прочитать для расшифровки байт `[offset, offset + len)`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamRange {
+    pub first_chunk: u32,
+    pub ciphertext_offset: u64,
+    pub ciphertext_len: u64,
+}
+
+// Шифр потока: подключ и префикс nonce; чанк адресуется номером и флагом последнего.
+struct StreamCipher {
+    cipher: ChaCha20Poly1305,
+    prefix: [u8; NONCE_PREFIX_LEN],
+}
+
+impl StreamCipher {
+    fn new(key: &AeadKey, prefix: [u8; NONCE_PREFIX_LEN]) -> Result<Self, CryptoError> {
+        let hk = Hkdf::<Sha256>::new(Some(&prefix), key.as_key().as_slice());
+        let mut subkey = Zeroizing::new([0u8; 32]);
+        hkdf_expand(&hk, STREAM_KEY_INFO, subkey.as_mut_slice())?;
+        Ok(StreamCipher { cipher: ChaCha20Poly1305::new(AeadKey::from_bytes(subkey.as_slice())?.as_key()), prefix })
+    }
+
+    fn from_info(key: &AeadKey, info: &EncryptedStream) -> Result<Self, CryptoError> {
+        check_chunk_size(info.chunk_size)?;
+        if info.chunk_count == 0 {
+            return Err(CryptoError::Stream("empty chunk count".into()));
+        }
+        let prefix: [u8; NONCE_PREFIX_LEN] = b64_decode(&info.nonce_prefix)?
+            .try_into()
+            .map_err(|v: Vec<u8>| CryptoError::InvalidNonceLen(v.len()))?;
+        Self::new(key, prefix)
+    }
+
+    fn nonce(&self, index: u32, last: bool) -> Nonce {
+        let mut nonce = [0u8; 12];
+        nonce[..NONCE_PREFIX_LEN].copy_from_slice(&self.prefix);
+        nonce[NONCE_PREFIX_LEN..11].copy_from_slice(&index.to_be_bytes());
+        nonce[11] = last as u8;
+        Nonce::from(nonce)
+    }
+
+    fn seal_chunk(&self, index: u32, last: bool, chunk: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        Ok(self.cipher.encrypt(&self.nonce(index, last), chunk)?)
+    }
+
+    fn open_chunk(&self, index: u32, last: bool, chunk: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        Ok(self.cipher.decrypt(&self.nonce(index, last), chunk)?)
+    }
+}
+
+fn check_chunk_size(chunk_size: u32) -> Result<(), CryptoError> {
+    if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+        return Err(CryptoError::Stream(format!("chunk size {chunk_size} outside 1..={MAX_CHUNK_SIZE}")));
+    }
+    Ok(())
+}
+
+fn new_stream(key: &AeadKey, chunk_size: u32) -> Result<(StreamCipher, [u8; NONCE_PREFIX_LEN]), CryptoError> {
+    check_chunk_size(chunk_size)?;
+    let mut prefix = [0u8; NONCE_PREFIX_LEN];
+    fill_random(&mut prefix)?;
+    Ok((StreamCipher::new(key, prefix)?, prefix))
+}
+
+fn next_index(index: u32) -> Result<u32, CryptoError> {
+    index.checked_add(1).ok_or_else(|| CryptoError::Stream("too many chunks".into()))
+}
+
+// Проверка итогов расшифровки против метаданных (количество чанков и длина).
+fn check_totals(info: &EncryptedStream, chunks: u32, total: u64) -> Result<(), CryptoError> {
+    if chunks != info.chunk_count || total != info.plaintext_len {
+        return Err(CryptoError::Stream(format!("expected {} chunks / {} bytes, got {chunks} / {total}", info.chunk_count, info.plaintext_len)));
+    }
+    Ok(())
+}
+
+// Читает, пока буфер не заполнится или не закончится поток; возвращает число прочитанных байт.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize, CryptoError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(filled)
+}
+
+/// Шифрует поток `reader` в `writer` чанками по `chunk_size` байт (см. `DEFAULT_CHUNK_SIZE`).
+/// Память — два чанка независимо от размера файла; возвращает метаданные для расшифровки.
+pub fn encrypt_stream<R: Read, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    key: &AeadKey,
+    filename: &str,
+    mimetype: &str,
+    chunk_size: u32,
+) -> Result<EncryptedStream, CryptoError> {
+    let (cipher, prefix) = new_stream(key, chunk_size)?;
+    let mut cur = vec![0u8; chunk_size as usize];
+    let mut next = vec![0u8; chunk_size as usize];
+    let mut cur_len = read_full(&mut reader, &mut cur)?;
+    let (mut index, mut total) = (0u32, 0u64);
+    loop {
+        // Чанк последний, если за ним ничего нет: неполный чанк всегда последний.
+        let next_len = if cur_len == cur.len() { read_full(&mut reader, &mut next)? } else { 0 };
+        let last = next_len == 0;
+        writer.write_all(&cipher.seal_chunk(index, last, &cur[..cur_len])?)?;
+        total += cur_len as u64;
+        if last {
+            break;
+        }
+        index = next_index(index)?;
+        std::mem::swap(&mut cur, &mut next);
+        cur_len = next_len;
+    }
+    writer.flush()?;
+    Ok(EncryptedStream {
+        nonce_prefix: b64_encode(&prefix),
+        chunk_size,
+        chunk_count: index + 1,
+        plaintext_len: total,
+        filename: filename.to_string(),
+        mimetype: mimetype.to_string(),
+    })
+}
+
+/// Дешифрует поток, созданный `encrypt_stream`, и возвращает длину открытого текста.
+/// Чанки пишутся в `writer` по мере проверки; при ошибке (подмена, перестановка, обрезка)
+/// уже записанный вывод нужно отбросить.
+pub fn decrypt_stream<R: Read, W: Write>(
+    mut reader: R,
+    mut writer: W,
+    key: &AeadKey,
+    info: &EncryptedStream,
+) -> Result<u64, CryptoError> {
+    let cipher = StreamCipher::from_info(key, info)?;
+    let chunk_len = info.chunk_size as usize + STREAM_TAG_LEN;
+    let mut cur = vec![0u8; chunk_len];
+    let mut next = vec![0u8; chunk_len];
+    let mut cur_len = read_full(&mut reader, &mut cur)?;
+    let (mut index, mut total) = (0u32, 0u64);
+    loop {
+        let next_len = if cur_len == chunk_len { read_full(&mut reader, &mut next)? } else { 0 };
+        let last = next_len == 0;
+        let chunk = cipher.open_chunk(index, last, &cur[..cur_len])?;
+        writer.write_all(&chunk)?;
+        total += chunk.len() as u64;
+        if last {
+            break;
+        }
+        index = next_index(index)?;
+        std::mem::swap(&mut cur, &mut next);
+        cur_len = next_len;
+    }
+    writer.flush()?;
+    check_totals(info, index + 1, total)?;
+    Ok(total)
+}
+
+/// Вычисляет, какие чанки покрывают байты открытого текста `[offset, offset + len)`.
+/// Диапазон обрезается по концу данных; за концом — пустой диапазон.
+pub fn stream_range(info: &EncryptedStream, offset: u64, len: u64) -> Result<StreamRange, CryptoError> {
+    info.check_layout()?;
+    let end = offset.saturating_add(len).min(info.plaintext_len);
+    let start = offset.min(end);
+    if start == end {
+        return Ok(StreamRange { first_chunk: 0, ciphertext_offset: 0, ciphertext_len: 0 });
+    }
+    let cs = info.chunk_size as u64;
+    let first = (start / cs) as u32;
+    let last = ((end - 1) / cs) as u32;
+    let ct_end = info.chunk_offset(last) + (info.chunk_plain_len(last) + STREAM_TAG_LEN) as u64;
+    Ok(StreamRange { first_chunk: first, ciphertext_offset: info.chunk_offset(first), ciphertext_len: ct_end - info.chunk_offset(first) })
+}
+
+/// Дешифрует байты открытого текста `[offset, offset + len)` без чтения всего файла.
+/// `ciphertext` — ровно те байты шифртекста, что указывает `stream_range` для того же диапазона;
+/// функция работает со срезами и числами, поэтому её удобно пробрасывать в FFI для мобильных плееров.
+pub fn decrypt_stream_range(ciphertext: &[u8], key: &AeadKey, info: &EncryptedStream, offset: u64, len: u64) -> Result<Vec<u8>, CryptoError> {
+    let range = stream_range(info, offset, len)?;
+    if ciphertext.len() as u64 != range.ciphertext_len {
+        return Err(CryptoError::Stream(format!("expected {} ciphertext bytes, got {}", range.ciphertext_len, ciphertext.len())));
+    }
+    if range.ciphertext_len == 0 {
+        return Ok(Vec::new());
+    }
+    let cipher = StreamCipher::from_info(key, info)?;
+    let end = offset + len.min(info.plaintext_len - offset);
+    let mut out = Vec::with_capacity((end - offset) as usize);
+    let (mut index, mut pos) = (range.first_chunk, 0usize);
+    while pos < ciphertext.len() {
+        let ct_len = info.chunk_plain_len(index) + STREAM_TAG_LEN;
+        let chunk = cipher.open_chunk(index, index + 1 == info.chunk_count, &ciphertext[pos..pos + ct_len])?;
+        let chunk_start = index as u64 * info.chunk_size as u64;
+        let from = offset.saturating_sub(chunk_start) as usize;
+        let to = ((end - chunk_start) as usize).min(chunk.len());
+        out.extend_from_slice(&chunk[from..to]);
+        pos += ct_len;
+        index += 1;
+    }
+    Ok(out)
+}
+
+/// Произвольный доступ к шифртексту `encrypt_stream`: `Read + Seek` по открытому тексту,
+/// расшифровываются только чанки, которые читаются. Последний расшифрованный чанк кэшируется.
+pub struct SeekableStreamReader<R: Read + Seek> {
+    inner: R,
+    cipher: StreamCipher,
+    info: EncryptedStream,
+    pos: u64,
+    cached: Option<(u32, Vec<u8>)>,
+}
+
+impl<R: Read + Seek> SeekableStreamReader<R> {
+    /// Проверяет метаданные и длину шифртекста (обрезанный файл отклоняется сразу).
+    pub fn new(mut inner: R, key: &AeadKey, info: &EncryptedStream) -> Result<Self, CryptoError> {
+        info.check_layout()?;
+        let cipher = StreamCipher::from_info(key, info)?;
+        let len = inner.seek(SeekFrom::End(0))?;
+        if len != info.ciphertext_len() {
+            return Err(CryptoError::Stream(format!("expected {} ciphertext bytes, got {len}", info.ciphertext_len())));
+        }
+        Ok(SeekableStreamReader { inner, cipher, info: info.clone(), pos: 0, cached: None })
+    }
+
+    /// Длина открытого текста.
+    pub fn len(&self) -> u64 {
+        self.info.plaintext_len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.info.plaintext_len == 0
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn load_chunk(&mut self, index: u32) -> Result<&[u8], CryptoError> {
+        if self.cached.as_ref().is_none_or(|(i, _)| *i != index) {
+            self.cached = None;
+            let mut ct = vec![0u8; self.info.chunk_plain_len(index) + STREAM_TAG_LEN];
+            self.inner.seek(SeekFrom::Start(self.info.chunk_offset(index)))?;
+            self.inner.read_exact(&mut ct)?;
+            let chunk = self.cipher.open_chunk(index, index + 1 == self.info.chunk_count, &ct)?;
+            return Ok(&self.cached.insert((index, chunk)).1);
+        }
+        match &self.cached {
+            Some((_, chunk)) => Ok(chunk),
+            None => Err(CryptoError::InvalidCiphertext("chunk cache".into())),
+        }
+    }
+}
+
+fn to_io_error(e: CryptoError) -> io::Error {
+    match e {
+        CryptoError::Io(e) => e,
+        other => io::Error::new(ErrorKind::InvalidData, other),
+    }
+}
+
+impl<R: Read + Seek> Read for SeekableStreamReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.pos >= self.info.plaintext_len {
+            return Ok(0);
+        }
+        let cs = self.info.chunk_size as u64;
+        let index = (self.pos / cs) as u32;
+        let from = (self.pos % cs) as usize;
+        let chunk = self.load_chunk(index).map_err(to_io_error)?;
+        let n = (chunk.len() - from).min(buf.len());
+        buf[..n].copy_from_slice(&chunk[from..from + n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for SeekableStreamReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::End(d) => self.info.plaintext_len.checked_add_signed(d),
+            SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+        };
+        self.pos = target.ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "seek before start"))?;
+        Ok(self.pos)
+    }
+}
+
+#[cfg(feature = "native")]
+mod async_io {
+    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+    use super::*;
+
+    async fn read_full<R: AsyncRead + Unpin>(reader: &mut R, buf: &mut [u8]) -> Result<usize, CryptoError> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match reader.read(&mut buf[filled..]).await {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(filled)
+    }
+
+    /// Асинхронный вариант `encrypt_stream` поверх `tokio::io`; формат шифртекста тот же.
+    pub async fn encrypt_stream_async<R: AsyncRead + Unpin, W: AsyncWrite + Unpin>(
+        mut reader: R,
+        mut writer: W,
+        key: &AeadKey,
+        filename: &str,
+        mimetype: &str,
+        chunk_size: u32,
+    ) -> Result<EncryptedStream, CryptoError> {
+        let (cipher, prefix) = new_stream(key, chunk_size)?;
+        let mut cur = vec![0u8; chunk_size as usize];
+        let mut next = vec![0u8; chunk_size as usize];
+        let mut cur_len = read_full(&mut reader, &mut cur).await?;
+        let (mut index, mut total) = (0u32, 0u64);
+        loop {
+            let next_len = if cur_len == cur.len() { read_full(&mut reader, &mut next).await? } else { 0 };
+            let last = next_len == 0;
+            writer.write_all(&cipher.seal_chunk(index, last, &cur[..cur_len])?).await?;
+            total += cur_len as u64;
+            if last {
+                break;
+            }
+            index = next_index(index)?;
+            std::mem::swap(&mut cur, &mut next);
+            cur_len = next_len;
+        }
+        writer.flush().await?;
+        Ok(EncryptedStream {
+            nonce_prefix: b64_encode(&prefix),
+            chunk_size,
+            chunk_count: index + 1,
+            plaintext_len: total,
+            filename: filename.to_string(),
+            mimetype: mimetype.to_string(),
+        })
+    }
+
+    /// Асинхронный вариант `decrypt_stream` поверх `tokio::io`.
+    pub async fn decrypt_stream_async<R: AsyncRead + Unpin, W: AsyncWrite + Unpin>(
+        mut reader: R,
+        mut writer: W,
+        key: &AeadKey,
+        info: &EncryptedStream,
+    ) -> Result<u64, CryptoError> {
+        let cipher = StreamCipher::from_info(key, info)?;
+        let chunk_len = info.chunk_size as usize + STREAM_TAG_LEN;
+        let mut cur = vec![0u8; chunk_len];
+        let mut next = vec![0u8; chunk_len];
+        let mut cur_len = read_full(&mut reader, &mut cur).await?;
+        let (mut index, mut total) = (0u32, 0u64);
+        loop {
+            let next_len = if cur_len == chunk_len { read_full(&mut reader, &mut next).await? } else { 0 };
+            let last = next_len == 0;
+            let chunk = cipher.open_chunk(index, last, &cur[..cur_len])?;
+            writer.write_all(&chunk).await?;
+            total += chunk.len() as u64;
+            if last {
+                break;
+            }
+            index = next_index(index)?;
+            std::mem::swap(&mut cur, &mut next);
+            cur_len = next_len;
+        }
+        writer.flush().await?;
+        check_totals(info, index + 1, total)?;
+        Ok(total)
+    }
+}
+
+#[cfg(feature = "native")]
+pub use async_io::{decrypt_stream_async, encrypt_stream_async};
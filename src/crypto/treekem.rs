@@ -0,0 +1,1096 @@
+//! Собственный протокол групповых ключей на TreeKEM: предложения, коммиты, Welcome, эпохи.
+//!
+//! Это не MLS: протокол заимствует устройство RFC 9420 (левосбалансированное дерево в массиве, resolution,
+//! UpdatePath, key schedule, transcript hash, confirmation tag), но подписываемые структуры кодируются через
+//! serde_json, а не TLS presentation language, parent hash не вычисляется, tree hash считается от serde-формы
+//! дерева, метки KDF свои (`ren-sdk treekem`). С реализациями RFC 9420 он не совместим ни по проводу, ни по ключам.
+//!
+//! Шифронабор построен на примитивах крейта: X25519 (KEM), HKDF-SHA256 (KDF, `ExpandWithLabel`/`DeriveSecret`),
+//! ChaCha20-Poly1305 (AEAD), Ed25519 (подписи, ключ идентичности как credential). Упрощения: прямой путь
+//! не фильтруется, Update выполняется только через коммит с путём, коммит применяется коммиттером сразу,
+//! без подтверждения сервером.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use super::identity::{verify_signature, IdentityKeyPair};
+use super::ratchet::{MAX_SKIP, MAX_SKIPPED_KEYS};
+use super::keys::{Nonce, PrivateKey, PublicKey, SigningPublicKey, SymmetricKey};
+use super::types::{AeadKey, CryptoError, KeyPair};
+use super::{aead_open, aead_seal, b64_decode, b64_encode, fill_random, generate_key_pair, hkdf_expand};
+
+/// Листовой узел дерева: участник, его ключ шифрования (X25519) и ключ подписи (Ed25519).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LeafNode {
+    pub member_id: String,
+    pub encryption_key: PublicKey,
+    pub signature_key: SigningPublicKey,
+    pub signature: String,
+}
+
+/// Внутренний узел дерева: ключ шифрования и листья, добавленные после последнего обновления узла.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ParentNode {
+    pub encryption_key: PublicKey,
+    pub unmerged_leaves: Vec<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TreeNode {
+    Leaf(LeafNode),
+    Parent(ParentNode),
+}
+
+/// Ratchet tree в виде массива (RFC 9420, приложение C): лист `i` — узел `2i`, число листьев — степень двойки.
+/// При десериализации (в том числе из GroupInfo в `TreeKemGroup::join`) форма дерева проверяется.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(try_from = "RatchetTreeNodes")]
+pub struct RatchetTree {
+    nodes: Vec<Option<TreeNode>>,
+}
+
+#[derive(Deserialize)]
+struct RatchetTreeNodes {
+    nodes: Vec<Option<TreeNode>>,
+}
+
+impl TryFrom<RatchetTreeNodes> for RatchetTree {
+    type Error = CryptoError;
+
+    fn try_from(raw: RatchetTreeNodes) -> Result<Self, Self::Error> {
+        let tree = RatchetTree { nodes: raw.nodes };
+        tree.check_shape()?;
+        Ok(tree)
+    }
+}
+
+/// Публичный KeyPackage участника: init-ключ для Welcome и будущий листовой узел.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KeyPackage {
+    pub init_key: PublicKey,
+    pub leaf_node: LeafNode,
+    pub signature: String,
+}
+
+/// KeyPackage вместе с приватными ключами владельца (хранится локально до вступления в группу).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct KeyPackageBundle {
+    pub key_package: KeyPackage,
+    pub init_private_key: PrivateKey,
+    pub encryption_private_key: PrivateKey,
+    pub identity: IdentityKeyPair,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Proposal {
+    Add { key_package: KeyPackage },
+    Remove { leaf_index: u32 },
+}
+
+/// Подписанное предложение участника, которое позже включается в коммит.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TreeKemProposal {
+    pub group_id: String,
+    pub epoch: u64,
+    pub sender: u32,
+    pub proposal: Proposal,
+    pub signature: String,
+}
+
+/// HPKE-подобный шифртекст: эфемерный X25519-ключ (`kem_output`), AEAD ciphertext и nonce.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HpkeCiphertext {
+    pub kem_output: PublicKey,
+    pub ciphertext: String,
+    pub nonce: Nonce,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UpdatePathNode {
+    pub encryption_key: PublicKey,
+    pub encrypted_path_secrets: Vec<HpkeCiphertext>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct UpdatePath {
+    pub leaf_node: LeafNode,
+    pub nodes: Vec<UpdatePathNode>,
+}
+
+/// Коммит: предложения (по значению) и новый путь коммиттера; переводит группу в следующую эпоху.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TreeKemCommit {
+    pub group_id: String,
+    pub epoch: u64,
+    pub sender: u32,
+    pub proposals: Vec<Proposal>,
+    pub path: UpdatePath,
+    pub signature: String,
+    pub confirmation_tag: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedGroupSecrets {
+    pub new_member: PublicKey,
+    pub encrypted_group_secrets: HpkeCiphertext,
+}
+
+/// Welcome для новых участников: секреты, зашифрованные на их init-ключи, и зашифрованный GroupInfo.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TreeKemWelcome {
+    pub secrets: Vec<EncryptedGroupSecrets>,
+    pub encrypted_group_info: String,
+}
+
+/// Прикладное сообщение группы, зашифрованное ключом из цепочки отправителя текущей эпохи.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TreeKemApplicationMessage {
+    pub group_id: String,
+    pub epoch: u64,
+    pub sender: u32,
+    pub generation: u32,
+    pub ciphertext: String,
+    pub signature: String,
+}
+
+/// GroupContext эпохи: входит в key schedule и подтверждается confirmation tag.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GroupContext {
+    pub group_id: String,
+    pub epoch: u64,
+    pub tree_hash: String,
+    pub confirmed_transcript_hash: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct GroupSecrets {
+    joiner_secret: SymmetricKey,
+    path_secret: Option<SymmetricKey>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct GroupInfo {
+    group_context: GroupContext,
+    tree: RatchetTree,
+    confirmation_tag: String,
+    signer: u32,
+    signature: String,
+}
+
+// Секреты эпохи и цепочек — `SymmetricKey`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct EpochSecrets {
+    init_secret: SymmetricKey,
+    encryption_secret: SymmetricKey,
+    exporter_secret: SymmetricKey,
+    confirmation_key: SymmetricKey,
+    epoch_authenticator: SymmetricKey,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct SenderRatchet {
+    generation: u32,
+    secret: SymmetricKey,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+struct SkippedKey {
+    sender: u32,
+    generation: u32,
+    key: SymmetricKey,
+    nonce: Nonce,
+}
+
+/// Состояние участника TreeKEM-группы (serde).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TreeKemGroup {
+    context: GroupContext,
+    tree: RatchetTree,
+    own_leaf: u32,
+    identity: IdentityKeyPair,
+    private_keys: BTreeMap<u32, PrivateKey>,
+    secrets: EpochSecrets,
+    interim_transcript_hash: String,
+    sender_ratchets: BTreeMap<u32, SenderRatchet>,
+    skipped_keys: VecDeque<SkippedKey>,
+    pending_proposals: Vec<Proposal>,
+}
+
+/// Генерирует KeyPackage участника `member_id`, подписанный ключом идентичности.
+pub fn generate_key_package(member_id: &str, identity: &IdentityKeyPair) -> Result<KeyPackageBundle, CryptoError> {
+    let init = generate_key_pair(false)?;
+    let encryption = generate_key_pair(false)?;
+    let leaf_node = sign_leaf_node(member_id, encryption.public_key, identity)?;
+    let mut key_package = KeyPackage { init_key: init.public_key, leaf_node, signature: String::new() };
+    key_package.signature = identity.sign(&tbs(&(&key_package.init_key, &key_package.leaf_node))?);
+    Ok(KeyPackageBundle {
+        key_package,
+        init_private_key: init.private_key,
+        encryption_private_key: encryption.private_key,
+        identity: identity.clone(),
+    })
+}
+
+/// Проверяет подписи KeyPackage и его листового узла.
+pub fn verify_key_package(key_package: &KeyPackage) -> Result<(), CryptoError> {
+    verify_leaf_node(&key_package.leaf_node)?;
+    verify_signature(
+        &tbs(&(&key_package.init_key, &key_package.leaf_node))?,
+        &key_package.signature,
+        &key_package.leaf_node.signature_key,
+    )
+}
+
+impl TreeKemGroup {
+    /// Создаёт группу из одного участника (эпоха 0).
+    pub fn create(group_id: &str, bundle: KeyPackageBundle) -> Result<Self, CryptoError> {
+        verify_key_package(&bundle.key_package)?;
+        let mut tree = RatchetTree::default();
+        tree.add_leaf(bundle.key_package.leaf_node.clone());
+        let context = GroupContext {
+            group_id: group_id.to_string(),
+            epoch: 0,
+            tree_hash: tree.tree_hash()?,
+            confirmed_transcript_hash: String::new(),
+        };
+        let init_secret = random_secret()?;
+        let (secrets, _, _) = key_schedule(&init_secret, &[0u8; 32], &context)?;
+        let tag = mac(secrets.confirmation_key.as_bytes(), &[]);
+        let mut private_keys = BTreeMap::new();
+        private_keys.insert(0, bundle.encryption_private_key);
+        Ok(TreeKemGroup {
+            context,
+            tree,
+            own_leaf: 0,
+            identity: bundle.identity,
+            private_keys,
+            secrets,
+            interim_transcript_hash: b64_encode(&sha256(&[&[], &tag])),
+            sender_ratchets: BTreeMap::new(),
+            skipped_keys: VecDeque::new(),
+            pending_proposals: Vec::new(),
+        })
+    }
+
+    /// Вступает в группу по Welcome, используя KeyPackage, на который он был выписан.
+    pub fn join(welcome: &TreeKemWelcome, bundle: KeyPackageBundle) -> Result<Self, CryptoError> {
+        let entry = welcome
+            .secrets
+            .iter()
+            .find(|s| s.new_member == bundle.key_package.init_key)
+            .ok_or_else(|| CryptoError::TreeKem("welcome is not addressed to this key package".into()))?;
+        let encrypted_group_info = b64_decode(&welcome.encrypted_group_info)?;
+        let secrets_json = open_with(&bundle.init_private_key, &entry.encrypted_group_secrets, "Welcome", &encrypted_group_info)?;
+        let group_secrets: GroupSecrets = serde_json::from_slice(&secrets_json)?;
+        let member_secret = extract(group_secrets.joiner_secret.as_bytes(), &[0u8; 32]);
+        let welcome_secret = derive_secret(&member_secret, "welcome")?;
+
+        let (welcome_key, welcome_nonce) = welcome_key(&welcome_secret)?;
+        let info_json = aead_open(&welcome_key, &welcome_nonce, &encrypted_group_info, &[])?;
+        // форма дерева проверяется при разборе GroupInfo (`RatchetTree: TryFrom<RatchetTreeNodes>`)
+        let info: GroupInfo = serde_json::from_slice(&info_json)?;
+        let signer = info.tree.leaf(info.signer).ok_or_else(|| CryptoError::TreeKem("unknown welcome signer".into()))?;
+        verify_signature(&group_info_tbs(&info)?, &info.signature, &signer.signature_key)?;
+        if info.tree.tree_hash()? != info.group_context.tree_hash {
+            return Err(CryptoError::TreeKem("tree hash mismatch".into()));
+        }
+
+        let own_leaf = (0..info.tree.leaf_count())
+            .find(|&l| info.tree.leaf(l).is_some_and(|n| n.encryption_key == bundle.key_package.leaf_node.encryption_key))
+            .ok_or_else(|| CryptoError::TreeKem("own leaf not found in tree".into()))?;
+        let mut private_keys = BTreeMap::new();
+        private_keys.insert(2 * own_leaf, bundle.encryption_private_key.clone());
+        if let Some(ps) = &group_secrets.path_secret {
+            let own_dp = info.tree.direct_path(2 * own_leaf);
+            let signer_dp = info.tree.direct_path(2 * info.signer);
+            let start = own_dp
+                .iter()
+                .position(|x| signer_dp.contains(x))
+                .ok_or_else(|| CryptoError::TreeKem("no common ancestor with signer".into()))?;
+            let mut path_secret = *ps.as_bytes();
+            for (k, node) in own_dp[start..].iter().enumerate() {
+                if k > 0 {
+                    path_secret = derive_secret(&path_secret, "path")?;
+                }
+                let kp = derive_key_pair(&derive_secret(&path_secret, "node")?)?;
+                if info.tree.public_key(*node) != Some(&kp.public_key) {
+                    return Err(CryptoError::TreeKem("path secret does not match tree".into()));
+                }
+                private_keys.insert(*node, kp.private_key);
+            }
+        }
+
+        let secrets = epoch_secrets(&member_secret, &info.group_context)?;
+        let tag = mac(secrets.confirmation_key.as_bytes(), info.group_context.confirmed_transcript_hash.as_bytes());
+        if b64_encode(&tag) != info.confirmation_tag {
+            return Err(CryptoError::TreeKem("confirmation tag mismatch".into()));
+        }
+        Ok(TreeKemGroup {
+            interim_transcript_hash: b64_encode(&sha256(&[info.group_context.confirmed_transcript_hash.as_bytes(), &tag])),
+            context: info.group_context,
+            tree: info.tree,
+            own_leaf,
+            identity: bundle.identity,
+            private_keys,
+            secrets,
+            sender_ratchets: BTreeMap::new(),
+            skipped_keys: VecDeque::new(),
+            pending_proposals: Vec::new(),
+        })
+    }
+
+    pub fn group_id(&self) -> &str {
+        &self.context.group_id
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.context.epoch
+    }
+
+    pub fn own_leaf_index(&self) -> u32 {
+        self.own_leaf
+    }
+
+    /// Участники группы: `(leaf_index, member_id)`.
+    pub fn members(&self) -> Vec<(u32, String)> {
+        (0..self.tree.leaf_count())
+            .filter_map(|l| self.tree.leaf(l).map(|n| (l, n.member_id.clone())))
+            .collect()
+    }
+
+    /// Epoch authenticator (Base64) для сверки состояния группы вне канала.
+    pub fn epoch_authenticator(&self) -> String {
+        b64_encode(self.secrets.epoch_authenticator.as_bytes())
+    }
+
+    /// Exporter: секрет длины `len`, привязанный к эпохе, метке и контексту (например, для звонков).
+    pub fn export_secret(&self, label: &str, context: &[u8], len: usize) -> Result<Vec<u8>, CryptoError> {
+        let secret = Zeroizing::new(derive_secret(self.secrets.exporter_secret.as_bytes(), label)?);
+        expand_with_label(secret.as_slice(), "exported", &sha256(&[context]), len)
+    }
+
+    /// Подписывает предложение для рассылки и добавляет его в собственный список ожидающих.
+    /// Предложение удалить себя (выход из группы) рассылается, но не ставится в очередь: его коммитит другой участник.
+    pub fn propose(&mut self, proposal: Proposal) -> Result<TreeKemProposal, CryptoError> {
+        self.validate_proposal(&proposal)?;
+        let mut msg = TreeKemProposal {
+            group_id: self.context.group_id.clone(),
+            epoch: self.context.epoch,
+            sender: self.own_leaf,
+            proposal,
+            signature: String::new(),
+        };
+        msg.signature = self.identity.sign(&proposal_tbs(&msg)?);
+        if !self.removes_self(&msg.proposal) {
+            self.pending_proposals.push(msg.proposal.clone());
+        }
+        Ok(msg)
+    }
+
+    /// Принимает предложение другого участника текущей эпохи.
+    /// Предложение удалить наш лист отклоняется: коммиттер не может удалить себя, и такое предложение
+    /// заблокировало бы все наши коммиты. Его коммитит любой другой участник.
+    pub fn receive_proposal(&mut self, msg: &TreeKemProposal) -> Result<(), CryptoError> {
+        self.check_epoch(&msg.group_id, msg.epoch)?;
+        let sender = self.tree.leaf(msg.sender).ok_or_else(|| CryptoError::TreeKem("unknown proposal sender".into()))?;
+        verify_signature(&proposal_tbs(msg)?, &msg.signature, &sender.signature_key)?;
+        self.validate_proposal(&msg.proposal)?;
+        if self.removes_self(&msg.proposal) {
+            return Err(CryptoError::TreeKem("proposal removes own leaf; another member must commit it".into()));
+        }
+        self.pending_proposals.push(msg.proposal.clone());
+        Ok(())
+    }
+
+    /// Коммитит все ожидающие предложения.
+    pub fn commit_pending(&mut self) -> Result<(TreeKemCommit, Option<TreeKemWelcome>), CryptoError> {
+        self.commit(Vec::new())
+    }
+
+    /// Ожидающие предложения текущей эпохи: их включит следующий собственный коммит.
+    pub fn pending_proposals(&self) -> &[Proposal] {
+        &self.pending_proposals
+    }
+
+    /// Отбрасывает ожидающие предложения (например, если одно из них больше не применимо).
+    pub fn discard_pending_proposals(&mut self) {
+        self.pending_proposals.clear();
+    }
+
+    /// Добавляет участников по их KeyPackage: коммит для группы и Welcome для новых участников.
+    pub fn add_members(&mut self, key_packages: &[KeyPackage]) -> Result<(TreeKemCommit, TreeKemWelcome), CryptoError> {
+        let proposals = key_packages.iter().map(|kp| Proposal::Add { key_package: kp.clone() }).collect();
+        let (commit, welcome) = self.commit(proposals)?;
+        Ok((commit, welcome.ok_or_else(|| CryptoError::TreeKem("no members added".into()))?))
+    }
+
+    /// Удаляет участников по `member_id`; ключи их поддеревьев обновляются путём коммиттера.
+    pub fn remove_members(&mut self, member_ids: &[&str]) -> Result<TreeKemCommit, CryptoError> {
+        let mut proposals = Vec::new();
+        for id in member_ids {
+            let (leaf_index, _) = self
+                .members()
+                .into_iter()
+                .find(|(_, m)| m == id)
+                .ok_or_else(|| CryptoError::TreeKem(format!("unknown member: {id}")))?;
+            proposals.push(Proposal::Remove { leaf_index });
+        }
+        Ok(self.commit(proposals)?.0)
+    }
+
+    /// Обновляет собственный лист и путь до корня (post-compromise security).
+    pub fn update(&mut self) -> Result<TreeKemCommit, CryptoError> {
+        Ok(self.commit(Vec::new())?.0)
+    }
+
+    /// Создаёт коммит с путём, сразу применяет его к своему состоянию и возвращает Welcome, если есть новые участники.
+    /// Ожидающие предложения (`propose`/`receive_proposal`) включаются в коммит перед `proposals`; это же
+    /// относится к `add_members`, `remove_members` и `update`.
+    pub fn commit(&mut self, proposals: Vec<Proposal>) -> Result<(TreeKemCommit, Option<TreeKemWelcome>), CryptoError> {
+        let mut all = self.pending_proposals.clone();
+        all.extend(proposals.into_iter().filter(|p| !self.pending_proposals.contains(p)));
+        let proposals = all;
+        self.validate_commit_proposals(&proposals, self.own_leaf)?;
+        let old_context = context_bytes(&self.context)?;
+        let mut tree = self.tree.clone();
+        let joiners = tree.apply_proposals(&proposals)?;
+        let joiner_nodes: Vec<u32> = joiners.iter().map(|(l, _)| 2 * l).collect();
+
+        let own_node = 2 * self.own_leaf;
+        let leaf_keys = generate_key_pair(false)?;
+        let own_member_id = self.tree.leaf(self.own_leaf).map(|n| n.member_id.clone()).unwrap_or_default();
+        let leaf_node = sign_leaf_node(&own_member_id, leaf_keys.public_key, &self.identity)?;
+        tree.nodes[own_node as usize] = Some(TreeNode::Leaf(leaf_node.clone()));
+
+        let dp = tree.direct_path(own_node);
+        let cp = tree.copath(own_node);
+        let mut path_secret = random_secret()?;
+        let mut path_secrets = Vec::with_capacity(dp.len());
+        let mut path_nodes = Vec::with_capacity(dp.len());
+        let mut new_private = vec![(own_node, leaf_keys.private_key)];
+        for (i, (node, copath_node)) in dp.iter().zip(&cp).enumerate() {
+            if i > 0 {
+                path_secret = derive_secret(&path_secret, "path")?;
+            }
+            let kp = derive_key_pair(&derive_secret(&path_secret, "node")?)?;
+            let mut encrypted_path_secrets = Vec::new();
+            for r in tree.resolution(*copath_node).into_iter().filter(|r| !joiner_nodes.contains(r)) {
+                let pk = tree.public_key(r).ok_or_else(|| CryptoError::TreeKem("blank node in resolution".into()))?;
+                encrypted_path_secrets.push(seal_to(pk, "UpdatePathNode", &old_context, &path_secret)?);
+            }
+            path_nodes.push(UpdatePathNode { encryption_key: kp.public_key, encrypted_path_secrets });
+            new_private.push((*node, kp.private_key));
+            path_secrets.push(path_secret);
+        }
+        let commit_secret = derive_secret(&path_secret, "path")?;
+        let path = UpdatePath { leaf_node, nodes: path_nodes };
+        tree.merge_path(self.own_leaf, &path)?;
+
+        let mut commit = TreeKemCommit {
+            group_id: self.context.group_id.clone(),
+            epoch: self.context.epoch,
+            sender: self.own_leaf,
+            proposals,
+            path,
+            signature: String::new(),
+            confirmation_tag: String::new(),
+        };
+        commit.signature = self.identity.sign(&commit_tbs(&commit)?);
+        let (context, secrets, joiner_secret, welcome_secret, tag) = self.next_epoch(&tree, &commit, &commit_secret)?;
+        commit.confirmation_tag = b64_encode(&tag);
+
+        let welcome = if joiners.is_empty() {
+            None
+        } else {
+            let mut info = GroupInfo {
+                group_context: context.clone(),
+                tree: tree.clone(),
+                confirmation_tag: commit.confirmation_tag.clone(),
+                signer: self.own_leaf,
+                signature: String::new(),
+            };
+            info.signature = self.identity.sign(&group_info_tbs(&info)?);
+            let info_json = serde_json::to_vec(&info)?;
+            let (welcome_key, welcome_nonce) = welcome_key(&welcome_secret)?;
+            let encrypted_group_info = aead_seal(&welcome_key, &welcome_nonce, &info_json, &[])?;
+            let mut secrets_out = Vec::with_capacity(joiners.len());
+            for (leaf, kp) in &joiners {
+                let joiner_dp = tree.direct_path(2 * leaf);
+                let path_secret = dp.iter().position(|x| joiner_dp.contains(x)).map(|i| SymmetricKey::from(Zeroizing::new(path_secrets[i])));
+                let gs = GroupSecrets { joiner_secret: SymmetricKey::from(Zeroizing::new(joiner_secret)), path_secret };
+                let gs_json = serde_json::to_vec(&gs)?;
+                secrets_out.push(EncryptedGroupSecrets {
+                    new_member: kp.init_key,
+                    encrypted_group_secrets: seal_to(&kp.init_key, "Welcome", &encrypted_group_info, &gs_json)?,
+                });
+            }
+            Some(TreeKemWelcome { secrets: secrets_out, encrypted_group_info: b64_encode(&encrypted_group_info) })
+        };
+
+        self.install_epoch(tree, context, secrets, &tag, new_private);
+        Ok((commit, welcome))
+    }
+
+    /// Применяет коммит другого участника; при удалении из группы возвращает ошибку.
+    pub fn process_commit(&mut self, commit: &TreeKemCommit) -> Result<(), CryptoError> {
+        self.check_epoch(&commit.group_id, commit.epoch)?;
+        if commit.sender == self.own_leaf {
+            return Err(CryptoError::TreeKem("own commit is already applied".into()));
+        }
+        let sender = self.tree.leaf(commit.sender).ok_or_else(|| CryptoError::TreeKem("unknown commit sender".into()))?;
+        verify_signature(&commit_tbs(commit)?, &commit.signature, &sender.signature_key)?;
+        verify_leaf_node(&commit.path.leaf_node)?;
+        if commit.path.leaf_node.signature_key != sender.signature_key || commit.path.leaf_node.member_id != sender.member_id {
+            return Err(CryptoError::TreeKem("committer changed its identity".into()));
+        }
+        self.validate_commit_proposals(&commit.proposals, commit.sender)?;
+
+        let old_context = context_bytes(&self.context)?;
+        let mut tree = self.tree.clone();
+        let joiners = tree.apply_proposals(&commit.proposals)?;
+        if tree.leaf(self.own_leaf).is_none_or(|n| n.signature_key != self.identity.public_key) {
+            return Err(CryptoError::TreeKem("removed from group".into()));
+        }
+        let joiner_nodes: Vec<u32> = joiners.iter().map(|(l, _)| 2 * l).collect();
+        let sender_node = 2 * commit.sender;
+        tree.nodes[sender_node as usize] = Some(TreeNode::Leaf(commit.path.leaf_node.clone()));
+        let dp = tree.direct_path(sender_node);
+        let cp = tree.copath(sender_node);
+        if dp.len() != commit.path.nodes.len() {
+            return Err(CryptoError::TreeKem("update path length mismatch".into()));
+        }
+
+        let own_dp = tree.direct_path(2 * self.own_leaf);
+        let start = dp
+            .iter()
+            .position(|x| own_dp.contains(x))
+            .ok_or_else(|| CryptoError::TreeKem("no common ancestor with committer".into()))?;
+        let resolution: Vec<u32> = tree.resolution(cp[start]).into_iter().filter(|r| !joiner_nodes.contains(r)).collect();
+        let (pos, private_key) = resolution
+            .iter()
+            .enumerate()
+            .find_map(|(i, r)| self.private_keys.get(r).filter(|sk| self.key_matches(*r, sk)).map(|sk| (i, sk)))
+            .ok_or_else(|| CryptoError::TreeKem("no private key for update path".into()))?;
+        let encrypted = commit.path.nodes[start]
+            .encrypted_path_secrets
+            .get(pos)
+            .ok_or_else(|| CryptoError::TreeKem("missing encrypted path secret".into()))?;
+        let mut path_secret = to32(&open_with(private_key, encrypted, "UpdatePathNode", &old_context)?)?;
+        let mut new_private = Vec::new();
+        for (k, node) in dp.iter().enumerate().skip(start) {
+            if k > start {
+                path_secret = derive_secret(&path_secret, "path")?;
+            }
+            let kp = derive_key_pair(&derive_secret(&path_secret, "node")?)?;
+            if kp.public_key != commit.path.nodes[k].encryption_key {
+                return Err(CryptoError::TreeKem("update path public key mismatch".into()));
+            }
+            new_private.push((*node, kp.private_key));
+        }
+        let commit_secret = derive_secret(&path_secret, "path")?;
+        tree.merge_path(commit.sender, &commit.path)?;
+
+        let (context, secrets, _, _, tag) = self.next_epoch(&tree, commit, &commit_secret)?;
+        if b64_encode(&tag) != commit.confirmation_tag {
+            return Err(CryptoError::TreeKem("confirmation tag mismatch".into()));
+        }
+        self.install_epoch(tree, context, secrets, &tag, new_private);
+        Ok(())
+    }
+
+    /// Шифрует прикладное сообщение для всех участников текущей эпохи.
+    pub fn encrypt(&mut self, plaintext: &str) -> Result<TreeKemApplicationMessage, CryptoError> {
+        let mut ratchet = self.sender_ratchet(self.own_leaf)?;
+        let generation = ratchet.generation;
+        let (key, nonce) = ratchet.advance()?;
+        let mut msg = TreeKemApplicationMessage {
+            group_id: self.context.group_id.clone(),
+            epoch: self.context.epoch,
+            sender: self.own_leaf,
+            generation,
+            ciphertext: String::new(),
+            signature: String::new(),
+        };
+        msg.ciphertext = b64_encode(&aead_seal(&AeadKey::from_bytes(key.as_bytes())?, &nonce, plaintext.as_bytes(), &application_aad(&msg))?);
+        msg.signature = self.identity.sign(&application_tbs(&msg));
+        self.sender_ratchets.insert(self.own_leaf, ratchet);
+        Ok(msg)
+    }
+
+    /// Проверяет подпись отправителя и дешифрует прикладное сообщение текущей эпохи.
+    pub fn decrypt(&mut self, msg: &TreeKemApplicationMessage) -> Result<String, CryptoError> {
+        self.check_epoch(&msg.group_id, msg.epoch)?;
+        let sender = self.tree.leaf(msg.sender).ok_or_else(|| CryptoError::TreeKem("unknown message sender".into()))?;
+        verify_signature(&application_tbs(msg), &msg.signature, &sender.signature_key)?;
+        let ct = b64_decode(&msg.ciphertext)?;
+        let aad = application_aad(msg);
+
+        if let Some(pos) = self.skipped_keys.iter().position(|k| k.sender == msg.sender && k.generation == msg.generation) {
+            let k = &self.skipped_keys[pos];
+            let pt = aead_open(&AeadKey::from_bytes(k.key.as_bytes())?, &k.nonce, &ct, &aad)?;
+            self.skipped_keys.remove(pos);
+            return Ok(String::from_utf8(pt)?);
+        }
+        let mut ratchet = self.sender_ratchet(msg.sender)?;
+        if msg.generation < ratchet.generation {
+            return Err(CryptoError::AuthenticationFailed);
+        }
+        if msg.generation > ratchet.generation.saturating_add(MAX_SKIP) {
+            return Err(CryptoError::TooManySkippedMessages);
+        }
+        let mut skipped = Vec::new();
+        while ratchet.generation < msg.generation {
+            let generation = ratchet.generation;
+            let (key, nonce) = ratchet.advance()?;
+            skipped.push(SkippedKey { sender: msg.sender, generation, key, nonce });
+        }
+        let (key, nonce) = ratchet.advance()?;
+        let pt = aead_open(&AeadKey::from_bytes(key.as_bytes())?, &nonce, &ct, &aad)?;
+        let s = String::from_utf8(pt)?;
+        self.sender_ratchets.insert(msg.sender, ratchet);
+        for k in skipped {
+            self.skipped_keys.push_back(k);
+            if self.skipped_keys.len() > MAX_SKIPPED_KEYS {
+                self.skipped_keys.pop_front();
+            }
+        }
+        Ok(s)
+    }
+
+    fn check_epoch(&self, group_id: &str, epoch: u64) -> Result<(), CryptoError> {
+        if group_id != self.context.group_id {
+            return Err(CryptoError::TreeKem("group id mismatch".into()));
+        }
+        if epoch != self.context.epoch {
+            return Err(CryptoError::TreeKem(format!("epoch mismatch: expected {}, got {epoch}", self.context.epoch)));
+        }
+        Ok(())
+    }
+
+    fn validate_proposal(&self, proposal: &Proposal) -> Result<(), CryptoError> {
+        match proposal {
+            Proposal::Add { key_package } => verify_key_package(key_package),
+            Proposal::Remove { leaf_index } if self.tree.leaf(*leaf_index).is_none() => {
+                Err(CryptoError::TreeKem(format!("invalid remove of leaf {leaf_index}")))
+            }
+            Proposal::Remove { .. } => Ok(()),
+        }
+    }
+
+    fn removes_self(&self, proposal: &Proposal) -> bool {
+        matches!(proposal, Proposal::Remove { leaf_index } if *leaf_index == self.own_leaf)
+    }
+
+    fn validate_commit_proposals(&self, proposals: &[Proposal], committer: u32) -> Result<(), CryptoError> {
+        for p in proposals {
+            self.validate_proposal(p)?;
+            if matches!(p, Proposal::Remove { leaf_index } if *leaf_index == committer) {
+                return Err(CryptoError::TreeKem("committer cannot remove itself".into()));
+            }
+        }
+        Ok(())
+    }
+
+    fn key_matches(&self, node: u32, private_key: &PrivateKey) -> bool {
+        self.tree.public_key(node) == Some(&private_key.public_key())
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn next_epoch(
+        &self,
+        tree: &RatchetTree,
+        commit: &TreeKemCommit,
+        commit_secret: &[u8; 32],
+    ) -> Result<(GroupContext, EpochSecrets, [u8; 32], [u8; 32], [u8; 32]), CryptoError> {
+        let confirmed = sha256(&[&b64_decode(&self.interim_transcript_hash)?, &commit_tbs(commit)?, commit.signature.as_bytes()]);
+        let context = GroupContext {
+            group_id: self.context.group_id.clone(),
+            epoch: self.context.epoch + 1,
+            tree_hash: tree.tree_hash()?,
+            confirmed_transcript_hash: b64_encode(&confirmed),
+        };
+        let (secrets, joiner_secret, welcome_secret) = key_schedule(self.secrets.init_secret.as_bytes(), commit_secret, &context)?;
+        let tag = mac(secrets.confirmation_key.as_bytes(), context.confirmed_transcript_hash.as_bytes());
+        Ok((context, secrets, joiner_secret, welcome_secret, tag))
+    }
+
+    fn install_epoch(&mut self, tree: RatchetTree, context: GroupContext, secrets: EpochSecrets, tag: &[u8; 32], new_private: Vec<(u32, PrivateKey)>) {
+        self.interim_transcript_hash = b64_encode(&sha256(&[context.confirmed_transcript_hash.as_bytes(), tag]));
+        self.tree = tree;
+        self.context = context;
+        self.secrets = secrets;
+        self.private_keys.extend(new_private);
+        let stale: Vec<u32> = self.private_keys.iter().filter(|(n, sk)| !self.key_matches(**n, sk)).map(|(n, _)| *n).collect();
+        for n in stale {
+            self.private_keys.remove(&n);
+        }
+        self.sender_ratchets.clear();
+        self.skipped_keys.clear();
+        self.pending_proposals.clear();
+    }
+
+    fn sender_ratchet(&self, leaf: u32) -> Result<SenderRatchet, CryptoError> {
+        if let Some(r) = self.sender_ratchets.get(&leaf) {
+            return Ok(r.clone());
+        }
+        let secret = Zeroizing::new(expand_with_label(self.secrets.encryption_secret.as_bytes(), "application", &leaf.to_be_bytes(), 32)?);
+        Ok(SenderRatchet { generation: 0, secret: SymmetricKey::from_bytes(&secret)? })
+    }
+}
+
+impl SenderRatchet {
+    // Возвращает (key, nonce) текущего поколения и переходит к следующему.
+    fn advance(&mut self) -> Result<(SymmetricKey, Nonce), CryptoError> {
+        let secret = self.secret.as_bytes();
+        let ctx = self.generation.to_be_bytes();
+        let key = SymmetricKey::from_bytes(&Zeroizing::new(expand_with_label(secret, "key", &ctx, 32)?))?;
+        let nonce = Nonce::from_bytes(&expand_with_label(secret, "nonce", &ctx, 12)?)?;
+        self.secret = SymmetricKey::from_bytes(&Zeroizing::new(expand_with_label(secret, "secret", &ctx, 32)?))?;
+        self.generation += 1;
+        Ok((key, nonce))
+    }
+}
+
+impl RatchetTree {
+    pub fn leaf_count(&self) -> u32 {
+        self.nodes.len().div_ceil(2) as u32
+    }
+
+    // Ширина 2^k - 1 (иначе `direct_path` не дойдёт до корня), листья на чётных позициях, внутренние узлы на нечётных,
+    // unmerged_leaves — существующие листья поддерева своего узла.
+    fn check_shape(&self) -> Result<(), CryptoError> {
+        let width = self.nodes.len();
+        if width == 0 || width > u32::MAX as usize || !(width + 1).is_power_of_two() {
+            return Err(CryptoError::TreeKem(format!("invalid tree width {width}")));
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            match node {
+                Some(TreeNode::Leaf(_)) if i % 2 == 1 => return Err(CryptoError::TreeKem(format!("leaf at parent position {i}"))),
+                Some(TreeNode::Parent(_)) if i % 2 == 0 => return Err(CryptoError::TreeKem(format!("parent at leaf position {i}"))),
+                Some(TreeNode::Parent(p)) => {
+                    let (lo, hi) = subtree_bounds(i as u32);
+                    let bad = p.unmerged_leaves.iter().any(|&l| self.leaf(l).is_none() || 2 * l < lo || 2 * l > hi);
+                    if bad {
+                        return Err(CryptoError::TreeKem(format!("invalid unmerged leaves at node {i}")));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub fn leaf(&self, leaf: u32) -> Option<&LeafNode> {
+        match self.nodes.get(2 * leaf as usize) {
+            Some(Some(TreeNode::Leaf(l))) => Some(l),
+            _ => None,
+        }
+    }
+
+    fn public_key(&self, node: u32) -> Option<&PublicKey> {
+        match self.nodes.get(node as usize)? {
+            Some(TreeNode::Leaf(l)) => Some(&l.encryption_key),
+            Some(TreeNode::Parent(p)) => Some(&p.encryption_key),
+            None => None,
+        }
+    }
+
+    fn root(&self) -> u32 {
+        self.leaf_count() - 1
+    }
+
+    fn direct_path(&self, node: u32) -> Vec<u32> {
+        let root = self.root();
+        let mut out = Vec::new();
+        let mut x = node;
+        while x != root {
+            x = parent(x);
+            out.push(x);
+        }
+        out
+    }
+
+    fn copath(&self, node: u32) -> Vec<u32> {
+        let mut path = vec![node];
+        path.extend(self.direct_path(node));
+        path.pop();
+        path.into_iter().map(sibling).collect()
+    }
+
+    fn resolution(&self, node: u32) -> Vec<u32> {
+        match &self.nodes[node as usize] {
+            Some(TreeNode::Leaf(_)) => vec![node],
+            Some(TreeNode::Parent(p)) => {
+                let mut r = vec![node];
+                r.extend(p.unmerged_leaves.iter().map(|l| 2 * l));
+                r
+            }
+            None if level(node) == 0 => Vec::new(),
+            None => {
+                let mut r = self.resolution(left(node));
+                r.extend(self.resolution(right(node)));
+                r
+            }
+        }
+    }
+
+    fn add_leaf(&mut self, leaf_node: LeafNode) -> u32 {
+        let n = self.leaf_count();
+        let leaf = match (0..n).find(|l| self.nodes[2 * *l as usize].is_none()) {
+            Some(l) => l,
+            None if n == 0 => {
+                self.nodes.push(None);
+                0
+            }
+            None => {
+                self.nodes.resize(2 * (2 * n as usize) - 1, None);
+                n
+            }
+        };
+        self.nodes[2 * leaf as usize] = Some(TreeNode::Leaf(leaf_node));
+        for x in self.direct_path(2 * leaf) {
+            if let Some(TreeNode::Parent(p)) = &mut self.nodes[x as usize] {
+                p.unmerged_leaves.push(leaf);
+            }
+        }
+        leaf
+    }
+
+    fn remove_leaf(&mut self, leaf: u32) {
+        for x in self.direct_path(2 * leaf) {
+            self.nodes[x as usize] = None;
+        }
+        self.nodes[2 * leaf as usize] = None;
+        let mut n = self.leaf_count();
+        while n > 1 && (n / 2..n).all(|l| self.nodes[2 * l as usize].is_none()) {
+            n /= 2;
+            self.nodes.truncate(2 * n as usize - 1);
+        }
+    }
+
+    // Порядок применения как в RFC 9420: сначала Remove, затем Add. Возвращает добавленные листья.
+    fn apply_proposals(&mut self, proposals: &[Proposal]) -> Result<Vec<(u32, KeyPackage)>, CryptoError> {
+        for p in proposals {
+            if let Proposal::Remove { leaf_index } = p {
+                if self.leaf(*leaf_index).is_none() {
+                    return Err(CryptoError::TreeKem(format!("invalid remove of leaf {leaf_index}")));
+                }
+                self.remove_leaf(*leaf_index);
+            }
+        }
+        let mut joiners = Vec::new();
+        for p in proposals {
+            if let Proposal::Add { key_package } = p {
+                let leaf = self.add_leaf(key_package.leaf_node.clone());
+                joiners.push((leaf, key_package.clone()));
+            }
+        }
+        Ok(joiners)
+    }
+
+    fn merge_path(&mut self, sender: u32, path: &UpdatePath) -> Result<(), CryptoError> {
+        let dp = self.direct_path(2 * sender);
+        if dp.len() != path.nodes.len() {
+            return Err(CryptoError::TreeKem("update path length mismatch".into()));
+        }
+        self.nodes[2 * sender as usize] = Some(TreeNode::Leaf(path.leaf_node.clone()));
+        for (x, node) in dp.into_iter().zip(&path.nodes) {
+            self.nodes[x as usize] = Some(TreeNode::Parent(ParentNode { encryption_key: node.encryption_key, unmerged_leaves: Vec::new() }));
+        }
+        Ok(())
+    }
+
+    fn tree_hash(&self) -> Result<String, CryptoError> {
+        Ok(b64_encode(&sha256(&[&tbs(&self.nodes)?])))
+    }
+}
+
+// Арифметика левосбалансированного дерева (RFC 9420, приложение C).
+fn level(x: u32) -> u32 {
+    x.trailing_ones()
+}
+
+fn left(x: u32) -> u32 {
+    x ^ (1 << (level(x) - 1))
+}
+
+fn right(x: u32) -> u32 {
+    x ^ (3 << (level(x) - 1))
+}
+
+fn parent(x: u32) -> u32 {
+    let k = level(x);
+    let b = (x >> (k + 1)) & 1;
+    (x | (1 << k)) ^ (b << (k + 1))
+}
+
+// Крайние узлы поддерева узла `x`.
+fn subtree_bounds(x: u32) -> (u32, u32) {
+    let k = level(x);
+    if k == 0 { (x, x) } else { (x - ((1 << k) - 1), x + ((1 << k) - 1)) }
+}
+
+fn sibling(x: u32) -> u32 {
+    let p = parent(x);
+    if x < p { right(p) } else { left(p) }
+}
+
+fn sign_leaf_node(member_id: &str, encryption_key: PublicKey, identity: &IdentityKeyPair) -> Result<LeafNode, CryptoError> {
+    let mut leaf = LeafNode {
+        member_id: member_id.to_string(),
+        encryption_key,
+        signature_key: identity.public_key,
+        signature: String::new(),
+    };
+    leaf.signature = identity.sign(&tbs(&(&leaf.member_id, &leaf.encryption_key, &leaf.signature_key))?);
+    Ok(leaf)
+}
+
+fn verify_leaf_node(leaf: &LeafNode) -> Result<(), CryptoError> {
+    verify_signature(&tbs(&(&leaf.member_id, &leaf.encryption_key, &leaf.signature_key))?, &leaf.signature, &leaf.signature_key)
+}
+
+// Подписываемые данные кодируются детерминированным serde_json.
+fn tbs<T: Serialize>(value: &T) -> Result<Vec<u8>, CryptoError> {
+    Ok(serde_json::to_vec(value)?)
+}
+
+fn context_bytes(context: &GroupContext) -> Result<Vec<u8>, CryptoError> {
+    tbs(context)
+}
+
+fn commit_tbs(c: &TreeKemCommit) -> Result<Vec<u8>, CryptoError> {
+    tbs(&(&c.group_id, c.epoch, c.sender, &c.proposals, &c.path))
+}
+
+fn proposal_tbs(p: &TreeKemProposal) -> Result<Vec<u8>, CryptoError> {
+    tbs(&(&p.group_id, p.epoch, p.sender, &p.proposal))
+}
+
+fn group_info_tbs(info: &GroupInfo) -> Result<Vec<u8>, CryptoError> {
+    tbs(&(&info.group_context, &info.tree, &info.confirmation_tag, info.signer))
+}
+
+fn application_aad(m: &TreeKemApplicationMessage) -> Vec<u8> {
+    let mut aad = Vec::new();
+    aad.extend_from_slice(&(m.group_id.len() as u32).to_be_bytes());
+    aad.extend_from_slice(m.group_id.as_bytes());
+    aad.extend_from_slice(&m.epoch.to_be_bytes());
+    aad.extend_from_slice(&m.sender.to_be_bytes());
+    aad.extend_from_slice(&m.generation.to_be_bytes());
+    aad
+}
+
+fn application_tbs(m: &TreeKemApplicationMessage) -> Vec<u8> {
+    let mut data = application_aad(m);
+    data.extend_from_slice(m.ciphertext.as_bytes());
+    data
+}
+
+// key schedule (RFC 9420, 8): init_secret + commit_secret -> joiner_secret -> member_secret -> epoch_secret.
+fn key_schedule(
+    init_secret: &[u8; 32],
+    commit_secret: &[u8; 32],
+    context: &GroupContext,
+) -> Result<(EpochSecrets, [u8; 32], [u8; 32]), CryptoError> {
+    let joiner_secret = to32(&expand_with_label(&extract(init_secret, commit_secret), "joiner", &context_bytes(context)?, 32)?)?;
+    let member_secret = extract(&joiner_secret, &[0u8; 32]);
+    let welcome_secret = derive_secret(&member_secret, "welcome")?;
+    Ok((epoch_secrets(&member_secret, context)?, joiner_secret, welcome_secret))
+}
+
+fn epoch_secrets(member_secret: &[u8; 32], context: &GroupContext) -> Result<EpochSecrets, CryptoError> {
+    let epoch_secret = Zeroizing::new(expand_with_label(member_secret, "epoch", &context_bytes(context)?, 32)?);
+    let derive = |label| Ok::<_, CryptoError>(SymmetricKey::from(Zeroizing::new(derive_secret(&epoch_secret, label)?)));
+    Ok(EpochSecrets {
+        init_secret: derive("init")?,
+        encryption_secret: derive("encryption")?,
+        exporter_secret: derive("exporter")?,
+        confirmation_key: derive("confirm")?,
+        epoch_authenticator: derive("authentication")?,
+    })
+}
+
+fn expand_with_label(secret: &[u8], label: &str, context: &[u8], len: usize) -> Result<Vec<u8>, CryptoError> {
+    let label = format!("ren-sdk treekem {label}");
+    let mut info = Vec::with_capacity(7 + label.len() + context.len());
+    info.extend_from_slice(&(len as u16).to_be_bytes());
+    info.push(label.len() as u8);
+    info.extend_from_slice(label.as_bytes());
+    info.extend_from_slice(&(context.len() as u32).to_be_bytes());
+    info.extend_from_slice(context);
+    let hk = Hkdf::<Sha256>::from_prk(secret).map_err(|_| CryptoError::Kdf("hkdf prk length".into()))?;
+    let mut out = vec![0u8; len];
+    hkdf_expand(&hk, &info, &mut out)?;
+    Ok(out)
+}
+
+fn derive_secret(secret: &[u8], label: &str) -> Result<[u8; 32], CryptoError> {
+    to32(&expand_with_label(secret, label, &[], 32)?)
+}
+
+fn extract(salt: &[u8], ikm: &[u8]) -> [u8; 32] {
+    let (prk, _) = Hkdf::<Sha256>::extract(Some(salt), ikm);
+    prk.into()
+}
+
+// HMAC-SHA256 (HKDF-Extract с ключом в роли соли).
+fn mac(key: &[u8; 32], data: &[u8]) -> [u8; 32] {
+    extract(key, data)
+}
+
+fn sha256(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for p in parts {
+        hasher.update(p);
+    }
+    hasher.finalize().into()
+}
+
+fn to32(bytes: &[u8]) -> Result<[u8; 32], CryptoError> {
+    bytes.try_into().map_err(|_| CryptoError::InvalidKeyLen(format!("{}", bytes.len())))
+}
+
+fn random_secret() -> Result<[u8; 32], CryptoError> {
+    let mut s = [0u8; 32];
+    fill_random(&mut s)?;
+    Ok(s)
+}
+
+fn derive_key_pair(node_secret: &[u8; 32]) -> Result<KeyPair, CryptoError> {
+    let private_key = PrivateKey::from_bytes(&expand_with_label(node_secret, "derive key pair", &[], 32)?)?;
+    Ok(KeyPair { public_key: private_key.public_key(), private_key })
+}
+
+// Ключ и nonce шифрования GroupInfo в Welcome.
+fn welcome_key(welcome_secret: &[u8]) -> Result<(AeadKey, Nonce), CryptoError> {
+    let key = AeadKey::from_bytes(&Zeroizing::new(expand_with_label(welcome_secret, "key", &[], 32)?))?;
+    Ok((key, Nonce::from_bytes(&expand_with_label(welcome_secret, "nonce", &[], 12)?)?))
+}
+
+fn hpke_key(shared: &[u8; 32], kem_output: &PublicKey, recipient: &PublicKey, label: &str, context: &[u8]) -> Result<AeadKey, CryptoError> {
+    let ikm = Zeroizing::new([shared.as_slice(), kem_output.as_bytes(), recipient.as_bytes()].concat());
+    AeadKey::from_bytes(&Zeroizing::new(expand_with_label(&extract(b"ren-sdk-treekem-hpke", &ikm), label, context, 32)?))
+}
+
+// EncryptWithLabel: эфемерный X25519 -> HKDF -> ChaCha20-Poly1305, контекст также служит AAD.
+fn seal_to(public_key: &PublicKey, label: &str, context: &[u8], plaintext: &[u8]) -> Result<HpkeCiphertext, CryptoError> {
+    let eph = generate_key_pair(false)?;
+    let shared = eph.private_key.diffie_hellman(public_key);
+    let key = hpke_key(&shared, &eph.public_key, public_key, label, context)?;
+    let nonce = Nonce::generate()?;
+    let ct = aead_seal(&key, &nonce, plaintext, context)?;
+    Ok(HpkeCiphertext { kem_output: eph.public_key, ciphertext: b64_encode(&ct), nonce })
+}
+
+fn open_with(private_key: &PrivateKey, ct: &HpkeCiphertext, label: &str, context: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let shared = private_key.diffie_hellman(&ct.kem_output);
+    let key = hpke_key(&shared, &ct.kem_output, &private_key.public_key(), label, context)?;
+    aead_open(&key, &ct.nonce, &b64_decode(&ct.ciphertext)?, context)
+}
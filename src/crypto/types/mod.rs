@@ -1,6 +1,12 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use chacha20poly1305::Key;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use super::{b64_decode, b64_encode, ct_eq};
+use super::keys::{Nonce, PrivateKey, PublicKey};
 
 #[derive(Debug, Error)]
 pub enum CryptoError {
@@ -8,25 +14,102 @@ pub enum CryptoError {
     Base64(#[from] base64::DecodeError),
     #[error("invalid key length: {0}")]
     InvalidKeyLen(String),
-    #[error("aead error")]
-    Aead,
+    #[error("invalid key encoding: {0}")]
+    InvalidKeyEncoding(String),
+    /// Тег AEAD не сошёлся: неверный ключ, изменённые данные или другие `aad`.
+    #[error("authentication failed")]
+    AuthenticationFailed,
+    #[error("invalid nonce length: {0}")]
+    InvalidNonceLen(usize),
+    #[error("invalid ciphertext: {0}")]
+    InvalidCiphertext(String),
+    #[error("plaintext is not valid utf-8")]
+    InvalidUtf8(#[from] std::string::FromUtf8Error),
+    #[error("random number generator failure: {0}")]
+    Rng(getrandom::Error),
+    #[error("invalid signature")]
+    InvalidSignature,
+    #[error("unknown prekey id: {0}")]
+    UnknownPreKey(u32),
+    #[error("ratchet session has no chain for this direction yet")]
+    RatchetNotReady,
+    #[error("too many skipped messages")]
+    TooManySkippedMessages,
+    #[error("unknown sender key: {0}")]
+    UnknownSenderKey(String),
+    /// Рассылка Sender Key старше уже принятой цепочки (откат `key_id` или `iteration`).
+    #[error("stale sender key distribution: {0}")]
+    StaleSenderKey(String),
+    /// `key_id` собственной цепочки достиг `u32::MAX`: ротация невозможна, группу нужно пересоздать.
+    #[error("sender key id exhausted: {0}")]
+    SenderKeyIdExhausted(String),
+    #[error("treekem: {0}")]
+    TreeKem(String),
+    #[error("invalid envelope: {0}")]
+    InvalidEnvelope(String),
+    #[error("unsupported cipher suite: {0}")]
+    UnsupportedSuite(u8),
+    #[error("invalid stream: {0}")]
+    Stream(String),
+    #[error("kdf error: {0}")]
+    Kdf(String),
+    #[error("wrong password")]
+    WrongPassword,
+    #[error("unsupported format version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("public key does not match private key")]
+    KeyPairMismatch,
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("test vector mismatch: {0}")]
+    TestVector(String),
+    #[error("invalid fingerprint: {0}")]
+    InvalidFingerprint(String),
+    #[error("recipients: {0}")]
+    Recipients(String),
+    #[error("devices: {0}")]
+    Devices(String),
+    #[error("provisioning: {0}")]
+    Provisioning(String),
 }
 
 impl From<chacha20poly1305::aead::Error> for CryptoError {
-    fn from(_: chacha20poly1305::aead::Error) -> Self { CryptoError::Aead }
+    fn from(_: chacha20poly1305::aead::Error) -> Self { CryptoError::AuthenticationFailed }
+}
+
+impl From<getrandom::Error> for CryptoError {
+    fn from(e: getrandom::Error) -> Self { CryptoError::Rng(e) }
 }
 
+/// Симметричный ключ AEAD (32 байта); затирается при drop, в `Debug` не печатается.
+/// Байты доступны только через `from_bytes`/`to_bytes`.
 #[derive(Clone)]
-pub struct AeadKey(pub Key);
+pub struct AeadKey(Key);
+
+impl Drop for AeadKey {
+    fn drop(&mut self) {
+        self.0.as_mut_slice().zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for AeadKey {}
+
+impl fmt::Debug for AeadKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AeadKey([REDACTED])")
+    }
+}
 
 impl AeadKey {
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
         if bytes.len() != 32 { return Err(CryptoError::InvalidKeyLen(format!("{}", bytes.len()))); }
-        let mut arr = [0u8; 32];
-        arr.copy_from_slice(bytes);
-        Ok(AeadKey(Key::from(arr)))
+        Ok(AeadKey(Key::clone_from_slice(bytes)))
     }
-    pub fn to_bytes(&self) -> [u8;32] { self.0.clone().into() }
+    pub fn to_bytes(&self) -> [u8;32] { self.0.into() }
+    // Ключ для конструкторов шифров без промежуточной копии байтов.
+    pub(crate) fn as_key(&self) -> &Key { &self.0 }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -50,6 +133,9 @@ pub struct EncryptedFileWithMessage {
     pub nonce: String,
     pub filename: String,
     pub mimetype: String,
+    /// Версия формата; отсутствует (0) у данных, созданных до разделения подключей.
+    #[serde(default)]
+    pub version: u8,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -60,8 +146,111 @@ pub struct DecryptedFileWithMessage {
     pub mimetype: String,
 }
 
+/// Версия формата `WrappedKey`.
+pub const WRAPPED_KEY_VERSION: u8 = 1;
+
+/// Симметричный ключ, обёрнутый для получателя (`wrap_symmetric_key`), со всем, что нужно для разворачивания.
+///
+/// JSON (serde): `{"version":1,"wrapped_key":..,"ephemeral_public_key":..,"nonce":..}`, байтовые поля — Base64.
+/// Бинарно (`encode`): version (1) || ephemeral_public_key (32) || nonce (12) || wrapped_key.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WrappedKey {
+    pub version: u8,
+    pub wrapped_key: String,
+    pub ephemeral_public_key: PublicKey,
+    pub nonce: Nonce,
+}
+
+impl WrappedKey {
+    pub fn encode(&self) -> Result<Vec<u8>, CryptoError> {
+        let ct = b64_decode(&self.wrapped_key)?;
+        let mut out = Vec::with_capacity(1 + 32 + 12 + ct.len());
+        out.push(self.version);
+        out.extend_from_slice(self.ephemeral_public_key.as_bytes());
+        out.extend_from_slice(self.nonce.as_bytes());
+        out.extend_from_slice(&ct);
+        Ok(out)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let (&version, rest) = bytes.split_first().ok_or_else(|| CryptoError::InvalidCiphertext("empty wrapped key".into()))?;
+        if version != WRAPPED_KEY_VERSION {
+            return Err(CryptoError::UnsupportedVersion(version));
+        }
+        if rest.len() < 32 + 12 {
+            return Err(CryptoError::InvalidCiphertext("wrapped key too short".into()));
+        }
+        let (eph, rest) = rest.split_at(32);
+        let (nonce, ct) = rest.split_at(12);
+        Ok(WrappedKey {
+            version,
+            wrapped_key: b64_encode(ct),
+            ephemeral_public_key: PublicKey::from_bytes(eph)?,
+            nonce: Nonce::from_bytes(nonce)?,
+        })
+    }
+
+    pub fn to_b64(&self) -> Result<String, CryptoError> {
+        Ok(b64_encode(&self.encode()?))
+    }
+
+    pub fn from_b64(b64: &str) -> Result<Self, CryptoError> {
+        Self::decode(&b64_decode(b64)?)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct KeyPair {
-    pub public_key: String,  // base64 raw 32 bytes
-    pub private_key: String, // base64 raw 32 bytes
+    pub public_key: PublicKey,   // serde: base64 raw 32 bytes
+    pub private_key: PrivateKey, // serde: base64 raw 32 bytes
+}
+
+/// Строка с секретом (Base64 приватного ключа): затирается при drop, в `Debug` не печатается.
+/// В serde — обычная строка; значение доступно только явно, через `expose_secret`.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(transparent)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(secret: impl Into<String>) -> Self {
+        SecretString(secret.into())
+    }
+
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        SecretString(secret)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(secret: &str) -> Self {
+        SecretString(secret.to_string())
+    }
+}
+
+impl PartialEq for SecretString {
+    fn eq(&self, other: &Self) -> bool {
+        ct_eq(self.0.as_bytes(), other.0.as_bytes())
+    }
+}
+
+impl Eq for SecretString {}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SecretString {}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretString([REDACTED])")
+    }
 }
@@ -0,0 +1,214 @@
+//! Тест-векторы совместимости форматов (JSON) для web- и mobile-клиентов.
+//!
+//! В векторе заданы все входы, включая «случайные» байты (nonce, эфемерный ключ), поэтому его можно проверить
+//! в любой реализации без общего RNG. `TestVectors::verify` повторяет каждое шифрование с nonce и эфемерным
+//! ключом из вектора, сравнивает результат побайтно и расшифровывает ожидаемый шифртекст.
+//!
+//! Необязательная секция `webcrypto` создана не этим крейтом, а WebCrypto (`tests/vectors/gen-webcrypto.mjs`)
+//! по алгоритмам web-клиента. Её nonce и эфемерный ключ выбрал WebCrypto, поэтому такие векторы только
+//! расшифровываются, и только с фичей `crypto_p256_aesgcm`; без неё секция пропускается.
+
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use super::keys::{Nonce, PrivateKey, PublicKey};
+use super::types::{AeadKey, CryptoError};
+use super::{
+    b64_decode, b64_encode, decrypt_data, decrypt_message, encrypt_data_with_nonce, encrypt_message_with_nonce,
+    generate_message_encryption_key, unwrap_symmetric_key, wrap_symmetric_key_with,
+};
+
+/// Текущая версия формата файла векторов.
+pub const TEST_VECTORS_VERSION: u8 = 1;
+
+// Открытые тексты по кругу: пустой, ASCII, UTF-8 не из ASCII, длиннее одного блока ChaCha20.
+const PLAINTEXTS: [&str; 4] = [
+    "",
+    "hello",
+    "Привет, мир! 👋",
+    "The quick brown fox jumps over the lazy dog. Съешь же ещё этих мягких французских булок, да выпей чаю.",
+];
+
+/// Набор векторов (serde, все байтовые поля — Base64).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TestVectors {
+    pub version: u8,
+    pub encrypt_data: Vec<DataVector>,
+    pub encrypt_message: Vec<MessageVector>,
+    pub wrap_symmetric_key: Vec<WrapVector>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webcrypto: Option<WebCryptoVectors>,
+}
+
+/// `encrypt_data`: `expected` = Base64(nonce(12) || ciphertext).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DataVector {
+    pub key: String,
+    pub nonce: String,
+    pub plaintext: String,
+    pub expected: String,
+}
+
+/// `encrypt_message`: шифртекст и nonce раздельно, как в `EncryptedMessage`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MessageVector {
+    pub key: String,
+    pub nonce: String,
+    pub plaintext: String,
+    pub ciphertext: String,
+}
+
+/// `wrap_symmetric_key`: ключ `key` оборачивается для `receiver_public_key` эфемерным ключом и nonce из вектора.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WrapVector {
+    pub key: String,
+    pub receiver_private_key: String,
+    pub receiver_public_key: String,
+    pub ephemeral_private_key: String,
+    pub ephemeral_public_key: String,
+    pub nonce: String,
+    pub wrapped_key: String,
+}
+
+/// Векторы web-клиента (AES-256-GCM, P-256); `source` — чем и как они созданы.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WebCryptoVectors {
+    pub source: String,
+    pub encrypt_message: Vec<MessageVector>,
+    pub wrap_symmetric_key: Vec<P256WrapVector>,
+}
+
+/// Обёртка ключа P-256: ключи получателя и эфемерный публичный ключ — Base64(PKCS#8) / Base64(SPKI).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct P256WrapVector {
+    pub key: String,
+    pub receiver_private_key: String,
+    pub receiver_public_key: String,
+    pub ephemeral_public_key: String,
+    pub nonce: String,
+    pub wrapped_key: String,
+}
+
+fn key_from_b64(b64: &str) -> Result<AeadKey, CryptoError> {
+    AeadKey::from_bytes(&Zeroizing::new(b64_decode(b64)?))
+}
+
+fn check(ok: bool, name: &str, index: usize, field: &str) -> Result<(), CryptoError> {
+    if ok { Ok(()) } else { Err(CryptoError::TestVector(format!("{name}[{index}]: {field}"))) }
+}
+
+/// Генерирует `count` векторов каждого вида со случайными ключами и nonce.
+pub fn generate_test_vectors(count: usize) -> Result<TestVectors, CryptoError> {
+    let mut vectors = TestVectors {
+        version: TEST_VECTORS_VERSION,
+        encrypt_data: Vec::with_capacity(count),
+        encrypt_message: Vec::with_capacity(count),
+        wrap_symmetric_key: Vec::with_capacity(count),
+        webcrypto: None,
+    };
+    for i in 0..count {
+        let plaintext = PLAINTEXTS[i % PLAINTEXTS.len()];
+
+        let key = generate_message_encryption_key()?;
+        let nonce = Nonce::generate()?;
+        let expected = encrypt_data_with_nonce(plaintext, &key, &[], &nonce)?;
+        vectors.encrypt_data.push(DataVector {
+            key: b64_encode(&key.to_bytes()),
+            nonce: nonce.to_string(),
+            plaintext: plaintext.to_string(),
+            expected,
+        });
+
+        let key = generate_message_encryption_key()?;
+        let nonce = Nonce::generate()?;
+        let em = encrypt_message_with_nonce(plaintext, &key, &[], &nonce)?;
+        vectors.encrypt_message.push(MessageVector {
+            key: b64_encode(&key.to_bytes()),
+            nonce: em.nonce,
+            plaintext: plaintext.to_string(),
+            ciphertext: em.ciphertext,
+        });
+
+        let key = generate_message_encryption_key()?;
+        let receiver = PrivateKey::generate()?;
+        let ephemeral = PrivateKey::generate()?;
+        let wrapped = wrap_symmetric_key_with(&key, &receiver.public_key(), &ephemeral, Nonce::generate()?)?;
+        vectors.wrap_symmetric_key.push(WrapVector {
+            key: b64_encode(&key.to_bytes()),
+            receiver_private_key: receiver.to_secret_b64().expose_secret().to_string(),
+            receiver_public_key: receiver.public_key().to_string(),
+            ephemeral_private_key: ephemeral.to_secret_b64().expose_secret().to_string(),
+            ephemeral_public_key: wrapped.ephemeral_public_key.to_string(),
+            nonce: wrapped.nonce.to_string(),
+            wrapped_key: wrapped.wrapped_key,
+        });
+    }
+    Ok(vectors)
+}
+
+impl TestVectors {
+    /// Проверяет все векторы; ошибка `CryptoError::TestVector` называет вектор и поле, которые не сошлись.
+    pub fn verify(&self) -> Result<(), CryptoError> {
+        if self.version != TEST_VECTORS_VERSION {
+            return Err(CryptoError::UnsupportedVersion(self.version));
+        }
+        for (i, v) in self.encrypt_data.iter().enumerate() {
+            let key = key_from_b64(&v.key)?;
+            let out = encrypt_data_with_nonce(&v.plaintext, &key, &[], &v.nonce.parse()?)?;
+            check(out == v.expected, "encrypt_data", i, "expected")?;
+            check(decrypt_data(&v.expected, &key)? == v.plaintext, "encrypt_data", i, "plaintext")?;
+        }
+        for (i, v) in self.encrypt_message.iter().enumerate() {
+            let key = key_from_b64(&v.key)?;
+            let em = encrypt_message_with_nonce(&v.plaintext, &key, &[], &v.nonce.parse()?)?;
+            check(em.ciphertext == v.ciphertext && em.nonce == v.nonce, "encrypt_message", i, "ciphertext")?;
+            check(decrypt_message(&v.ciphertext, &v.nonce, &key)? == v.plaintext, "encrypt_message", i, "plaintext")?;
+        }
+        for (i, v) in self.wrap_symmetric_key.iter().enumerate() {
+            let key = key_from_b64(&v.key)?;
+            let receiver: PrivateKey = v.receiver_private_key.parse()?;
+            let receiver_pk: PublicKey = v.receiver_public_key.parse()?;
+            check(receiver.public_key() == receiver_pk, "wrap_symmetric_key", i, "receiver_public_key")?;
+            let ephemeral: PrivateKey = v.ephemeral_private_key.parse()?;
+            let wrapped = wrap_symmetric_key_with(&key, &receiver_pk, &ephemeral, v.nonce.parse()?)?;
+            check(wrapped.ephemeral_public_key.to_string() == v.ephemeral_public_key, "wrap_symmetric_key", i, "ephemeral_public_key")?;
+            check(wrapped.wrapped_key == v.wrapped_key, "wrap_symmetric_key", i, "wrapped_key")?;
+            let unwrapped = unwrap_symmetric_key(&wrapped, &receiver)?;
+            check(unwrapped.to_bytes() == key.to_bytes(), "wrap_symmetric_key", i, "key")?;
+        }
+        #[cfg(feature = "crypto_p256_aesgcm")]
+        if let Some(web) = &self.webcrypto {
+            web.verify()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "crypto_p256_aesgcm")]
+impl WebCryptoVectors {
+    fn verify(&self) -> Result<(), CryptoError> {
+        use super::types::WRAPPED_KEY_VERSION;
+        use super::webcrypto::{self, P256WrappedKey};
+
+        for (i, v) in self.encrypt_message.iter().enumerate() {
+            let key = key_from_b64(&v.key)?;
+            let plaintext = webcrypto::decrypt_message(&v.ciphertext, &v.nonce, &key)?;
+            check(plaintext == v.plaintext, "webcrypto.encrypt_message", i, "plaintext")?;
+        }
+        for (i, v) in self.wrap_symmetric_key.iter().enumerate() {
+            let key = key_from_b64(&v.key)?;
+            let receiver = webcrypto::import_private_key_pkcs8_b64(&v.receiver_private_key)?;
+            let receiver_pk = webcrypto::export_public_key_spki_b64(&receiver.public_key())?;
+            check(receiver_pk == v.receiver_public_key, "webcrypto.wrap_symmetric_key", i, "receiver_public_key")?;
+            let wrapped = P256WrappedKey {
+                version: WRAPPED_KEY_VERSION,
+                wrapped_key: v.wrapped_key.clone(),
+                ephemeral_public_key: v.ephemeral_public_key.clone(),
+                nonce: v.nonce.parse()?,
+            };
+            let unwrapped = webcrypto::unwrap_symmetric_key(&wrapped, &v.receiver_private_key)?;
+            check(unwrapped.to_bytes() == key.to_bytes(), "webcrypto.wrap_symmetric_key", i, "key")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+//! Набор алгоритмов web-клиента (WebCrypto): ECDH P-256 + AES-256-GCM (фича `crypto_p256_aesgcm`).
+//!
+//! Функции повторяют основной API (`encrypt_message`, `wrap_symmetric_key`, ...) с теми же Base64-форматами,
+//! но с алгоритмами TS-версии, поэтому Rust-клиент читает шифртексты web-клиента и наоборот:
+//!
+//! - AES-256-GCM, IV 12 байт, тег 16 байт в конце шифртекста (как `crypto.subtle.encrypt({ name: "AES-GCM" })`);
+//! - публичные ключи — SPKI DER, приватные — PKCS#8 DER (`exportKey("spki" | "pkcs8")`), всё в Base64;
+//! - обёртка ключа: эфемерный P-256, общий секрет — x-координата ECDH (`deriveBits(.., 256)`),
+//!   ключ обёртки — HKDF-SHA256(salt = пусто, info = `ren-sdk-wrap`), затем AES-256-GCM.
+//!
+//! Конверты с `CipherSuite::Aes256Gcm` расшифровываются и обычными `decrypt_data`/`decrypt_message`.
+
+use hkdf::Hkdf;
+use p256::ecdh::diffie_hellman;
+use p256::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey};
+use p256::{PublicKey as P256PublicKey, SecretKey};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use super::envelope::{CipherSuite, open_envelope_or_legacy, seal_envelope_with_suite};
+use super::keys::Nonce;
+use super::types::{AeadKey, CryptoError, EncryptedMessage, SecretString, WRAPPED_KEY_VERSION};
+use super::{aead_open_with_suite, aead_seal_with_suite, b64_decode, b64_encode, fill_random, hkdf_expand, secret_b64};
+
+/// Пара ключей P-256 в форматах WebCrypto: `public_key` — Base64(SPKI), `private_key` — Base64(PKCS#8).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct P256KeyPair {
+    pub public_key: String,
+    pub private_key: SecretString,
+}
+
+/// Ключ, обёрнутый `wrap_symmetric_key` для P-256 получателя (serde): аналог `WrappedKey` основного API,
+/// но `ephemeral_public_key` — Base64(SPKI), а шифр — AES-256-GCM. `version` — `WRAPPED_KEY_VERSION`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct P256WrappedKey {
+    pub version: u8,
+    pub wrapped_key: String,
+    pub ephemeral_public_key: String,
+    pub nonce: Nonce,
+}
+
+fn key_encoding_error(e: impl std::fmt::Display) -> CryptoError {
+    CryptoError::InvalidKeyEncoding(e.to_string())
+}
+
+fn generate_secret_key() -> Result<SecretKey, CryptoError> {
+    // Случайные 32 байта — скаляр, если он в [1, n); иначе (вероятность ~2^-32) берём новые.
+    let mut bytes = Zeroizing::new([0u8; 32]);
+    loop {
+        fill_random(bytes.as_mut_slice())?;
+        if let Ok(sk) = SecretKey::from_slice(bytes.as_slice()) {
+            return Ok(sk);
+        }
+    }
+}
+
+/// Генерирует пару ECDH P-256.
+pub fn generate_key_pair() -> Result<P256KeyPair, CryptoError> {
+    let sk = generate_secret_key()?;
+    Ok(P256KeyPair { public_key: export_public_key_spki_b64(&sk.public_key())?, private_key: export_private_key_pkcs8_b64(&sk)? })
+}
+
+pub fn export_public_key_spki_b64(public_key: &P256PublicKey) -> Result<String, CryptoError> {
+    Ok(b64_encode(public_key.to_public_key_der().map_err(key_encoding_error)?.as_bytes()))
+}
+
+pub fn import_public_key_spki_b64(b64: &str) -> Result<P256PublicKey, CryptoError> {
+    P256PublicKey::from_public_key_der(&b64_decode(b64)?).map_err(key_encoding_error)
+}
+
+pub fn export_private_key_pkcs8_b64(private_key: &SecretKey) -> Result<SecretString, CryptoError> {
+    Ok(secret_b64(private_key.to_pkcs8_der().map_err(key_encoding_error)?.as_bytes()))
+}
+
+pub fn import_private_key_pkcs8_b64(b64: &str) -> Result<SecretKey, CryptoError> {
+    let der = Zeroizing::new(b64_decode(b64)?);
+    SecretKey::from_pkcs8_der(&der).map_err(key_encoding_error)
+}
+
+/// AES-256-GCM: Base64(iv(12) || ciphertext || tag).
+pub fn encrypt_data(data: &str, key: &AeadKey) -> Result<String, CryptoError> {
+    let nonce = Nonce::generate()?;
+    let ct = aead_seal_with_suite(CipherSuite::Aes256Gcm, key, &nonce, data.as_bytes(), &[])?;
+    Ok(b64_encode(&[nonce.as_bytes().as_slice(), &ct].concat()))
+}
+
+/// Дешифрует результат `encrypt_data` (или конверт).
+pub fn decrypt_data(b64_combined: &str, key: &AeadKey) -> Result<String, CryptoError> {
+    let data = b64_decode(b64_combined)?;
+    let pt = open_envelope_or_legacy(&data, key, &[], || {
+        if data.len() < 12 { return Err(CryptoError::InvalidCiphertext("shorter than nonce".into())); }
+        let (nonce, ct) = data.split_at(12);
+        aead_open_with_suite(CipherSuite::Aes256Gcm, key, &Nonce::from_bytes(nonce)?, ct, &[])
+    })?;
+    Ok(String::from_utf8(pt)?)
+}
+
+/// AES-256-GCM для сообщения: Base64 ciphertext (с тегом) и iv раздельно, как в `EncryptedMessage`.
+pub fn encrypt_message(data: &str, key: &AeadKey) -> Result<EncryptedMessage, CryptoError> {
+    let nonce = Nonce::generate()?;
+    let ct = aead_seal_with_suite(CipherSuite::Aes256Gcm, key, &nonce, data.as_bytes(), &[])?;
+    Ok(EncryptedMessage { ciphertext: b64_encode(&ct), nonce: nonce.to_string() })
+}
+
+/// Дешифрует результат `encrypt_message` (или конверт).
+pub fn decrypt_message(ciphertext_b64: &str, nonce_b64: &str, key: &AeadKey) -> Result<String, CryptoError> {
+    let ct = b64_decode(ciphertext_b64)?;
+    let pt = open_envelope_or_legacy(&ct, key, &[], || {
+        aead_open_with_suite(CipherSuite::Aes256Gcm, key, &nonce_b64.parse()?, &ct, &[])
+    })?;
+    Ok(String::from_utf8(pt)?)
+}
+
+/// Конверт с suite id `Aes256Gcm` (Base64), см. `encrypt_data_envelope` основного API.
+pub fn encrypt_data_envelope(data: &str, key: &AeadKey, key_id: &[u8]) -> Result<String, CryptoError> {
+    seal_envelope_with_suite(data.as_bytes(), key, key_id, &[], CipherSuite::Aes256Gcm)?.to_b64()
+}
+
+fn wrap_key(private_key: &SecretKey, public_key: &P256PublicKey) -> Result<AeadKey, CryptoError> {
+    let shared = diffie_hellman(private_key.to_nonzero_scalar(), public_key.as_affine());
+    let hk = Hkdf::<Sha256>::new(None, shared.raw_secret_bytes());
+    let mut wrap_key_bytes = Zeroizing::new([0u8; 32]);
+    hkdf_expand(&hk, b"ren-sdk-wrap", wrap_key_bytes.as_mut_slice())?;
+    AeadKey::from_bytes(wrap_key_bytes.as_slice())
+}
+
+/// Оборачивает ключ для получателя с публичным ключом Base64(SPKI).
+pub fn wrap_symmetric_key(key_to_wrap: &AeadKey, receiver_public_key_spki_b64: &str) -> Result<P256WrappedKey, CryptoError> {
+    let receiver = import_public_key_spki_b64(receiver_public_key_spki_b64)?;
+    let eph_sk = generate_secret_key()?;
+    let wrap_key = wrap_key(&eph_sk, &receiver)?;
+    let nonce = Nonce::generate()?;
+    let ct = aead_seal_with_suite(CipherSuite::Aes256Gcm, &wrap_key, &nonce, Zeroizing::new(key_to_wrap.to_bytes()).as_slice(), &[])?;
+    Ok(P256WrappedKey {
+        version: WRAPPED_KEY_VERSION,
+        wrapped_key: b64_encode(&ct),
+        ephemeral_public_key: export_public_key_spki_b64(&eph_sk.public_key())?,
+        nonce,
+    })
+}
+
+/// Разворачивает ключ из `wrap_symmetric_key` приватным ключом получателя Base64(PKCS#8).
+pub fn unwrap_symmetric_key(wrapped: &P256WrappedKey, receiver_private_key_pkcs8_b64: &str) -> Result<AeadKey, CryptoError> {
+    if wrapped.version != WRAPPED_KEY_VERSION {
+        return Err(CryptoError::UnsupportedVersion(wrapped.version));
+    }
+    let receiver = import_private_key_pkcs8_b64(receiver_private_key_pkcs8_b64)?;
+    let wrap_key = wrap_key(&receiver, &import_public_key_spki_b64(&wrapped.ephemeral_public_key)?)?;
+    let ct = b64_decode(&wrapped.wrapped_key)?;
+    let pt = Zeroizing::new(aead_open_with_suite(CipherSuite::Aes256Gcm, &wrap_key, &wrapped.nonce, &ct, &[])?);
+    AeadKey::from_bytes(&pt)
+}
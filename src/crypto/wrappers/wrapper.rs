@@ -1,16 +1,28 @@
 use crate::crypto::{
-    decrypt_message, derive_key_from_password, derive_key_from_string, encrypt_message,
-    EncryptedMessage, CryptoError,
+    decrypt_message, derive_key_from_password, encrypt_message, legacy_key_from_string,
+    unwrap_symmetric_key, unwrap_symmetric_key_authenticated, wrap_symmetric_key,
+    wrap_symmetric_key_authenticated, EncryptedMessage, CryptoError, KeyPair, SecretString, WrappedKey,
 };
+use crate::crypto::identity::{IdentityKeyPair, generate_identity_key_pair, verify_signature};
+use crate::crypto::keys::SigningPublicKey;
+use crate::crypto::kdf::{KdfParams, MasterKey, PasswordKdf, derive_key_from_secret};
 use base64::{engine::general_purpose, Engine as _};
 use crate::AeadKey;
+#[cfg(feature = "ffi")]
+use crate::crypto::stream::{EncryptedStream, StreamRange, decrypt_stream_range, stream_range};
+#[cfg(feature = "ffi")]
+use zeroize::Zeroizing;
+
+/// Метка контекста ключа для `encrypt_message_with_secret` / `decrypt_message_with_secret`.
+pub const SECRET_MESSAGE_CONTEXT: &str = "ren-sdk/message-with-secret";
 
 /// Шифрует строковое сообщение, используя секретную строку как источник ключа.
 ///
-/// Внутри: SHA-256 от `secret` -> AEAD ключ -> `encrypt_message`.
+/// Внутри: HKDF-SHA256 от `secret` с контекстом `SECRET_MESSAGE_CONTEXT` -> AEAD ключ -> `encrypt_message`.
+/// Секрет должен быть с высокой энтропией (токен, общий ключ), а не паролем.
 /// Возвращает `EncryptedMessage` с Base64-полями `ciphertext` и `nonce`.
 pub fn encrypt_message_with_secret(secret: &str, message: &str) -> Result<EncryptedMessage, CryptoError> {
-    let key = derive_key_from_string(secret)?;
+    let key = derive_key_from_secret(secret, SECRET_MESSAGE_CONTEXT, None)?;
     encrypt_message(message, &key)
 }
 
@@ -18,19 +30,96 @@ pub fn encrypt_message_with_secret(secret: &str, message: &str) -> Result<Encryp
 ///
 /// Принимает: `secret` (строка), `ciphertext_b64` и `nonce_b64` (Base64).
 /// Возвращает исходную строку при успешной аутентифицированной расшифровке.
+/// Сообщения, зашифрованные старой деривацией (SHA-256 от секрета), тоже читаются.
 pub fn decrypt_message_with_secret(secret: &str, ciphertext_b64: &str, nonce_b64: &str) -> Result<String, CryptoError> {
-    let key = derive_key_from_string(secret)?;
-    decrypt_message(ciphertext_b64, nonce_b64, &key)
+    let key = derive_key_from_secret(secret, SECRET_MESSAGE_CONTEXT, None)?;
+    match decrypt_message(ciphertext_b64, nonce_b64, &key) {
+        Err(CryptoError::AuthenticationFailed) => decrypt_message(ciphertext_b64, nonce_b64, &legacy_key_from_string(secret)?),
+        res => res,
+    }
+}
+
+/// Мастер-ключ существующего пользователя PBKDF2, у которого на сервере только соль (Base64, 16 байт).
+/// `key` — прежний ключ PBKDF2 для расшифровки приватного ключа; `migration` всегда есть и содержит
+/// новый ключ Argon2id (параметры по умолчанию) и запись KDF, которой нужно заменить соль на сервере.
+pub fn derive_master_key_from_password(password: &str, salt_b64: &str) -> Result<MasterKey, CryptoError> {
+    PasswordKdf::legacy_pbkdf2(salt_b64.parse()?).derive_and_migrate(password, KdfParams::default())
 }
 
-/// Деривирует мастер-ключ (AEAD, 32 байта) из пароля и соли (Base64-16 байт).
-/// Возвращает AeadKey, который можно использовать для шифрования/дешифрования.
-pub fn derive_master_key_from_password(password: &str, salt_b64: &str) -> Result<AeadKey, CryptoError> {
-    derive_key_from_password(password, salt_b64)
+/// Мастер-ключ по серверной записи KDF (соль + параметры + verifier).
+/// `migration` есть, если запись отстаёт от параметров по умолчанию.
+pub fn derive_master_key_from_password_kdf(password: &str, kdf: &PasswordKdf) -> Result<MasterKey, CryptoError> {
+    kdf.derive_and_migrate(password, KdfParams::default())
 }
 
 /// Деривирует мастер-ключ из пароля и соли и экспортирует его как Base64 (сырые 32 байта).
 pub fn derive_master_key_b64_from_password(password: &str, salt_b64: &str) -> Result<String, CryptoError> {
-    let key = derive_key_from_password(password, salt_b64)?;
+    let key = derive_key_from_password(password, &salt_b64.parse()?)?;
     Ok(general_purpose::STANDARD.encode(key.to_bytes()))
-}
\ No newline at end of file
+}
+
+// Строковые обёртки над типизированным API для WASM/FFI: все ключи и nonce — Base64.
+
+/// Пара ключей X25519 как Base64-строки: (publicKey, privateKey).
+pub fn generate_key_pair_b64() -> Result<(String, SecretString), CryptoError> {
+    let KeyPair { public_key, private_key } = crate::crypto::generate_key_pair(false)?;
+    Ok((public_key.to_string(), private_key.to_secret_b64()))
+}
+
+/// `wrap_symmetric_key` с Base64-ключом получателя.
+pub fn wrap_symmetric_key_b64(key_to_wrap: &AeadKey, receiver_public_key_b64: &str) -> Result<WrappedKey, CryptoError> {
+    wrap_symmetric_key(key_to_wrap, &receiver_public_key_b64.parse()?)
+}
+
+/// `unwrap_symmetric_key` с Base64-ключом получателя.
+pub fn unwrap_symmetric_key_b64(wrapped: &WrappedKey, receiver_private_key_b64: &str) -> Result<AeadKey, CryptoError> {
+    unwrap_symmetric_key(wrapped, &receiver_private_key_b64.parse()?)
+}
+
+/// `wrap_symmetric_key_authenticated` с Base64-ключами отправителя и получателя.
+pub fn wrap_symmetric_key_authenticated_b64(key_to_wrap: &AeadKey, sender_private_key_b64: &str, receiver_public_key_b64: &str) -> Result<WrappedKey, CryptoError> {
+    wrap_symmetric_key_authenticated(key_to_wrap, &sender_private_key_b64.parse()?, &receiver_public_key_b64.parse()?)
+}
+
+/// `unwrap_symmetric_key_authenticated` с Base64-ключами получателя и ожидаемого отправителя.
+pub fn unwrap_symmetric_key_authenticated_b64(
+    wrapped: &WrappedKey,
+    receiver_private_key_b64: &str,
+    expected_sender_public_key_b64: &str,
+) -> Result<AeadKey, CryptoError> {
+    unwrap_symmetric_key_authenticated(wrapped, &receiver_private_key_b64.parse()?, &expected_sender_public_key_b64.parse()?)
+}
+
+/// Пара ключей идентичности Ed25519 как Base64-строки: (publicKey, privateKey).
+pub fn generate_identity_key_pair_b64() -> Result<(String, SecretString), CryptoError> {
+    let IdentityKeyPair { public_key, private_key } = generate_identity_key_pair()?;
+    Ok((public_key.to_string(), private_key.to_secret_b64()))
+}
+
+/// `verify_signature` с Base64-ключом подписи.
+pub fn verify_signature_b64(data: &[u8], signature_b64: &str, public_key_b64: &str) -> Result<(), CryptoError> {
+    verify_signature(data, signature_b64, &public_key_b64.parse()?)
+}
+
+/// Публичный X25519-ключ (Base64), соответствующий Base64-ключу идентичности Ed25519.
+pub fn signing_public_key_to_x25519_b64(public_key_b64: &str) -> Result<String, CryptoError> {
+    let public_key: SigningPublicKey = public_key_b64.parse()?;
+    Ok(public_key.to_x25519().to_string())
+}
+
+// FFI-обёртки частичного чтения потока (фича `ffi`): метаданные — JSON `EncryptedStream`, ключ — Base64.
+// Плеер сначала узнаёт, какие байты шифртекста читать, затем передаёт их в `decrypt_stream_range_b64`.
+
+/// `stream_range` по JSON-метаданным `EncryptedStream`.
+#[cfg(feature = "ffi")]
+pub fn stream_range_json(info_json: &str, offset: u64, len: u64) -> Result<StreamRange, CryptoError> {
+    stream_range(&serde_json::from_str(info_json)?, offset, len)
+}
+
+/// `decrypt_stream_range` с Base64-ключом и JSON-метаданными: `ciphertext` — ровно байты из `stream_range_json`.
+#[cfg(feature = "ffi")]
+pub fn decrypt_stream_range_b64(ciphertext: &[u8], key_b64: &str, info_json: &str, offset: u64, len: u64) -> Result<Vec<u8>, CryptoError> {
+    let key = AeadKey::from_bytes(&Zeroizing::new(general_purpose::STANDARD.decode(key_b64)?))?;
+    let info: EncryptedStream = serde_json::from_str(info_json)?;
+    decrypt_stream_range(ciphertext, &key, &info, offset, len)
+}
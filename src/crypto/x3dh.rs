@@ -0,0 +1,241 @@
+//! X3DH: асинхронное установление сессии (identity key, signed prekey, one-time prekeys).
+//!
+//! Получатель (Bob) заранее публикует `PreKeyBundle`; отправитель (Alice) по бандлу
+//! вычисляет общий секрет и формирует `X3dhInitialMessage`, не дожидаясь Bob онлайн.
+//! Идентичность — Ed25519 (`IdentityKeyPair`): она подписывает SPK, а для DH конвертируется в X25519.
+
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use super::identity::{verify_signature, IdentityKeyPair};
+use super::{aead_open, aead_seal, b64_decode, b64_encode, generate_key_pair, hkdf_expand};
+use super::keys::{Nonce, PublicKey, SigningPublicKey};
+use super::types::{AeadKey, CryptoError, KeyPair};
+
+const X3DH_INFO: &[u8] = b"ren-sdk-x3dh";
+const X3DH_INITIAL_INFO: &[u8] = b"ren-sdk-x3dh-initial";
+const SPK_SIGNATURE_CONTEXT: &[u8] = b"ren-sdk-signed-prekey";
+
+/// Среднесрочный пре-ключ получателя (SPK) с идентификатором и подписью ключом идентичности.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SignedPreKey {
+    pub id: u32,
+    pub key_pair: KeyPair,
+    pub signature: String,
+}
+
+impl SignedPreKey {
+    /// Генерирует SPK и подписывает его публичную часть ключом идентичности.
+    pub fn generate(id: u32, identity: &IdentityKeyPair) -> Result<Self, CryptoError> {
+        let key_pair = generate_key_pair(false)?;
+        let signature = identity.sign(&spk_signed_data(&key_pair.public_key));
+        Ok(SignedPreKey { id, key_pair, signature })
+    }
+}
+
+/// Одноразовый пре-ключ получателя (OPK) с идентификатором.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct OneTimePreKey {
+    pub id: u32,
+    pub key_pair: KeyPair,
+}
+
+/// Публичный бандл получателя, который сервер выдаёт инициатору (в serde все ключи — Base64 RAW 32 байта).
+/// `identity_key` — публичный Ed25519-ключ идентичности, остальные — X25519.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PreKeyBundle {
+    pub identity_key: SigningPublicKey,
+    pub signed_prekey_id: u32,
+    pub signed_prekey: PublicKey,
+    pub signed_prekey_signature: String,
+    pub one_time_prekey_id: Option<u32>,
+    pub one_time_prekey: Option<PublicKey>,
+}
+
+/// Первое сообщение инициатора: его ключи, использованные пре-ключи и AEAD-полезная нагрузка.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct X3dhInitialMessage {
+    pub identity_key: SigningPublicKey,
+    pub ephemeral_key: PublicKey,
+    pub signed_prekey_id: u32,
+    pub one_time_prekey_id: Option<u32>,
+    pub ciphertext: String,
+    pub nonce: Nonce,
+}
+
+/// Результат X3DH: общий секрет SK и associated data `AD = IK_A || IK_B` (Ed25519-ключи идентичности).
+pub struct X3dhOutput {
+    pub shared_secret: AeadKey,
+    pub associated_data: Vec<u8>,
+}
+
+/// Локальное хранилище пре-ключей получателя (сериализуемо для персистентности).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PreKeyStore {
+    pub identity: IdentityKeyPair,
+    pub signed_prekey: SignedPreKey,
+    /// SPK до последней ротации: принимается, пока не вызван `discard_previous_signed_prekey`,
+    /// чтобы не потерять первые сообщения, отправленные по старому бандлу.
+    #[serde(default)]
+    pub previous_signed_prekey: Option<SignedPreKey>,
+    pub one_time_prekeys: Vec<OneTimePreKey>,
+    pub next_prekey_id: u32,
+}
+
+impl PreKeyStore {
+    /// Создаёт хранилище с новым SPK и `one_time_count` одноразовыми пре-ключами.
+    pub fn new(identity: IdentityKeyPair, one_time_count: usize) -> Result<Self, CryptoError> {
+        let signed_prekey = SignedPreKey::generate(0, &identity)?;
+        let mut store = PreKeyStore {
+            identity,
+            signed_prekey,
+            previous_signed_prekey: None,
+            one_time_prekeys: Vec::new(),
+            next_prekey_id: 1,
+        };
+        store.generate_one_time_prekeys(one_time_count)?;
+        Ok(store)
+    }
+
+    /// Дополняет запас одноразовых пре-ключей и возвращает их публичные части `(id, public_key)`.
+    pub fn generate_one_time_prekeys(&mut self, count: usize) -> Result<Vec<(u32, PublicKey)>, CryptoError> {
+        let mut out = Vec::with_capacity(count);
+        for _ in 0..count {
+            let id = self.next_prekey_id;
+            self.next_prekey_id = self.next_prekey_id.wrapping_add(1);
+            let key_pair = generate_key_pair(false)?;
+            out.push((id, key_pair.public_key));
+            self.one_time_prekeys.push(OneTimePreKey { id, key_pair });
+        }
+        Ok(out)
+    }
+
+    /// Заменяет SPK новым подписанным ключом (периодическая ротация).
+    /// Прежний SPK сохраняется в `previous_signed_prekey` на льготный период; более старый удаляется.
+    pub fn rotate_signed_prekey(&mut self) -> Result<(), CryptoError> {
+        let id = self.next_prekey_id;
+        let previous = std::mem::replace(&mut self.signed_prekey, SignedPreKey::generate(id, &self.identity)?);
+        self.previous_signed_prekey = Some(previous);
+        self.next_prekey_id = self.next_prekey_id.wrapping_add(1);
+        Ok(())
+    }
+
+    /// Завершает льготный период: прежний SPK больше не принимается.
+    pub fn discard_previous_signed_prekey(&mut self) {
+        self.previous_signed_prekey = None;
+    }
+
+    /// Публичный бандл для публикации; берёт первый доступный одноразовый пре-ключ, если он есть.
+    pub fn bundle(&self) -> PreKeyBundle {
+        let opk = self.one_time_prekeys.first();
+        PreKeyBundle {
+            identity_key: self.identity.public_key,
+            signed_prekey_id: self.signed_prekey.id,
+            signed_prekey: self.signed_prekey.key_pair.public_key,
+            signed_prekey_signature: self.signed_prekey.signature.clone(),
+            one_time_prekey_id: opk.map(|k| k.id),
+            one_time_prekey: opk.map(|k| k.key_pair.public_key),
+        }
+    }
+
+    /// Обрабатывает первое сообщение инициатора: вычисляет SK, расшифровывает полезную нагрузку
+    /// и удаляет использованный одноразовый пре-ключ (повторное использование невозможно).
+    pub fn accept(&mut self, msg: &X3dhInitialMessage) -> Result<(X3dhOutput, Vec<u8>), CryptoError> {
+        let spk = std::iter::once(&self.signed_prekey)
+            .chain(&self.previous_signed_prekey)
+            .find(|k| k.id == msg.signed_prekey_id)
+            .ok_or(CryptoError::UnknownPreKey(msg.signed_prekey_id))?;
+        let opk_index = match msg.one_time_prekey_id {
+            Some(id) => Some(
+                self.one_time_prekeys.iter().position(|k| k.id == id).ok_or(CryptoError::UnknownPreKey(id))?,
+            ),
+            None => None,
+        };
+
+        let spk = &spk.key_pair.private_key;
+        let own_ik = self.identity.to_x25519_key_pair();
+        let remote_ik = msg.identity_key.to_x25519();
+        let mut ikm = Zeroizing::new(Vec::with_capacity(32 * 5));
+        ikm.extend_from_slice(&[0xFF; 32]);
+        ikm.extend_from_slice(spk.diffie_hellman(&remote_ik).as_slice());
+        ikm.extend_from_slice(own_ik.private_key.diffie_hellman(&msg.ephemeral_key).as_slice());
+        ikm.extend_from_slice(spk.diffie_hellman(&msg.ephemeral_key).as_slice());
+        if let Some(i) = opk_index {
+            ikm.extend_from_slice(self.one_time_prekeys[i].key_pair.private_key.diffie_hellman(&msg.ephemeral_key).as_slice());
+        }
+        let output = derive_output(&ikm, &msg.identity_key, &self.identity.public_key)?;
+
+        let plaintext = open_initial(&output, &msg.ciphertext, &msg.nonce)?;
+        if let Some(i) = opk_index {
+            self.one_time_prekeys.remove(i);
+        }
+        Ok((output, plaintext))
+    }
+}
+
+/// Инициирует сессию по бандлу получателя и шифрует `initial_plaintext` в первом сообщении.
+/// Перед вычислением DH проверяется подпись SPK ключом идентичности получателя.
+pub fn x3dh_initiate(
+    identity: &IdentityKeyPair,
+    bundle: &PreKeyBundle,
+    initial_plaintext: &[u8],
+) -> Result<(X3dhOutput, X3dhInitialMessage), CryptoError> {
+    verify_signature(&spk_signed_data(&bundle.signed_prekey), &bundle.signed_prekey_signature, &bundle.identity_key)?;
+    let own_ik = identity.to_x25519_key_pair();
+    let remote_ik = bundle.identity_key.to_x25519();
+    let ephemeral = generate_key_pair(false)?;
+    let mut ikm = Zeroizing::new(Vec::with_capacity(32 * 5));
+    ikm.extend_from_slice(&[0xFF; 32]);
+    ikm.extend_from_slice(own_ik.private_key.diffie_hellman(&bundle.signed_prekey).as_slice());
+    ikm.extend_from_slice(ephemeral.private_key.diffie_hellman(&remote_ik).as_slice());
+    ikm.extend_from_slice(ephemeral.private_key.diffie_hellman(&bundle.signed_prekey).as_slice());
+    if let Some(opk) = &bundle.one_time_prekey {
+        ikm.extend_from_slice(ephemeral.private_key.diffie_hellman(opk).as_slice());
+    }
+    let output = derive_output(&ikm, &identity.public_key, &bundle.identity_key)?;
+
+    let (ciphertext, nonce) = seal_initial(&output, initial_plaintext)?;
+    let msg = X3dhInitialMessage {
+        identity_key: identity.public_key,
+        ephemeral_key: ephemeral.public_key,
+        signed_prekey_id: bundle.signed_prekey_id,
+        one_time_prekey_id: bundle.one_time_prekey_id,
+        ciphertext,
+        nonce,
+    };
+    Ok((output, msg))
+}
+
+fn spk_signed_data(signed_prekey: &PublicKey) -> Vec<u8> {
+    let mut data = SPK_SIGNATURE_CONTEXT.to_vec();
+    data.extend_from_slice(signed_prekey.as_bytes());
+    data
+}
+
+fn derive_output(ikm: &[u8], initiator_ik: &SigningPublicKey, responder_ik: &SigningPublicKey) -> Result<X3dhOutput, CryptoError> {
+    let hk = Hkdf::<Sha256>::new(Some(&[0u8; 32]), ikm);
+    let mut sk = Zeroizing::new([0u8; 32]);
+    hkdf_expand(&hk, X3DH_INFO, sk.as_mut_slice())?;
+    let associated_data = [initiator_ik.as_bytes().as_slice(), responder_ik.as_bytes()].concat();
+    Ok(X3dhOutput { shared_secret: AeadKey::from_bytes(sk.as_slice())?, associated_data })
+}
+
+// Ключ полезной нагрузки первого сообщения выводится из SK, чтобы SK оставался только корнем сессии.
+fn initial_key(output: &X3dhOutput) -> Result<AeadKey, CryptoError> {
+    let hk = Hkdf::<Sha256>::new(None, output.shared_secret.as_key().as_slice());
+    let mut key = Zeroizing::new([0u8; 32]);
+    hkdf_expand(&hk, X3DH_INITIAL_INFO, key.as_mut_slice())?;
+    AeadKey::from_bytes(key.as_slice())
+}
+
+fn seal_initial(output: &X3dhOutput, plaintext: &[u8]) -> Result<(String, Nonce), CryptoError> {
+    let nonce = Nonce::generate()?;
+    let ct = aead_seal(&initial_key(output)?, &nonce, plaintext, &output.associated_data)?;
+    Ok((b64_encode(&ct), nonce))
+}
+
+fn open_initial(output: &X3dhOutput, ciphertext_b64: &str, nonce: &Nonce) -> Result<Vec<u8>, CryptoError> {
+    aead_open(&initial_key(output)?, nonce, &b64_decode(ciphertext_b64)?, &output.associated_data)
+}
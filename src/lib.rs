@@ -7,18 +7,80 @@ pub mod wasm;
 #[path = "crypto/wrappers/wrapper.rs"]
 pub mod wrapper;
 
+#[allow(deprecated)]
 pub use crypto::{
     AeadKey, CryptoError, DecryptedFileWithMessage, EncryptedFile, EncryptedFileWithMessage,
-    EncryptedMessage, KeyPair, decrypt_data, decrypt_file, decrypt_file_with_message,
-    decrypt_message, derive_key_from_password, derive_key_from_string, encrypt_data,
-    encrypt_file, encrypt_file_with_message, encrypt_message, export_private_key_b64,
+    EncryptedMessage, FILE_WITH_MESSAGE_VERSION, KeyPair, decrypt_data, decrypt_data_with_aad, decrypt_file,
+    decrypt_file_with_aad, decrypt_file_with_message, decrypt_message, decrypt_message_with_aad,
+    derive_key_from_password, derive_key_from_string, encrypt_data, encrypt_data_envelope,
+    encrypt_data_with_aad, encrypt_file, encrypt_file_envelope, encrypt_file_with_aad,
+    encrypt_file_with_message, encrypt_message, encrypt_message_envelope,
+    encrypt_message_with_aad, export_private_key_b64,
     export_public_key_b64, generate_key_pair, generate_message_encryption_key, generate_nonce,
     generate_salt, import_private_key_b64, import_public_key_b64, unwrap_symmetric_key,
-    wrap_symmetric_key,
+    unwrap_symmetric_key_authenticated, wrap_symmetric_key, wrap_symmetric_key_authenticated, WRAPPED_KEY_VERSION,
+    WrappedKey,
+};
+pub use crypto::keys::{Nonce, PrivateKey, PublicKey, Salt, SigningPrivateKey, SigningPublicKey, SymmetricKey};
+pub use crypto::rng::{CryptoRng, OsRng};
+#[cfg(feature = "test-rng")]
+pub use crypto::rng::{SeededRng, with_rng};
+pub use crypto::fingerprint::{
+    ContactKey, FINGERPRINT_ITERATIONS, FINGERPRINT_VERSION, SafetyNumber, VerificationState, VerificationStore,
+};
+pub use crypto::recipients::{
+    RECIPIENT_KEYS_VERSION, Recipient, RecipientKey, RecipientKeys, unwrap_for_recipient, wrap_for_recipients,
+    wrap_for_recipients_shared,
+};
+pub use crypto::devices::{
+    DEVICE_LIST_VERSION, Device, DeviceDirectory, DeviceInfo, DeviceList, DeviceListUpdate, device_recipient_id,
+    unwrap_for_device, wrap_for_device_lists,
+};
+pub use crypto::provisioning::{
+    ExistingDeviceLink, LinkedAccount, NewDeviceLink, PROVISIONING_VERSION, ProvisioningAck, ProvisioningMessage,
+    ProvisioningState, parse_provisioning_qr,
+};
+pub use crypto::vectors::{
+    DataVector, MessageVector, P256WrapVector, TEST_VECTORS_VERSION, TestVectors, WebCryptoVectors, WrapVector,
+    generate_test_vectors,
+};
+pub use crypto::envelope::{CipherSuite, Envelope, open_envelope, seal_envelope, seal_envelope_with_suite};
+pub use crypto::identity::{IdentityKeyPair, generate_identity_key_pair, verify_signature};
+pub use crypto::x3dh::{
+    OneTimePreKey, PreKeyBundle, PreKeyStore, SignedPreKey, X3dhInitialMessage, X3dhOutput,
+    x3dh_initiate,
+};
+pub use crypto::ratchet::{RatchetHeader, RatchetMessage, RatchetSession};
+pub use crypto::sender_keys::{
+    GroupSession, SenderKeyDistribution, SenderKeyMessage, SenderKeyState,
+};
+pub use crypto::treekem::{
+    GroupContext, KeyPackage, KeyPackageBundle, TreeKemApplicationMessage, TreeKemCommit, TreeKemGroup,
+    TreeKemProposal, TreeKemWelcome, Proposal, generate_key_package, verify_key_package,
+};
+pub use crypto::stream::{
+    DEFAULT_CHUNK_SIZE, EncryptedStream, SeekableStreamReader, StreamRange, decrypt_stream,
+    decrypt_stream_range, encrypt_stream, stream_range,
+};
+#[cfg(feature = "native")]
+pub use crypto::stream::{decrypt_stream_async, encrypt_stream_async};
+pub use crypto::kdf::{
+    KdfMigration, KdfParams, MasterKey, PasswordKdf, derive_key_from_human_secret, derive_key_from_secret,
+    migrate_password_kdf,
+};
+pub use crypto::backup::{
+    PRIVATE_KEY_BACKUP_VERSION, PrivateKeyBackup, open_private_key, seal_private_key,
+    seal_private_key_with_params,
 };
 
 pub use wrapper::{
     decrypt_message_with_secret, derive_master_key_b64_from_password,
-    derive_master_key_from_password, encrypt_message_with_secret,
+    derive_master_key_from_password, derive_master_key_from_password_kdf, encrypt_message_with_secret,
+    generate_identity_key_pair_b64, generate_key_pair_b64, signing_public_key_to_x25519_b64,
+    unwrap_symmetric_key_authenticated_b64, unwrap_symmetric_key_b64, verify_signature_b64,
+    wrap_symmetric_key_authenticated_b64, wrap_symmetric_key_b64,
+    SECRET_MESSAGE_CONTEXT,
 };
+#[cfg(feature = "ffi")]
+pub use wrapper::{decrypt_stream_range_b64, stream_range_json};
 
@@ -1,8 +1,10 @@
 use ren_sdk::crypto::*;
+use ren_sdk::{TestVectors, decrypt_message_with_secret, encrypt_message_with_secret, generate_test_vectors};
+use base64::Engine as _;
 use std::env;
 
 fn print_usage() {
-    eprintln!("Usage:\n  ren-sdk gen-keypair\n  ren-sdk enc-msg <secret> <message>\n  ren-sdk dec-msg <secret> <cipher_b64> <nonce_b64>");
+    eprintln!("Usage:\n  ren-sdk gen-keypair\n  ren-sdk enc-msg <secret> <message>\n  ren-sdk dec-msg <secret> <cipher_b64> <nonce_b64>\n  ren-sdk gen-vectors [count]\n  ren-sdk verify-vectors <file.json>\n  ren-sdk wrap-key <receiver_public_key_b64> <key_b64>\n  ren-sdk unwrap-key <receiver_private_key_b64> <wrapped_key.json>");
 }
 
 fn main() {
@@ -13,16 +15,15 @@ fn main() {
     }
     match args[1].as_str() {
         "gen-keypair" => {
-            let kp = generate_key_pair(false);
+            let kp = generate_key_pair(false).expect("keygen");
             println!("public_key_b64: {}", kp.public_key);
-            println!("private_key_b64: {}", kp.private_key);
+            println!("private_key_b64: {}", kp.private_key.to_secret_b64().expose_secret());
         }
         "enc-msg" => {
             if args.len() < 4 { print_usage(); return; }
             let secret = &args[2];
             let msg = &args[3];
-            let key = derive_key_from_string(secret).expect("key");
-            let enc = encrypt_message(msg, &key).expect("enc");
+            let enc = encrypt_message_with_secret(secret, msg).expect("enc");
             println!("ciphertext_b64: {}", enc.ciphertext);
             println!("nonce_b64: {}", enc.nonce);
         }
@@ -31,10 +32,50 @@ fn main() {
             let secret = &args[2];
             let ct = &args[3];
             let nonce = &args[4];
-            let key = derive_key_from_string(secret).expect("key");
-            let msg = decrypt_message(ct, nonce, &key).expect("dec");
+            let msg = decrypt_message_with_secret(secret, ct, nonce).expect("dec");
             println!("{}", msg);
         }
+        "gen-vectors" => {
+            let count = match args.get(2).map(|n| n.parse::<usize>()) {
+                None => 4,
+                Some(Ok(n)) => n,
+                Some(Err(_)) => { print_usage(); return; }
+            };
+            let vectors = generate_test_vectors(count).expect("gen");
+            println!("{}", serde_json::to_string_pretty(&vectors).expect("json"));
+        }
+        "verify-vectors" => {
+            if args.len() < 3 { print_usage(); return; }
+            let json = std::fs::read_to_string(&args[2]).expect("read");
+            let vectors: TestVectors = serde_json::from_str(&json).expect("json");
+            match vectors.verify() {
+                Ok(()) => println!("ok"),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        "wrap-key" => {
+            if args.len() < 4 { print_usage(); return; }
+            let receiver: PublicKey = args[2].parse().expect("public key");
+            let key = AeadKey::from_bytes(&base64::engine::general_purpose::STANDARD.decode(&args[3]).expect("key b64")).expect("key");
+            let wrapped = wrap_symmetric_key(&key, &receiver).expect("wrap");
+            println!("{}", serde_json::to_string_pretty(&wrapped).expect("json"));
+        }
+        "unwrap-key" => {
+            if args.len() < 4 { print_usage(); return; }
+            let receiver: PrivateKey = args[2].parse().expect("private key");
+            let json = std::fs::read_to_string(&args[3]).expect("read");
+            let wrapped: WrappedKey = serde_json::from_str(&json).expect("json");
+            match unwrap_symmetric_key(&wrapped, &receiver) {
+                Ok(key) => println!("{}", base64::engine::general_purpose::STANDARD.encode(key.to_bytes())),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
         _ => print_usage(),
     }
 }
@@ -0,0 +1,52 @@
+use ren_sdk::crypto::backup::*;
+use ren_sdk::crypto::kdf::*;
+use ren_sdk::crypto::*;
+
+fn fast_argon2() -> KdfParams {
+    KdfParams::Argon2id { memory_kib: MIN_ARGON2_MEMORY_KIB, iterations: MIN_ARGON2_ITERATIONS, parallelism: 1 }
+}
+
+#[test]
+fn test_seal_open_private_key_roundtrip() {
+    let kp = generate_key_pair(true).unwrap();
+    let backup = seal_private_key_with_params(&kp, "P@ssw0rd", fast_argon2()).unwrap();
+    assert_eq!(backup.version, PRIVATE_KEY_BACKUP_VERSION);
+    assert_eq!(backup.kdf.params, fast_argon2());
+
+    // Сервер хранит JSON; восстановление на новом устройстве — только по паролю.
+    let json = serde_json::to_string(&backup).unwrap();
+    let stored: PrivateKeyBackup = serde_json::from_str(&json).unwrap();
+    assert_eq!(open_private_key(&stored, "P@ssw0rd").unwrap(), kp);
+    assert!(matches!(open_private_key(&stored, "wrong"), Err(CryptoError::WrongPassword)));
+
+    let again = seal_private_key_with_params(&kp, "P@ssw0rd", fast_argon2()).unwrap();
+    assert_ne!(again.kdf.salt, backup.kdf.salt);
+}
+
+#[test]
+fn test_open_private_key_rejects_tampering() {
+    let kp = generate_key_pair(true).unwrap();
+    let backup = seal_private_key_with_params(&kp, "pw", fast_argon2()).unwrap();
+
+    let swapped = PrivateKeyBackup { public_key: generate_key_pair(true).unwrap().public_key, ..backup.clone() };
+    assert!(matches!(open_private_key(&swapped, "pw"), Err(CryptoError::AuthenticationFailed)));
+
+    let future = PrivateKeyBackup { version: 2, ..backup.clone() };
+    assert!(matches!(open_private_key(&future, "pw"), Err(CryptoError::UnsupportedVersion(2))));
+
+    // Подменённые параметры KDF меняют ключ, и verifier это обнаруживает; параметры ниже минимума отклоняются сразу.
+    let mut changed = backup.clone();
+    changed.kdf.params = KdfParams::Argon2id { memory_kib: MIN_ARGON2_MEMORY_KIB, iterations: MIN_ARGON2_ITERATIONS + 1, parallelism: 1 };
+    assert!(open_private_key(&changed, "pw").is_err());
+    let mut weaker = backup.clone();
+    weaker.kdf.params = KdfParams::Argon2id { memory_kib: 8, iterations: 1, parallelism: 1 };
+    assert!(matches!(open_private_key(&weaker, "pw"), Err(CryptoError::Kdf(_))));
+}
+
+#[test]
+fn test_seal_private_key_rejects_mismatched_pair() {
+    let a = generate_key_pair(true).unwrap();
+    let b = generate_key_pair(true).unwrap();
+    let mixed = KeyPair { public_key: b.public_key, private_key: a.private_key };
+    assert!(matches!(seal_private_key_with_params(&mixed, "pw", fast_argon2()), Err(CryptoError::KeyPairMismatch)));
+}
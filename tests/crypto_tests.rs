@@ -7,19 +7,19 @@ use base64::{engine::general_purpose, Engine as _};
 
 #[test]
 fn test_salt_and_nonce_generation() {
-    let salt_b64 = generate_salt();
+    let salt_b64 = generate_salt().unwrap();
     let salt = general_purpose::STANDARD.decode(salt_b64).unwrap();
     assert_eq!(salt.len(), 16);
 
-    let nonce_b64 = generate_nonce();
+    let nonce_b64 = generate_nonce().unwrap();
     let nonce = general_purpose::STANDARD.decode(nonce_b64).unwrap();
     assert_eq!(nonce.len(), 12);
 }
 
 #[test]
 fn test_decrypt_message_with_wrong_key_fails() {
-    let key1 = generate_message_encryption_key();
-    let key2 = generate_message_encryption_key();
+    let key1 = generate_message_encryption_key().unwrap();
+    let key2 = generate_message_encryption_key().unwrap();
     let enc = encrypt_message("oops", &key1).unwrap();
     let bad = decrypt_message(&enc.ciphertext, &enc.nonce, &key2);
     assert!(bad.is_err());
@@ -27,7 +27,7 @@ fn test_decrypt_message_with_wrong_key_fails() {
 
 #[test]
 fn test_decrypt_message_with_tampered_ciphertext_fails() {
-    let key = generate_message_encryption_key();
+    let key = generate_message_encryption_key().unwrap();
     let mut enc = encrypt_message("auth", &key).unwrap();
     // flip a byte in ciphertext
     let mut ct = general_purpose::STANDARD.decode(&enc.ciphertext).unwrap();
@@ -38,6 +38,7 @@ fn test_decrypt_message_with_tampered_ciphertext_fails() {
 }
 
 #[test]
+#[allow(deprecated)]
 fn test_decrypt_data_with_invalid_nonce_length_fails() {
     let key = derive_key_from_string("secret").unwrap();
     let ct = encrypt_data("hello", &key).unwrap();
@@ -51,12 +52,13 @@ fn test_decrypt_data_with_invalid_nonce_length_fails() {
 
 #[test]
 fn test_decrypt_file_with_invalid_base64_fails() {
-    let key = generate_message_encryption_key();
+    let key = generate_message_encryption_key().unwrap();
     let res = decrypt_file("not-base64!!!", "also-not-base64", &key);
     assert!(res.is_err());
 }
 
 #[test]
+#[allow(deprecated)]
 fn test_derive_key_from_string_and_encrypt_decrypt_data() {
     let key = derive_key_from_string("secret").unwrap();
     let ct = encrypt_data("hello", &key).unwrap();
@@ -66,7 +68,7 @@ fn test_derive_key_from_string_and_encrypt_decrypt_data() {
 
 #[test]
 fn test_encrypt_decrypt_message() {
-    let key = generate_message_encryption_key();
+    let key = generate_message_encryption_key().unwrap();
     let enc = encrypt_message("ping", &key).unwrap();
     let dec = decrypt_message(&enc.ciphertext, &enc.nonce, &key).unwrap();
     assert_eq!(dec, "ping");
@@ -74,7 +76,7 @@ fn test_encrypt_decrypt_message() {
 
 #[test]
 fn test_encrypt_decrypt_file() {
-    let key = generate_message_encryption_key();
+    let key = generate_message_encryption_key().unwrap();
     let data = b"file-bytes".to_vec();
     let ef = encrypt_file(&data, "f.txt", "text/plain", &key).unwrap();
     let dec = decrypt_file(&ef.ciphertext, &ef.nonce, &key).unwrap();
@@ -83,30 +85,172 @@ fn test_encrypt_decrypt_file() {
 
 #[test]
 fn test_encrypt_decrypt_file_with_message() {
-    let key = generate_message_encryption_key();
+    let key = generate_message_encryption_key().unwrap();
     let data = b"file-data".to_vec();
     let efm = encrypt_file_with_message(&data, "hi", &key, "f.bin", "application/octet-stream").unwrap();
-    let out = decrypt_file_with_message(&efm.enc_file, &efm.ciphertext, &efm.nonce, &key, &efm.filename, &efm.mimetype).unwrap();
+    let out = decrypt_file_with_message(&efm, &key).unwrap();
     assert_eq!(out.file, data);
     assert_eq!(out.message, "hi");
+    assert_eq!(out.filename, "f.bin");
+}
+
+#[test]
+fn test_encrypt_decrypt_message_with_aad() {
+    let key = generate_message_encryption_key().unwrap();
+    let enc = encrypt_message_with_aad("hi", &key, b"chat-1|alice|42").unwrap();
+    assert_eq!(decrypt_message_with_aad(&enc.ciphertext, &enc.nonce, &key, b"chat-1|alice|42").unwrap(), "hi");
+    // перенос шифртекста в другой чат или без метаданных не проходит проверку тега
+    assert!(decrypt_message_with_aad(&enc.ciphertext, &enc.nonce, &key, b"chat-2|alice|42").is_err());
+    assert!(decrypt_message(&enc.ciphertext, &enc.nonce, &key).is_err());
+}
+
+#[test]
+fn test_encrypt_decrypt_file_and_data_with_aad() {
+    let key = generate_message_encryption_key().unwrap();
+    let file = encrypt_file_with_aad(&[9, 8, 7], "a.bin", "application/octet-stream", &key, b"msg-1").unwrap();
+    assert_eq!(decrypt_file_with_aad(&file.ciphertext, &file.nonce, &key, b"msg-1").unwrap(), vec![9, 8, 7]);
+    assert!(decrypt_file_with_aad(&file.ciphertext, &file.nonce, &key, b"msg-2").is_err());
+
+    let data = encrypt_data_with_aad("payload", &key, b"ctx").unwrap();
+    assert_eq!(decrypt_data_with_aad(&data, &key, b"ctx").unwrap(), "payload");
+    assert!(decrypt_data(&data, &key).is_err());
+}
+
+#[test]
+fn test_empty_aad_is_compatible_with_plain_functions() {
+    let key = generate_message_encryption_key().unwrap();
+    let enc = encrypt_message("old", &key).unwrap();
+    assert_eq!(decrypt_message_with_aad(&enc.ciphertext, &enc.nonce, &key, &[]).unwrap(), "old");
+    let data = encrypt_data_with_aad("new", &key, &[]).unwrap();
+    assert_eq!(decrypt_data(&data, &key).unwrap(), "new");
+}
+
+#[test]
+fn test_file_with_message_parts_do_not_share_keystream() {
+    // Регрессия: раньше файл и сообщение шифровались одним (key, nonce), и ct_file ^ ct_msg == pt_file ^ pt_msg.
+    let key = generate_message_encryption_key().unwrap();
+    let file = b"AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA".to_vec();
+    let message = "BBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBB";
+    let efm = encrypt_file_with_message(&file, message, &key, "f.bin", "application/octet-stream").unwrap();
+    assert_eq!(efm.version, FILE_WITH_MESSAGE_VERSION);
+    let ct_file = general_purpose::STANDARD.decode(&efm.enc_file).unwrap();
+    let ct_msg = general_purpose::STANDARD.decode(&efm.ciphertext).unwrap();
+    let ct_xor: Vec<u8> = ct_file.iter().zip(&ct_msg).take(file.len()).map(|(a, b)| a ^ b).collect();
+    let pt_xor: Vec<u8> = file.iter().zip(message.as_bytes()).map(|(a, b)| a ^ b).collect();
+    assert_ne!(ct_xor, pt_xor);
+}
+
+#[test]
+fn test_decrypt_file_with_message_reads_legacy_format() {
+    use chacha20poly1305::aead::Aead;
+    use chacha20poly1305::{ChaCha20Poly1305, KeyInit, Nonce};
+    let key = generate_message_encryption_key().unwrap();
+    let nonce = [7u8; 12];
+    let cipher = ChaCha20Poly1305::new(&key.to_bytes().into());
+    let enc_file = cipher.encrypt(&Nonce::from(nonce), b"legacy-file".as_ref()).unwrap();
+    let enc_msg = cipher.encrypt(&Nonce::from(nonce), b"legacy-msg".as_ref()).unwrap();
+    let legacy = EncryptedFileWithMessage {
+        enc_file: general_purpose::STANDARD.encode(enc_file),
+        ciphertext: general_purpose::STANDARD.encode(enc_msg),
+        nonce: general_purpose::STANDARD.encode(nonce),
+        filename: "f.bin".into(),
+        mimetype: "application/octet-stream".into(),
+        version: 0,
+    };
+    let out = decrypt_file_with_message(&legacy, &key).unwrap();
+    assert_eq!(out.file, b"legacy-file");
+    assert_eq!(out.message, "legacy-msg");
+
+    // JSON старого формата без поля version по-прежнему десериализуется
+    let json = r#"{"enc_file":"","ciphertext":"","nonce":"","filename":"f","mimetype":"m"}"#;
+    let old: EncryptedFileWithMessage = serde_json::from_str(json).unwrap();
+    assert_eq!(old.version, 0);
+}
+
+#[test]
+fn test_decrypt_file_with_message_dispatches_on_version() {
+    let key = generate_message_encryption_key().unwrap();
+    let efm = encrypt_file_with_message(b"file", "msg", &key, "f.bin", "application/octet-stream").unwrap();
+    // подмена версии не переключает на legacy-схему
+    let downgraded = EncryptedFileWithMessage { version: 0, ..efm.clone() };
+    assert!(matches!(decrypt_file_with_message(&downgraded, &key), Err(CryptoError::AuthenticationFailed)));
+    let future = EncryptedFileWithMessage { version: FILE_WITH_MESSAGE_VERSION + 1, ..efm };
+    assert!(matches!(decrypt_file_with_message(&future, &key), Err(CryptoError::UnsupportedVersion(_))));
 }
 
 #[test]
 fn test_wrap_unwrap_symmetric_key() {
-    let receiver = generate_key_pair(false);
+    let receiver = generate_key_pair(false).unwrap();
     let receiver_pk = &receiver.public_key;
     let receiver_sk = &receiver.private_key;
 
-    let msg_key = generate_message_encryption_key();
+    let msg_key = generate_message_encryption_key().unwrap();
 
-    let (wrapped, eph_pub, nonce) = wrap_symmetric_key(&msg_key, receiver_pk).unwrap();
-    let unwrapped = unwrap_symmetric_key(&wrapped, &eph_pub, &nonce, receiver_sk).unwrap();
+    let wrapped = wrap_symmetric_key(&msg_key, receiver_pk).unwrap();
+    let unwrapped = unwrap_symmetric_key(&wrapped, receiver_sk).unwrap();
 
     let enc = encrypt_message("secret-msg", &msg_key).unwrap();
     let dec = decrypt_message(&enc.ciphertext, &enc.nonce, &unwrapped).unwrap();
     assert_eq!(dec, "secret-msg");
 }
 
+#[test]
+fn test_wrap_unwrap_symmetric_key_authenticated() {
+    let sender = generate_key_pair(false).unwrap();
+    let receiver = generate_key_pair(false).unwrap();
+    let msg_key = generate_message_encryption_key().unwrap();
+
+    let wrapped = wrap_symmetric_key_authenticated(&msg_key, &sender.private_key, &receiver.public_key).unwrap();
+    let unwrapped = unwrap_symmetric_key_authenticated(&wrapped, &receiver.private_key, &sender.public_key).unwrap();
+    assert_eq!(unwrapped.to_bytes(), msg_key.to_bytes());
+}
+
+#[test]
+fn test_unwrap_symmetric_key_authenticated_rejects_wrong_sender() {
+    let sender = generate_key_pair(false).unwrap();
+    let mallory = generate_key_pair(false).unwrap();
+    let receiver = generate_key_pair(false).unwrap();
+    let msg_key = generate_message_encryption_key().unwrap();
+
+    // ключ, обёрнутый Mallory, не принимается как ключ от ожидаемого отправителя
+    let wrapped = wrap_symmetric_key_authenticated(&msg_key, &mallory.private_key, &receiver.public_key).unwrap();
+    let res = unwrap_symmetric_key_authenticated(&wrapped, &receiver.private_key, &sender.public_key);
+    assert!(res.is_err());
+    // и обычный unwrap также не подходит для аутентифицированной обёртки
+    assert!(unwrap_symmetric_key(&wrapped, &receiver.private_key).is_err());
+}
+
+#[test]
+fn test_wrapped_key_json_and_binary_roundtrip() {
+    let receiver = generate_key_pair(false).unwrap();
+    let msg_key = generate_message_encryption_key().unwrap();
+    let wrapped = wrap_symmetric_key(&msg_key, &receiver.public_key).unwrap();
+    assert_eq!(wrapped.version, WRAPPED_KEY_VERSION);
+
+    let json = serde_json::to_value(&wrapped).unwrap();
+    let keys: Vec<&str> = json.as_object().unwrap().keys().map(String::as_str).collect();
+    assert_eq!(keys.len(), 4);
+    for k in ["version", "wrapped_key", "ephemeral_public_key", "nonce"] {
+        assert!(keys.contains(&k), "{k}");
+    }
+    let from_json: WrappedKey = serde_json::from_value(json).unwrap();
+    assert_eq!(from_json, wrapped);
+
+    let bytes = wrapped.encode().unwrap();
+    assert_eq!(bytes.len(), 1 + 32 + 12 + 48);
+    assert_eq!(bytes[0], WRAPPED_KEY_VERSION);
+    let from_b64 = WrappedKey::from_b64(&wrapped.to_b64().unwrap()).unwrap();
+    assert_eq!(unwrap_symmetric_key(&from_b64, &receiver.private_key).unwrap().to_bytes(), msg_key.to_bytes());
+
+    let mut bad = bytes.clone();
+    bad[0] = 9;
+    assert!(matches!(WrappedKey::decode(&bad), Err(CryptoError::UnsupportedVersion(9))));
+    assert!(matches!(WrappedKey::decode(&bytes[..40]), Err(CryptoError::InvalidCiphertext(_))));
+    let mut future = wrapped.clone();
+    future.version = 2;
+    assert!(matches!(unwrap_symmetric_key(&future, &receiver.private_key), Err(CryptoError::UnsupportedVersion(2))));
+}
+
 #[test]
 fn test_wrapper_encrypt_decrypt_with_secret() {
     let enc = encrypt_message_with_secret("my-secret", "hello").unwrap();
@@ -114,12 +258,55 @@ fn test_wrapper_encrypt_decrypt_with_secret() {
     assert_eq!(dec, "hello");
 }
 
+#[test]
+#[allow(deprecated)]
+fn test_wrapper_with_secret_uses_new_kdf_and_reads_legacy() {
+    let enc = encrypt_message_with_secret("my-secret", "hello").unwrap();
+    let legacy_key = derive_key_from_string("my-secret").unwrap();
+    assert!(decrypt_message(&enc.ciphertext, &enc.nonce, &legacy_key).is_err());
+
+    // Сообщение, зашифрованное старой деривацией (SHA-256 от секрета), по-прежнему читается.
+    let legacy = encrypt_message("old", &legacy_key).unwrap();
+    assert_eq!(decrypt_message_with_secret("my-secret", &legacy.ciphertext, &legacy.nonce).unwrap(), "old");
+    assert!(decrypt_message_with_secret("other", &legacy.ciphertext, &legacy.nonce).is_err());
+}
+
 #[test]
 fn test_wrapper_derive_master_key_from_password() {
-    let salt_b64 = generate_salt();
-    let aead = derive_master_key_from_password("P@ssw0rd", &salt_b64).unwrap();
+    let salt_b64 = generate_salt().unwrap();
+    let master = derive_master_key_from_password("P@ssw0rd", &salt_b64).unwrap();
     let b64 = derive_master_key_b64_from_password("P@ssw0rd", &salt_b64).unwrap();
     let raw = general_purpose::STANDARD.decode(b64).unwrap();
-    assert_eq!(aead.to_bytes().len(), 32);
-    assert_eq!(raw.len(), 32);
+    assert_eq!(master.key.to_bytes().to_vec(), raw);
+    assert!(master.migration.is_some());
+}
+
+#[test]
+fn test_secrets_are_redacted_in_debug_and_keep_serde_format() {
+    let kp = generate_key_pair(true).unwrap();
+    let debug = format!("{kp:?}");
+    assert!(debug.contains(&kp.public_key.to_string()));
+    assert!(!debug.contains(kp.private_key.to_secret_b64().expose_secret()));
+    assert_eq!(format!("{:?}", generate_message_encryption_key().unwrap()), "AeadKey([REDACTED])");
+
+    // В JSON приватный ключ остаётся обычной Base64-строкой.
+    let json = serde_json::to_value(&kp).unwrap();
+    assert_eq!(json["private_key"], kp.private_key.to_secret_b64().expose_secret());
+    let restored: KeyPair = serde_json::from_value(json).unwrap();
+    assert_eq!(restored, kp);
+    assert_ne!(SecretString::from("a"), SecretString::from("b"));
+}
+
+#[test]
+fn test_errors_are_distinct_variants() {
+    let key = generate_message_encryption_key().unwrap();
+    let other = generate_message_encryption_key().unwrap();
+    let em = encrypt_message("hi", &key).unwrap();
+    assert!(matches!(decrypt_message(&em.ciphertext, &em.nonce, &other), Err(CryptoError::AuthenticationFailed)));
+    assert!(matches!(decrypt_message(&em.ciphertext, "AAAA", &key), Err(CryptoError::InvalidNonceLen(3))));
+    assert!(matches!(decrypt_data("AAAA", &key), Err(CryptoError::InvalidCiphertext(_))));
+
+    // Байты, не являющиеся UTF-8, расшифровываются как файл, но не как сообщение.
+    let ef = encrypt_file(&[0xff, 0xfe], "a.bin", "application/octet-stream", &key).unwrap();
+    assert!(matches!(decrypt_message(&ef.ciphertext, &ef.nonce, &key), Err(CryptoError::InvalidUtf8(_))));
 }
@@ -0,0 +1,108 @@
+use ren_sdk::crypto::devices::*;
+use ren_sdk::crypto::identity::*;
+use ren_sdk::crypto::*;
+
+struct Account {
+    id: &'static str,
+    identity: IdentityKeyPair,
+    devices: Vec<Device>,
+    list: DeviceList,
+}
+
+fn account(id: &'static str, device_ids: &[&str]) -> Account {
+    let identity = generate_identity_key_pair().unwrap();
+    let devices: Vec<Device> = device_ids.iter().map(|d| Device::generate(d).unwrap()).collect();
+    let list = DeviceList::new(id, &identity, devices.iter().map(Device::info).collect()).unwrap();
+    Account { id, identity, devices, list }
+}
+
+#[test]
+fn test_device_list_is_signed_by_account_identity() {
+    let alice = account("alice", &["phone", "laptop"]);
+    alice.list.verify().unwrap();
+    assert_eq!(alice.list.generation, 1);
+    assert_eq!(alice.list.device("laptop").unwrap(), &alice.devices[1].info());
+
+    let mut forged = alice.list.clone();
+    forged.devices.push(Device::generate("evil").unwrap().info());
+    assert!(matches!(forged.verify(), Err(CryptoError::InvalidSignature)));
+
+    // список, переподписанный чужим ключом идентичности, не выдаётся за список alice
+    let mallory = generate_identity_key_pair().unwrap();
+    let mut list = alice.list.clone();
+    assert!(matches!(list.add_device(Device::generate("evil").unwrap().info(), &mallory), Err(CryptoError::KeyPairMismatch)));
+    assert!(matches!(DeviceList::new("a", &mallory, vec![alice.devices[0].info(), alice.devices[0].info()]), Err(CryptoError::Devices(_))));
+    assert!(matches!(Device::generate("a/b"), Err(CryptoError::Devices(_))));
+}
+
+#[test]
+fn test_fan_out_reaches_every_device() {
+    let alice = account("alice", &["phone", "laptop"]);
+    let bob = account("bob", &["phone", "tablet", "desktop"]);
+    let mut directory = DeviceDirectory::new();
+    directory.accept(alice.list.clone()).unwrap();
+    directory.accept(bob.list.clone()).unwrap();
+
+    let key = generate_message_encryption_key().unwrap();
+    let keys = directory.wrap_for_accounts(&key, &["alice", "bob"]).unwrap();
+    assert_eq!(keys.recipients.len(), 5);
+    for acc in [&alice, &bob] {
+        for device in &acc.devices {
+            assert_eq!(unwrap_for_device(&keys, acc.id, device).unwrap().to_bytes(), key.to_bytes());
+        }
+    }
+    // устройство не разворачивает запись другого аккаунта с тем же device_id
+    assert!(unwrap_for_device(&keys, "bob", &alice.devices[0]).is_err());
+    assert!(matches!(directory.wrap_for_accounts(&key, &["carol"]), Err(CryptoError::Devices(_))));
+
+    let keys = wrap_for_device_lists(&key, &[&bob.list]).unwrap();
+    assert_eq!(keys.recipients.len(), 3);
+}
+
+#[test]
+fn test_add_and_revoke_update_directory() {
+    let mut alice = account("alice", &["phone"]);
+    let mut directory = DeviceDirectory::new();
+    let update = directory.accept(alice.list.clone()).unwrap();
+    assert_eq!(update.added.len(), 1);
+    let old_list = alice.list.clone();
+
+    let laptop = Device::generate("laptop").unwrap();
+    alice.list.add_device(laptop.info(), &alice.identity).unwrap();
+    assert_eq!(alice.list.generation, 2);
+    let update = directory.accept(alice.list.clone()).unwrap();
+    assert_eq!(update, DeviceListUpdate { added: vec![laptop.info()], revoked: vec![] });
+
+    let revoked = alice.list.revoke_device("phone", &alice.identity).unwrap();
+    assert_eq!(revoked, alice.devices[0].info());
+    let update = directory.accept(alice.list.clone()).unwrap();
+    assert_eq!(update, DeviceListUpdate { added: vec![], revoked: vec![revoked] });
+
+    // отозванное устройство больше не получает ключи
+    let key = generate_message_encryption_key().unwrap();
+    let keys = directory.wrap_for_accounts(&key, &["alice"]).unwrap();
+    assert!(matches!(unwrap_for_device(&keys, "alice", &alice.devices[0]), Err(CryptoError::Recipients(_))));
+    assert_eq!(unwrap_for_device(&keys, "alice", &laptop).unwrap().to_bytes(), key.to_bytes());
+
+    // старая редакция (с отозванным устройством) и повтор текущей отклоняются
+    assert!(matches!(directory.accept(old_list), Err(CryptoError::Devices(_))));
+    assert!(matches!(directory.accept(alice.list.clone()), Err(CryptoError::Devices(_))));
+    assert!(matches!(alice.list.revoke_device("phone", &alice.identity), Err(CryptoError::Devices(_))));
+}
+
+#[test]
+fn test_directory_rejects_identity_change() {
+    let alice = account("alice", &["phone"]);
+    let mut directory = DeviceDirectory::new();
+    directory.accept(alice.list.clone()).unwrap();
+
+    let impostor = generate_identity_key_pair().unwrap();
+    let mut list = DeviceList::new("alice", &impostor, vec![Device::generate("phone").unwrap().info()]).unwrap();
+    list.add_device(Device::generate("laptop").unwrap().info(), &impostor).unwrap();
+    assert!(matches!(directory.accept(list), Err(CryptoError::Devices(_))));
+    assert_eq!(directory.list("alice").unwrap(), &alice.list);
+
+    let json = serde_json::to_string(&directory).unwrap();
+    let restored: DeviceDirectory = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, directory);
+}
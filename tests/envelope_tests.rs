@@ -0,0 +1,120 @@
+use ren_sdk::crypto::*;
+use ren_sdk::crypto::envelope::*;
+use base64::{engine::general_purpose, Engine as _};
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit, Nonce};
+
+#[test]
+fn test_envelope_encode_decode_roundtrip() {
+    let key = generate_message_encryption_key().unwrap();
+    let env = seal_envelope(b"payload", &key, b"key-7", b"chat-1").unwrap();
+    let bytes = env.encode().unwrap();
+    assert!(is_envelope(&bytes));
+    assert_eq!(bytes[4], ENVELOPE_VERSION);
+    assert_eq!(bytes[5], CipherSuite::ChaCha20Poly1305.id());
+
+    let parsed = Envelope::decode(&bytes).unwrap();
+    assert_eq!(parsed, env);
+    assert_eq!(parsed.key_id, b"key-7");
+    assert_eq!(parsed.aad_len, 6);
+    assert_eq!(open_envelope(&parsed, &key, b"chat-1").unwrap(), b"payload");
+    assert!(open_envelope(&parsed, &key, b"chat-2").is_err());
+    assert!(open_envelope(&parsed, &key, b"").is_err());
+}
+
+#[test]
+fn test_existing_functions_read_envelope_and_legacy() {
+    let key = generate_message_encryption_key().unwrap();
+    let env = encrypt_data_envelope("hello", &key, b"k1").unwrap();
+    let legacy = encrypt_data("hello", &key).unwrap();
+    assert_eq!(decrypt_data(&env, &key).unwrap(), "hello");
+    assert_eq!(decrypt_data(&legacy, &key).unwrap(), "hello");
+
+    let msg = encrypt_message_envelope("msg", &key, b"k1").unwrap();
+    assert_eq!(decrypt_message(&msg.ciphertext, &msg.nonce, &key).unwrap(), "msg");
+    let legacy_msg = encrypt_message("msg", &key).unwrap();
+    assert_eq!(decrypt_message(&legacy_msg.ciphertext, &legacy_msg.nonce, &key).unwrap(), "msg");
+
+    let file = encrypt_file_envelope(&[1, 2, 3], "a.bin", "application/octet-stream", &key, b"").unwrap();
+    assert_eq!(decrypt_file(&file.ciphertext, &file.nonce, &key).unwrap(), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_envelope_header_is_authenticated() {
+    let key = generate_message_encryption_key().unwrap();
+    let mut bytes = seal_envelope(b"payload", &key, b"key-1", b"").unwrap().encode().unwrap();
+    // key id "key-1" -> "key-2"
+    bytes[7 + 4] ^= 0x03;
+    let env = Envelope::decode(&bytes).unwrap();
+    assert_eq!(env.key_id, b"key-2");
+    assert!(open_envelope(&env, &key, b"").is_err());
+}
+
+#[test]
+fn test_envelope_decode_rejects_malformed_input() {
+    let key = generate_message_encryption_key().unwrap();
+    let bytes = seal_envelope(b"payload", &key, b"", b"").unwrap().encode().unwrap();
+
+    let mut unknown_suite = bytes.clone();
+    unknown_suite[5] = 0x7F;
+    assert!(matches!(Envelope::decode(&unknown_suite), Err(CryptoError::UnsupportedSuite(0x7F))));
+
+    let mut future_version = bytes.clone();
+    future_version[4] = 9;
+    assert!(matches!(Envelope::decode(&future_version), Err(CryptoError::UnsupportedVersion(_))));
+
+    assert!(matches!(Envelope::decode(&bytes[..10]), Err(CryptoError::InvalidEnvelope(_))));
+    assert!(matches!(Envelope::decode(b"nope"), Err(CryptoError::InvalidEnvelope(_))));
+}
+
+#[test]
+fn test_envelope_errors_are_reported_when_legacy_also_fails() {
+    let key = generate_message_encryption_key().unwrap();
+    let other = generate_message_encryption_key().unwrap();
+    let env = encrypt_data_envelope("hello", &key, b"k1").unwrap();
+    assert!(matches!(decrypt_data(&env, &other), Err(CryptoError::AuthenticationFailed)));
+
+    let mut bytes = general_purpose::STANDARD.decode(&env).unwrap();
+    bytes[4] = 9;
+    let future = general_purpose::STANDARD.encode(&bytes);
+    assert!(matches!(decrypt_data(&future, &key), Err(CryptoError::UnsupportedVersion(9))));
+    assert!(matches!(decrypt_message(&future, "", &key), Err(CryptoError::UnsupportedVersion(9))));
+    bytes[4] = ENVELOPE_VERSION;
+    bytes[5] = 0x7F;
+    let unknown = general_purpose::STANDARD.encode(&bytes);
+    assert!(matches!(decrypt_file(&unknown, "", &key), Err(CryptoError::UnsupportedSuite(0x7F))));
+}
+
+#[test]
+fn test_legacy_ciphertext_with_magic_nonce_still_decrypts() {
+    // nonce legacy-шифртекста случайный, поэтому может начинаться с "RENE" (и даже выглядеть как заголовок v1)
+    let key = generate_message_encryption_key().unwrap();
+    let cipher = ChaCha20Poly1305::new(&key.to_bytes().into());
+    let mut nonce = [0u8; 12];
+    nonce[..4].copy_from_slice(&ENVELOPE_MAGIC);
+    nonce[4] = ENVELOPE_VERSION;
+    nonce[5] = CipherSuite::ChaCha20Poly1305.id();
+    let ct = cipher.encrypt(&Nonce::from(nonce), b"legacy".as_ref()).unwrap();
+
+    let blob = general_purpose::STANDARD.encode([nonce.as_slice(), &ct].concat());
+    assert_eq!(decrypt_data(&blob, &key).unwrap(), "legacy");
+
+    // в decrypt_message/decrypt_file nonce отдельно, magic проверяется у самого шифртекста:
+    // подбираем открытый текст так, чтобы шифртекст начинался с "RENE"
+    let nonce = [9u8; 12];
+    let keystream = cipher.encrypt(&Nonce::from(nonce), [0u8; 8].as_ref()).unwrap();
+    let pt: Vec<u8> = keystream[..8].iter().zip(b"RENE\x01\x01\x00\x0c").map(|(k, m)| k ^ m).collect();
+    let ct = cipher.encrypt(&Nonce::from(nonce), pt.as_ref()).unwrap();
+    assert!(ct.starts_with(&ENVELOPE_MAGIC));
+    let (ct_b64, nonce_b64) = (general_purpose::STANDARD.encode(&ct), general_purpose::STANDARD.encode(nonce));
+    assert_eq!(decrypt_file(&ct_b64, &nonce_b64, &key).unwrap(), pt);
+}
+
+#[test]
+fn test_envelope_header_rejects_oversized_lengths() {
+    let key = generate_message_encryption_key().unwrap();
+    assert!(matches!(seal_envelope(b"x", &key, &[0u8; 256], b""), Err(CryptoError::InvalidEnvelope(_))));
+    let mut env = seal_envelope(b"x", &key, b"", b"").unwrap();
+    env.key_id = vec![0u8; 256];
+    assert!(matches!(env.encode(), Err(CryptoError::InvalidEnvelope(_))));
+}
@@ -0,0 +1,69 @@
+use ren_sdk::crypto::fingerprint::*;
+use ren_sdk::crypto::*;
+
+fn key(byte: u8) -> PublicKey {
+    PrivateKey::from_bytes(&[byte; 32]).unwrap().public_key()
+}
+
+#[test]
+fn test_safety_number_is_symmetric_and_stable() {
+    let (alice, bob) = (key(1), key(2));
+    let at_alice = SafetyNumber::new("alice", &alice, "bob", &bob);
+    let at_bob = SafetyNumber::new("bob", &bob, "alice", &alice);
+    assert_eq!(at_alice.digits(), at_bob.digits());
+    assert_eq!(at_alice.digits().len(), 60);
+    assert!(at_alice.digits().chars().all(|c| c.is_ascii_digit()));
+    assert_eq!(at_alice.to_string().split(' ').count(), 12);
+
+    // Известное значение фиксирует формат: другие клиенты должны получить то же число.
+    assert_eq!(at_alice.to_string(), "16167 54139 32946 35632 54448 82413 49060 05013 83826 27280 83149 65223");
+
+    assert!(at_bob.matches(&at_alice.to_string()));
+    assert!(!at_bob.matches(&SafetyNumber::new("alice", &key(3), "bob", &bob).digits()));
+    assert_ne!(SafetyNumber::new("mallory", &alice, "bob", &bob).digits(), at_alice.digits());
+}
+
+#[test]
+fn test_qr_payload_scan() {
+    let (alice, bob) = (key(1), key(2));
+    let at_alice = SafetyNumber::new("alice", &alice, "bob", &bob);
+    let at_bob = SafetyNumber::new("bob", &bob, "alice", &alice);
+    assert!(at_bob.verify_qr_payload(&at_alice.qr_payload()).unwrap());
+    assert!(at_alice.verify_qr_payload(&at_bob.qr_payload()).unwrap());
+    // Свой же код или код с подменённым ключом не проходят.
+    assert!(!at_alice.verify_qr_payload(&at_alice.qr_payload()).unwrap());
+    let mitm = SafetyNumber::new("bob", &key(3), "alice", &alice);
+    assert!(!at_alice.verify_qr_payload(&mitm.qr_payload()).unwrap());
+
+    assert!(matches!(at_alice.verify_qr_payload("AQ=="), Err(CryptoError::UnsupportedVersion(1))));
+    assert!(matches!(at_alice.verify_qr_payload("AAAA"), Err(CryptoError::InvalidFingerprint(_))));
+    assert!(matches!(at_alice.verify_qr_payload(""), Err(CryptoError::InvalidFingerprint(_))));
+}
+
+#[test]
+fn test_verification_state_flips_to_changed() {
+    let mut store = VerificationStore::new();
+    let bob = key(2);
+    assert_eq!(store.state("bob"), None);
+    assert_eq!(store.observe_key("bob", bob), VerificationState::Unverified);
+    store.mark_verified("bob", &bob);
+    assert_eq!(store.state("bob"), Some(VerificationState::Verified));
+
+    // Ключ, пришедший с сервера в Base64, сравнивается с подтверждённым.
+    let same = import_public_key_b64(&bob.to_string()).unwrap();
+    assert_eq!(store.observe_key("bob", same), VerificationState::Verified);
+    let rotated = import_public_key_b64(&key(4).to_string()).unwrap();
+    assert_eq!(store.observe_key("bob", rotated), VerificationState::Changed);
+
+    // Состояние переживает сохранение.
+    let json = serde_json::to_string(&store).unwrap();
+    let mut restored: VerificationStore = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, store);
+    assert_eq!(restored.state("bob"), Some(VerificationState::Changed));
+    assert_eq!(restored.contact("bob").unwrap().verified_key, Some(bob));
+
+    restored.mark_verified("bob", &key(4));
+    assert_eq!(restored.state("bob"), Some(VerificationState::Verified));
+    restored.clear_verification("bob");
+    assert_eq!(restored.state("bob"), Some(VerificationState::Unverified));
+}
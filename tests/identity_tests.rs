@@ -0,0 +1,56 @@
+use ren_sdk::crypto::*;
+use ren_sdk::crypto::identity::*;
+use ren_sdk::{generate_identity_key_pair_b64, signing_public_key_to_x25519_b64, verify_signature_b64};
+use ren_sdk::crypto::keys::{SigningPrivateKey, SigningPublicKey};
+
+#[test]
+fn test_identity_sign_verify() {
+    let id = generate_identity_key_pair().unwrap();
+    let sig = id.sign(b"device-list");
+    assert!(verify_signature(b"device-list", &sig, &id.public_key).is_ok());
+    assert!(matches!(verify_signature(b"device-list!", &sig, &id.public_key), Err(CryptoError::InvalidSignature)));
+    let other = generate_identity_key_pair().unwrap();
+    assert!(verify_signature(b"device-list", &sig, &other.public_key).is_err());
+}
+
+#[test]
+fn test_identity_b64_roundtrip() {
+    let id = generate_identity_key_pair().unwrap();
+    let pk: SigningPublicKey = id.public_key.to_string().parse().unwrap();
+    let sk: SigningPrivateKey = id.private_key.to_secret_b64().expose_secret().parse().unwrap();
+    assert_eq!(pk, id.public_key);
+    assert_eq!(sk, id.private_key);
+    assert_eq!(sk.public_key(), id.public_key);
+    assert!("AAAA".parse::<SigningPublicKey>().is_err());
+    assert!(format!("{:?}", id.private_key).contains("REDACTED"));
+}
+
+#[test]
+fn test_identity_b64_wrappers() {
+    let (pk_b64, sk_b64) = generate_identity_key_pair_b64().unwrap();
+    let sk: SigningPrivateKey = sk_b64.expose_secret().parse().unwrap();
+    assert_eq!(sk.public_key().to_string(), pk_b64);
+    let sig = sk.sign(b"device-list");
+    assert!(verify_signature_b64(b"device-list", &sig, &pk_b64).is_ok());
+    assert!(verify_signature_b64(b"device-list!", &sig, &pk_b64).is_err());
+    assert!(verify_signature_b64(b"device-list", &sig, "AAAA").is_err());
+}
+
+#[test]
+fn test_identity_x25519_binding_matches_public_conversion() {
+    let id = generate_identity_key_pair().unwrap();
+    let kp = id.to_x25519_key_pair();
+    assert_eq!(id.public_key.to_x25519(), kp.public_key);
+    assert_eq!(signing_public_key_to_x25519_b64(&id.public_key.to_string()).unwrap(), kp.public_key.to_string());
+}
+
+#[test]
+fn test_identity_x25519_key_works_with_wrap_symmetric_key() {
+    let id = generate_identity_key_pair().unwrap();
+    let receiver_pk = id.public_key.to_x25519();
+    let receiver_sk = id.to_x25519_key_pair().private_key;
+    let key = generate_message_encryption_key().unwrap();
+    let wrapped = wrap_symmetric_key(&key, &receiver_pk).unwrap();
+    let unwrapped = unwrap_symmetric_key(&wrapped, &receiver_sk).unwrap();
+    assert_eq!(unwrapped.to_bytes(), key.to_bytes());
+}
@@ -0,0 +1,159 @@
+use ren_sdk::crypto::*;
+use ren_sdk::crypto::identity::*;
+use ren_sdk::crypto::kdf::*;
+use ren_sdk::crypto::ratchet::RatchetSession;
+use ren_sdk::crypto::stream::{decrypt_stream, encrypt_stream};
+use ren_sdk::crypto::x3dh::*;
+use ren_sdk::{CryptoRng, OsRng, SeededRng, open_private_key, seal_private_key_with_params, with_rng};
+
+// Known-answer векторы: случайность берётся из `SeededRng::from_u64(n)`, ключи фиксированы.
+// Изменение любого значения означает изменение формата и несовместимость с уже выпущенными клиентами.
+
+fn seeded<T>(n: u64, f: impl FnOnce() -> T) -> T {
+    with_rng(SeededRng::from_u64(n), f)
+}
+
+fn key() -> AeadKey {
+    AeadKey::from_bytes(&[0x42; 32]).unwrap()
+}
+
+fn receiver() -> KeyPair {
+    let private_key = PrivateKey::from_bytes(&[0x07; 32]).unwrap();
+    KeyPair { public_key: private_key.public_key(), private_key }
+}
+
+#[test]
+fn test_seeded_rng_is_deterministic_and_scoped() {
+    let a = seeded(1, || (generate_nonce().unwrap(), generate_nonce().unwrap()));
+    let b = seeded(1, || (generate_nonce().unwrap(), generate_nonce().unwrap()));
+    assert_eq!(a, b);
+    assert_ne!(a.0, a.1);
+    assert_ne!(seeded(2, || generate_nonce().unwrap()), a.0);
+
+    // Вложенный источник действует только внутри своего замыкания.
+    seeded(1, || {
+        let inner = seeded(2, || generate_nonce().unwrap());
+        assert_eq!(inner, seeded(2, || generate_nonce().unwrap()));
+        assert_eq!(generate_nonce().unwrap(), a.0);
+    });
+    assert_ne!(generate_nonce().unwrap(), a.0);
+    assert_ne!(with_rng(OsRng, || generate_nonce().unwrap()), a.0);
+}
+
+#[test]
+fn test_rng_override_survives_panic_in_source() {
+    // Первый вызов паникует; источник должен вернуться в слот, а не смениться на `OsRng`.
+    struct PanicOnce(bool);
+    impl CryptoRng for PanicOnce {
+        fn fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), CryptoError> {
+            if !std::mem::replace(&mut self.0, true) {
+                panic!("rng failure");
+            }
+            dest.fill(0x42);
+            Ok(())
+        }
+    }
+    with_rng(PanicOnce(false), || {
+        assert!(std::panic::catch_unwind(Nonce::generate).is_err());
+        assert_eq!(Nonce::generate().unwrap().as_bytes(), &[0x42; 12]);
+    });
+}
+
+#[test]
+fn test_kat_random_values() {
+    let kp = seeded(1, || generate_key_pair(true).unwrap());
+    assert_eq!(kp.public_key.to_string(), "dSLfhW28MGCVBzqTtwsZhiOws93g+C7zL2ZdjG9uIDo=");
+    assert_eq!(kp.private_key.to_secret_b64().expose_secret(), "xdMKfOHsEZN4yE9IfXdahULxPs4jipRV6CKeiI3oW70=");
+    assert_eq!(seeded(1, || generate_nonce().unwrap()), "xdMKfOHsEZN4yE9I");
+    assert_eq!(seeded(1, || generate_salt().unwrap()), "xdMKfOHsEZN4yE9IfXdahQ==");
+    assert_eq!(receiver().public_key.to_string(), "E75P6uryBMf9M1j8nAByGIHRdCeBKCJ+xnTzf3/pe20=");
+}
+
+#[test]
+fn test_kat_symmetric_formats() {
+    let key = key();
+    let data = seeded(3, || encrypt_data("hello", &key).unwrap());
+    assert_eq!(data, "gFEMhxTeneCFSqwoCHhLnyuYkxAPMYQa5CGZDCz6L28A");
+    assert_eq!(decrypt_data(&data, &key).unwrap(), "hello");
+
+    let msg = seeded(4, || encrypt_message("hello", &key).unwrap());
+    assert_eq!(msg.ciphertext, "nl1cmq16bMGNXA1drTFpBzfeAqIk");
+    assert_eq!(msg.nonce, "kON9GeNx8RjHOAXY");
+
+    let efm = seeded(5, || encrypt_file_with_message(b"file", "caption", &key, "a.txt", "text/plain").unwrap());
+    assert_eq!(efm.enc_file, "B4SWvJczKFHsyjXqG+B1CipGwCc=");
+    assert_eq!(efm.ciphertext, "0VTbHj9Vk/2ikX+TZK2WW7HdntWvjDg=");
+    assert_eq!(efm.nonce, "nXHxDaYefUMhXZld");
+
+    let env = seeded(6, || encrypt_data_envelope("hello", &key, b"k1").unwrap());
+    assert_eq!(env, "UkVORQEBAmsxDAqMQrvuQmrGMyajfwAAAAD2A4Uiud4s4g6j6sa1XcHUxQ0U0Gk=");
+    assert_eq!(decrypt_data(&env, &key).unwrap(), "hello");
+
+    let mut stream = Vec::new();
+    let info = seeded(7, || encrypt_stream(&b"0123456789abcdefXYZ"[..], &mut stream, &key, "s.bin", "application/octet-stream", 16).unwrap());
+    assert_eq!(info.nonce_prefix, "8Z7juWVCmA==");
+    assert_eq!(base64_encode(&stream), "MTU0mH/8XLNqZgvAuh5TMLJw/7ngZoTDEi9T5qZhKHo7R0kBwUqLi3c+vIZnVz+lK16a");
+    let mut out = Vec::new();
+    decrypt_stream(&stream[..], &mut out, &key, &info).unwrap();
+    assert_eq!(out, b"0123456789abcdefXYZ");
+}
+
+#[test]
+fn test_kat_key_wrapping() {
+    let key = key();
+    let recv = receiver();
+    let wrapped = seeded(8, || wrap_symmetric_key(&key, &recv.public_key).unwrap());
+    assert_eq!(wrapped.wrapped_key, "s3IaabAwUINtbG2c9vhIpZiZWw6gEIjYlzG9kXAGmofPp0S+Ah33vQ8UBnPbabuw");
+    assert_eq!(wrapped.ephemeral_public_key.to_string(), "IJhOJdLgVO1bR3Wf/BK6n+JrUkoobXYUPpzIkvoJp0M=");
+    assert_eq!(wrapped.nonce.to_string(), "Cnti5NMgsoJvjgvJ");
+    assert_eq!(unwrap_symmetric_key(&wrapped, &recv.private_key).unwrap().to_bytes(), key.to_bytes());
+
+    let sender = PrivateKey::from_bytes(&[0x09; 32]).unwrap();
+    let wrapped = seeded(9, || wrap_symmetric_key_authenticated(&key, &sender, &recv.public_key).unwrap());
+    assert_eq!(wrapped.wrapped_key, "RE+MewF3BKCFCIayexhOD3KXit9gZwWCdIQQgAOIrB+8c1GBmwtaD7hdh0npdVoI");
+    assert_eq!(wrapped.ephemeral_public_key.to_string(), "+qLQqqzR//WbSzoPfj5uw62yvJ71z2VPezgTEVWuRw8=");
+    assert_eq!(wrapped.nonce.to_string(), "zPQ6ks3HooGOPgx7");
+    let unwrapped = unwrap_symmetric_key_authenticated(&wrapped, &recv.private_key, &sender.public_key()).unwrap();
+    assert_eq!(unwrapped.to_bytes(), key.to_bytes());
+}
+
+#[test]
+fn test_kat_kdf_and_backup() {
+    let salt: Salt = "c2FsdHNhbHRzYWx0c2FsdA==".parse().unwrap();
+    let pbkdf2 = derive_key_from_password("P@ssw0rd", &salt).unwrap();
+    assert_eq!(base64_encode(&pbkdf2.to_bytes()), "8FHmmBZzFWwHGy+uYB5aSIk8xAfLnSrU5Dene6ubeBA=");
+    let params = KdfParams::Argon2id { memory_kib: MIN_ARGON2_MEMORY_KIB, iterations: MIN_ARGON2_ITERATIONS, parallelism: 1 };
+    assert_eq!(base64_encode(&params.derive("P@ssw0rd", &salt).unwrap().to_bytes()), "3SlrHBy1ln52z41EdOcnh51yC9RCURwAXuUpbxoMAAw=");
+    let hkdf = derive_key_from_secret("my-secret", "ren-sdk-test", Some(&salt)).unwrap();
+    assert_eq!(base64_encode(&hkdf.to_bytes()), "XwgbHvUxVuQVx+DQo7yVTbu2CnYIp6S2917oACdO+sE=");
+
+    let backup = seeded(10, || seal_private_key_with_params(&receiver(), "P@ssw0rd", params).unwrap());
+    assert_eq!(serde_json::to_string(&backup).unwrap(), r#"{"version":1,"public_key":"E75P6uryBMf9M1j8nAByGIHRdCeBKCJ+xnTzf3/pe20=","kdf":{"salt":"+tFFgyV6t7SP0VnhKo6LzA==","params":{"alg":"argon2id","memory_kib":19456,"iterations":2,"parallelism":1},"verifier":"vDkUMGM4OK6pY4MSq4X9ln66ZSLv6CnKA8NvvT6YfEU="},"nonce":"5SrH0A/yvX3qPc9I","ciphertext":"85DrfRBSW9kYyJkJvQ2ayshOkFEIfHQH0Ioi9WEgcQIcl1BivAf8/6MCRukIBbiW"}"#);
+    assert_eq!(open_private_key(&backup, "P@ssw0rd").unwrap(), receiver());
+}
+
+#[test]
+fn test_kat_identity_x3dh_and_ratchet() {
+    let alice = seeded(11, || generate_identity_key_pair().unwrap());
+    assert_eq!(alice.public_key.to_string(), "LbNWSZghOxQL90ADU26FDa7cxHAlyl87u3cItGy4lzo=");
+    assert_eq!(alice.sign(b"ren"), "mTIbUkHzfyjFvoFcnpR8I0v+XjPD4QJVd/6/ETd8HrwUtYOfICMPTD2A2TQ0MshK4cyVDe7+xWWayiCCysr8DA==");
+
+    let mut bob = seeded(12, || PreKeyStore::new(generate_identity_key_pair().unwrap(), 1).unwrap());
+    let bundle = bob.bundle();
+    assert_eq!(serde_json::to_string(&bundle).unwrap(), r#"{"identity_key":"98SMLiueLgzcS6jEpGNufJI7XVAjaXgcDOYZ3rkOvT4=","signed_prekey_id":0,"signed_prekey":"QdcIhXtHYXwlGIJQdOFnyi6hjVCI9Y4u0MXBPH1aQSI=","signed_prekey_signature":"BjBCajrk0WDmW5hmgFrXV9Xfudl/pE94MDBwJsXBiODYVBVB66YMJywwblH/7rPr5Hi/obMqZ8cMLz6QSQumCA==","one_time_prekey_id":1,"one_time_prekey":"iGXzjRbZqDEaPaeG4MuvmM5Pqs4beeBXeD2mrZ01QGE="}"#);
+    let (alice_out, msg) = seeded(13, || x3dh_initiate(&alice, &bundle, b"hi").unwrap());
+    assert_eq!(serde_json::to_string(&msg).unwrap(), r#"{"identity_key":"LbNWSZghOxQL90ADU26FDa7cxHAlyl87u3cItGy4lzo=","ephemeral_key":"lSv60akzU9cyBPAVPfVQeXNybhSu/1sC4hR/gFPtEDo=","signed_prekey_id":0,"one_time_prekey_id":1,"ciphertext":"CDOGAKcT8BTSmsU9ZFIxn38B","nonce":"PzEfd8AT4iHqIwVO"}"#);
+    let (bob_out, plaintext) = bob.accept(&msg).unwrap();
+    assert_eq!(plaintext, b"hi");
+
+    let mut a = seeded(14, || RatchetSession::init_initiator(&alice_out.shared_secret, &bundle.signed_prekey, &alice_out.associated_data).unwrap());
+    let m = seeded(15, || a.encrypt("hello").unwrap());
+    assert_eq!(serde_json::to_string(&m).unwrap(), r#"{"header":{"dh_public_key":"jm3Z2KoEO2rFq1/tCstwKRSprC0ttqfRZankrCgrJHg=","previous_chain_length":0,"message_number":0},"ciphertext":"4yvp9CC5AisGL/wklOESF+lYaMlb","nonce":"2FJ4O4w7t5CRHtss"}"#);
+    let mut b = RatchetSession::init_responder(&bob_out.shared_secret, bob.signed_prekey.key_pair.clone(), &bob_out.associated_data);
+    assert_eq!(b.decrypt(&m).unwrap(), "hello");
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    use base64::Engine as _;
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
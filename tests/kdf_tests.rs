@@ -0,0 +1,122 @@
+use ren_sdk::crypto::kdf::*;
+use ren_sdk::crypto::*;
+use ren_sdk::{derive_master_key_from_password, derive_master_key_from_password_kdf};
+
+// Минимально допустимые параметры, чтобы тесты оставались быстрыми.
+fn fast_argon2() -> KdfParams {
+    KdfParams::Argon2id { memory_kib: MIN_ARGON2_MEMORY_KIB, iterations: MIN_ARGON2_ITERATIONS, parallelism: 1 }
+}
+
+#[test]
+fn test_argon2id_derivation_is_deterministic_per_params() {
+    let salt = Salt::generate().unwrap();
+    let a = fast_argon2().derive("pw", &salt).unwrap();
+    assert_eq!(a.to_bytes(), fast_argon2().derive("pw", &salt).unwrap().to_bytes());
+    assert_ne!(a.to_bytes(), fast_argon2().derive("pw2", &salt).unwrap().to_bytes());
+    assert_ne!(a.to_bytes(), fast_argon2().derive("pw", &Salt::generate().unwrap()).unwrap().to_bytes());
+    let slower = KdfParams::Argon2id { memory_kib: MIN_ARGON2_MEMORY_KIB, iterations: MIN_ARGON2_ITERATIONS + 1, parallelism: 1 };
+    assert_ne!(a.to_bytes(), slower.derive("pw", &salt).unwrap().to_bytes());
+
+    // Legacy-параметры дают тот же ключ, что и derive_key_from_password.
+    let legacy = KdfParams::legacy_pbkdf2().derive("pw", &salt).unwrap();
+    assert_eq!(legacy.to_bytes(), derive_key_from_password("pw", &salt).unwrap().to_bytes());
+}
+
+#[test]
+fn test_password_kdf_record_verifies_password() {
+    let (record, key) = PasswordKdf::create("correct horse", fast_argon2()).unwrap();
+    let json = serde_json::to_string(&record).unwrap();
+    assert!(json.contains("\"alg\":\"argon2id\""));
+    let stored: PasswordKdf = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(stored.derive("correct horse").unwrap().to_bytes(), key.to_bytes());
+    assert!(matches!(stored.derive("battery staple"), Err(CryptoError::WrongPassword)));
+    let master = derive_master_key_from_password_kdf("correct horse", &stored).unwrap();
+    assert_eq!(master.key.to_bytes(), key.to_bytes());
+    // Запись с параметрами ниже текущих по умолчанию переводится на них при входе.
+    let migration = master.migration.unwrap();
+    assert_eq!(migration.kdf.params, KdfParams::default());
+    assert!(derive_master_key_from_password_kdf("correct horse", &migration.kdf).unwrap().migration.is_none());
+}
+
+#[test]
+fn test_kdf_params_are_validated() {
+    let salt = Salt::generate().unwrap();
+    let huge = KdfParams::Argon2id { memory_kib: MAX_ARGON2_MEMORY_KIB + 1, iterations: 1, parallelism: 1 };
+    assert!(matches!(huge.derive("pw", &salt), Err(CryptoError::Kdf(_))));
+    let zero = KdfParams::Argon2id { memory_kib: MIN_ARGON2_MEMORY_KIB, iterations: 0, parallelism: 1 };
+    assert!(matches!(zero.derive("pw", &salt), Err(CryptoError::Kdf(_))));
+    assert!(matches!(KdfParams::Pbkdf2Sha256 { iterations: 0 }.derive("pw", &salt), Err(CryptoError::Kdf(_))));
+}
+
+#[test]
+fn test_kdf_params_below_minimum_are_rejected() {
+    let salt = Salt::generate().unwrap();
+    let small = KdfParams::Argon2id { memory_kib: MIN_ARGON2_MEMORY_KIB - 1, iterations: MIN_ARGON2_ITERATIONS, parallelism: 1 };
+    assert!(matches!(small.derive("pw", &salt), Err(CryptoError::Kdf(_))));
+    let single = KdfParams::Argon2id { memory_kib: MIN_ARGON2_MEMORY_KIB, iterations: MIN_ARGON2_ITERATIONS - 1, parallelism: 1 };
+    assert!(matches!(single.derive("pw", &salt), Err(CryptoError::Kdf(_))));
+    let pbkdf2 = KdfParams::Pbkdf2Sha256 { iterations: MIN_PBKDF2_ITERATIONS - 1 };
+    assert!(matches!(pbkdf2.derive("pw", &salt), Err(CryptoError::Kdf(_))));
+    assert!(fast_argon2().derive("pw", &salt).is_ok());
+    assert!(KdfParams::legacy_pbkdf2().derive("pw", &salt).is_ok());
+
+    // Подменённая запись на сервере не ослабляет деривацию.
+    let (mut record, _) = PasswordKdf::create("pw", fast_argon2()).unwrap();
+    record.params = KdfParams::Pbkdf2Sha256 { iterations: 1 };
+    assert!(matches!(derive_master_key_from_password_kdf("pw", &record), Err(CryptoError::Kdf(_))));
+}
+
+#[test]
+fn test_kdf_params_time_cost_is_bounded() {
+    let salt = Salt::generate().unwrap();
+    let slow = KdfParams::Argon2id { memory_kib: MIN_ARGON2_MEMORY_KIB, iterations: MAX_ARGON2_ITERATIONS + 1, parallelism: 1 };
+    assert!(matches!(slow.derive("pw", &salt), Err(CryptoError::Kdf(_))));
+    let wide = KdfParams::Argon2id { memory_kib: MIN_ARGON2_MEMORY_KIB, iterations: MIN_ARGON2_ITERATIONS, parallelism: MAX_ARGON2_PARALLELISM + 1 };
+    assert!(matches!(wide.derive("pw", &salt), Err(CryptoError::Kdf(_))));
+    let pbkdf2 = KdfParams::Pbkdf2Sha256 { iterations: MAX_PBKDF2_ITERATIONS + 1 };
+    assert!(matches!(pbkdf2.derive("pw", &salt), Err(CryptoError::Kdf(_))));
+}
+
+#[test]
+fn test_migrate_legacy_pbkdf2_user() {
+    let salt = generate_salt().unwrap();
+    let old_master = derive_key_from_password("pw", &salt.parse().unwrap()).unwrap();
+    let wrapped_private_key = encrypt_data("private key", &old_master).unwrap();
+
+    // Вход по одной соли: ключ остаётся прежним ключом PBKDF2, миграция подготовлена сразу.
+    let master = derive_master_key_from_password("pw", &salt).unwrap();
+    assert_eq!(master.key.to_bytes(), old_master.to_bytes());
+    let m = master.migration.unwrap();
+    assert_eq!(m.old_key.to_bytes(), old_master.to_bytes());
+    assert_eq!(m.kdf.params, KdfParams::default());
+    let private_key = decrypt_data(&wrapped_private_key, &master.key).unwrap();
+    let rewrapped = encrypt_data(&private_key, &m.new_key).unwrap();
+
+    // Следующий вход — по новой записи, миграция больше не нужна.
+    let key = m.kdf.derive("pw").unwrap();
+    assert_eq!(decrypt_data(&rewrapped, &key).unwrap(), "private key");
+    assert!(migrate_password_kdf("pw", &m.kdf, KdfParams::default()).unwrap().is_none());
+
+    let legacy_json = format!(r#"{{"salt":"{salt}","params":{{"alg":"pbkdf2_sha256","iterations":100000}}}}"#);
+    let legacy: PasswordKdf = serde_json::from_str(&legacy_json).unwrap();
+    assert_eq!(legacy, PasswordKdf::legacy_pbkdf2(salt.parse().unwrap()));
+    assert!(legacy.needs_migration(&fast_argon2()));
+}
+
+#[test]
+fn test_context_separated_secret_derivation() {
+    let a = derive_key_from_secret("token", "ren-sdk/chat", None).unwrap();
+    assert_eq!(a.to_bytes(), derive_key_from_secret("token", "ren-sdk/chat", None).unwrap().to_bytes());
+    assert_ne!(a.to_bytes(), derive_key_from_secret("token", "ren-sdk/files", None).unwrap().to_bytes());
+    let salt = Salt::generate().unwrap();
+    assert_ne!(a.to_bytes(), derive_key_from_secret("token", "ren-sdk/chat", Some(&salt)).unwrap().to_bytes());
+    assert!(matches!(derive_key_from_secret("token", "", None), Err(CryptoError::Kdf(_))));
+
+    let h = derive_key_from_human_secret("hunter2", "ren-sdk/chat", None, &fast_argon2()).unwrap();
+    assert_eq!(h.to_bytes(), derive_key_from_human_secret("hunter2", "ren-sdk/chat", None, &fast_argon2()).unwrap().to_bytes());
+    assert_ne!(h.to_bytes(), derive_key_from_human_secret("hunter2", "ren-sdk/other", None, &fast_argon2()).unwrap().to_bytes());
+    assert_ne!(h.to_bytes(), derive_key_from_human_secret("hunter2", "ren-sdk/chat", Some(&salt), &fast_argon2()).unwrap().to_bytes());
+    let pbkdf2 = KdfParams::legacy_pbkdf2();
+    assert_ne!(h.to_bytes(), derive_key_from_human_secret("hunter2", "ren-sdk/chat", None, &pbkdf2).unwrap().to_bytes());
+}
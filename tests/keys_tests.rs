@@ -0,0 +1,55 @@
+use ren_sdk::crypto::*;
+use ren_sdk::{unwrap_symmetric_key_b64, wrap_symmetric_key_b64};
+
+#[test]
+fn test_typed_keys_roundtrip_through_base64() {
+    let kp = generate_key_pair(false).unwrap();
+    let pk_b64 = kp.public_key.to_string();
+    assert_eq!(pk_b64.parse::<PublicKey>().unwrap(), kp.public_key);
+    assert_eq!(kp.private_key.to_secret_b64().expose_secret().parse::<PrivateKey>().unwrap(), kp.private_key);
+    assert_eq!(kp.private_key.public_key(), kp.public_key);
+    assert_eq!(export_public_key_b64(&(&kp.public_key).into()), pk_b64);
+
+    let nonce = Nonce::generate().unwrap();
+    assert_eq!(nonce.to_string().parse::<Nonce>().unwrap(), nonce);
+    let salt = Salt::generate().unwrap();
+    assert_eq!(salt.as_bytes().len(), 16);
+    assert_eq!(salt.to_string().parse::<Salt>().unwrap(), salt);
+}
+
+#[test]
+fn test_typed_keys_reject_wrong_lengths() {
+    let short = "AAAA";
+    assert!(matches!(short.parse::<PublicKey>(), Err(CryptoError::InvalidKeyLen(_))));
+    assert!(matches!(short.parse::<PrivateKey>(), Err(CryptoError::InvalidKeyLen(_))));
+    assert!(matches!(short.parse::<Nonce>(), Err(CryptoError::InvalidNonceLen(3))));
+    assert!(matches!(short.parse::<Salt>(), Err(CryptoError::InvalidKeyLen(_))));
+    assert!(matches!("not base64!".parse::<PublicKey>(), Err(CryptoError::Base64(_))));
+    assert!(serde_json::from_str::<KeyPair>(r#"{"public_key":"AAAA","private_key":"AAAA"}"#).is_err());
+}
+
+#[test]
+fn test_key_pair_json_format_is_unchanged() {
+    let kp = generate_key_pair(false).unwrap();
+    let json = serde_json::to_value(&kp).unwrap();
+    assert_eq!(json["public_key"], kp.public_key.to_string());
+    assert_eq!(json["private_key"], kp.private_key.to_secret_b64().expose_secret());
+    assert_eq!(serde_json::from_value::<KeyPair>(json).unwrap(), kp);
+    assert_eq!(format!("{:?}", kp.private_key), "PrivateKey([REDACTED])");
+}
+
+#[test]
+fn test_string_shims_interoperate_with_typed_api() {
+    let kp = generate_key_pair(false).unwrap();
+    let key = generate_message_encryption_key().unwrap();
+
+    let wrapped = wrap_symmetric_key_b64(&key, &kp.public_key.to_string()).unwrap();
+    let unwrapped = unwrap_symmetric_key(&wrapped, &kp.private_key).unwrap();
+    assert_eq!(unwrapped.to_bytes(), key.to_bytes());
+
+    let wrapped = wrap_symmetric_key(&key, &kp.public_key).unwrap();
+    let secret = kp.private_key.to_secret_b64();
+    let unwrapped = unwrap_symmetric_key_b64(&wrapped, secret.expose_secret()).unwrap();
+    assert_eq!(unwrapped.to_bytes(), key.to_bytes());
+    assert!(wrap_symmetric_key_b64(&key, "AAAA").is_err());
+}
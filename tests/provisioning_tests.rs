@@ -0,0 +1,113 @@
+use ren_sdk::crypto::devices::*;
+use ren_sdk::crypto::identity::*;
+use ren_sdk::crypto::provisioning::*;
+use ren_sdk::crypto::*;
+use base64::{engine::general_purpose, Engine as _};
+
+struct Phone {
+    identity: IdentityKeyPair,
+    device: Device,
+    list: DeviceList,
+    history_key: AeadKey,
+}
+
+fn phone() -> Phone {
+    let identity = generate_identity_key_pair().unwrap();
+    let device = Device::generate("phone").unwrap();
+    let list = DeviceList::new("alice", &identity, vec![device.info()]).unwrap();
+    Phone { identity, device, list, history_key: generate_message_encryption_key().unwrap() }
+}
+
+// Сообщения между сторонами передаются только в сериализованном виде.
+fn transmit<T: serde::Serialize + serde::de::DeserializeOwned>(msg: &T) -> T {
+    serde_json::from_str(&serde_json::to_string(msg).unwrap()).unwrap()
+}
+
+#[test]
+fn test_provisioning_links_new_device() {
+    let phone = phone();
+    let mut desktop = NewDeviceLink::new("desktop").unwrap();
+    assert_eq!(desktop.state(), ProvisioningState::AwaitingMessage);
+
+    let qr = desktop.qr_payload().unwrap();
+    let (mut link, msg) = ExistingDeviceLink::start(&qr, &phone.identity, &phone.list, &phone.history_key).unwrap();
+    assert_eq!(link.state(), ProvisioningState::AwaitingAck);
+
+    let ack = desktop.install(&transmit(&msg)).unwrap();
+    assert_eq!(desktop.state(), ProvisioningState::Complete);
+    let list = link.complete(&transmit(&ack)).unwrap();
+    assert_eq!(link.state(), ProvisioningState::Complete);
+    assert_eq!(list.generation, 2);
+    assert_eq!(list.device("desktop").unwrap(), &desktop.device().info());
+
+    let account = desktop.into_account().unwrap();
+    assert_eq!(account.account_id, "alice");
+    assert_eq!(account.identity, phone.identity);
+    assert_eq!(account.device_list, list);
+    assert_eq!(account.history_key.to_bytes(), phone.history_key.to_bytes());
+
+    // контакт принимает новый список, и ключи сообщений доходят до обоих устройств
+    let mut directory = DeviceDirectory::new();
+    directory.accept(phone.list.clone()).unwrap();
+    let update = directory.accept(list).unwrap();
+    assert_eq!(update.added, vec![account.device.info()]);
+    let key = generate_message_encryption_key().unwrap();
+    let keys = directory.wrap_for_accounts(&key, &["alice"]).unwrap();
+    assert_eq!(unwrap_for_device(&keys, "alice", &account.device).unwrap().to_bytes(), key.to_bytes());
+    assert_eq!(unwrap_for_device(&keys, "alice", &phone.device).unwrap().to_bytes(), key.to_bytes());
+}
+
+#[test]
+fn test_provisioning_rejects_out_of_order_and_replayed_messages() {
+    let phone = phone();
+    let mut desktop = NewDeviceLink::new("desktop").unwrap();
+    let qr = desktop.qr_payload().unwrap();
+    let (mut link, msg) = ExistingDeviceLink::start(&qr, &phone.identity, &phone.list, &phone.history_key).unwrap();
+
+    // ack до install: список не изменился
+    let stale = ProvisioningAck { version: PROVISIONING_VERSION, device_list: phone.list.clone() };
+    assert!(matches!(link.complete(&stale), Err(CryptoError::Provisioning(_))));
+
+    let ack = desktop.install(&msg).unwrap();
+    assert!(matches!(desktop.install(&msg), Err(CryptoError::Provisioning(_))));
+    assert!(matches!(desktop.qr_payload(), Err(CryptoError::Provisioning(_))));
+    link.complete(&ack).unwrap();
+    assert!(matches!(link.complete(&ack), Err(CryptoError::Provisioning(_))));
+}
+
+#[test]
+fn test_provisioning_message_is_bound_to_scanned_key() {
+    let phone = phone();
+    let mut desktop = NewDeviceLink::new("desktop").unwrap();
+    let mut other = NewDeviceLink::new("tablet").unwrap();
+    let qr = other.qr_payload().unwrap();
+    let (_, msg) = ExistingDeviceLink::start(&qr, &phone.identity, &phone.list, &phone.history_key).unwrap();
+
+    // сообщение для другого QR не расшифровывается, а состояние не меняется
+    assert!(desktop.install(&msg).is_err());
+    assert_eq!(desktop.state(), ProvisioningState::AwaitingMessage);
+
+    // nonce от другого сообщения — тег не сходится
+    let (_, msg2) = ExistingDeviceLink::start(&qr, &phone.identity, &phone.list, &phone.history_key).unwrap();
+    let mut tampered = msg.clone();
+    tampered.nonce = msg2.nonce;
+    assert!(matches!(other.install(&tampered), Err(CryptoError::AuthenticationFailed)));
+    other.install(&msg).unwrap();
+}
+
+#[test]
+fn test_provisioning_checks_inputs() {
+    let phone = phone();
+    let desktop = NewDeviceLink::new("desktop").unwrap();
+    let qr = desktop.qr_payload().unwrap();
+
+    assert!(matches!(parse_provisioning_qr("AQAA"), Err(CryptoError::Provisioning(_))));
+    let mut bytes = general_purpose::STANDARD.decode(&qr).unwrap();
+    bytes[0] = 7;
+    let bad = general_purpose::STANDARD.encode(&bytes);
+    assert!(matches!(parse_provisioning_qr(&bad), Err(CryptoError::UnsupportedVersion(7))));
+
+    let stranger = generate_identity_key_pair().unwrap();
+    let res = ExistingDeviceLink::start(&qr, &stranger, &phone.list, &phone.history_key);
+    assert!(matches!(res, Err(CryptoError::KeyPairMismatch)));
+}
@@ -0,0 +1,83 @@
+use ren_sdk::crypto::*;
+use ren_sdk::crypto::identity::*;
+use ren_sdk::crypto::ratchet::*;
+use ren_sdk::crypto::x3dh::*;
+
+fn session_pair() -> (RatchetSession, RatchetSession) {
+    let alice = generate_identity_key_pair().unwrap();
+    let mut bob_store = PreKeyStore::new(generate_identity_key_pair().unwrap(), 1).unwrap();
+    let bundle = bob_store.bundle();
+    let (alice_out, msg) = x3dh_initiate(&alice, &bundle, b"").unwrap();
+    let (bob_out, _) = bob_store.accept(&msg).unwrap();
+
+    let a = RatchetSession::init_initiator(&alice_out.shared_secret, &bundle.signed_prekey, &alice_out.associated_data).unwrap();
+    let b = RatchetSession::init_responder(&bob_out.shared_secret, bob_store.signed_prekey.key_pair.clone(), &bob_out.associated_data);
+    (a, b)
+}
+
+#[test]
+fn test_ratchet_ping_pong() {
+    let (mut alice, mut bob) = session_pair();
+    for i in 0..3 {
+        let m = alice.encrypt(&format!("a{i}")).unwrap();
+        assert_eq!(bob.decrypt(&m).unwrap(), format!("a{i}"));
+        let m = bob.encrypt(&format!("b{i}")).unwrap();
+        assert_eq!(alice.decrypt(&m).unwrap(), format!("b{i}"));
+    }
+}
+
+#[test]
+fn test_ratchet_responder_cannot_send_first() {
+    let (_, mut bob) = session_pair();
+    assert!(matches!(bob.encrypt("too early"), Err(CryptoError::RatchetNotReady)));
+}
+
+#[test]
+fn test_ratchet_out_of_order_uses_skipped_keys() {
+    let (mut alice, mut bob) = session_pair();
+    let m0 = alice.encrypt("m0").unwrap();
+    let m1 = alice.encrypt("m1").unwrap();
+    let m2 = alice.encrypt("m2").unwrap();
+
+    assert_eq!(bob.decrypt(&m2).unwrap(), "m2");
+    assert_eq!(bob.skipped_keys_len(), 2);
+    assert_eq!(bob.decrypt(&m0).unwrap(), "m0");
+    assert_eq!(bob.decrypt(&m1).unwrap(), "m1");
+    assert_eq!(bob.skipped_keys_len(), 0);
+    // повтор уже расшифрованного сообщения отклоняется
+    assert!(bob.decrypt(&m1).is_err());
+}
+
+#[test]
+fn test_ratchet_skip_limit() {
+    let (mut alice, mut bob) = session_pair();
+    let mut m = alice.encrypt("x").unwrap();
+    m.header.message_number = MAX_SKIP + 1;
+    assert!(matches!(bob.decrypt(&m), Err(CryptoError::TooManySkippedMessages)));
+}
+
+#[test]
+fn test_ratchet_tampered_header_fails_without_state_change() {
+    let (mut alice, mut bob) = session_pair();
+    let m = alice.encrypt("hello").unwrap();
+    let mut bad = m.clone();
+    bad.header.previous_chain_length = 7;
+    let before = bob.clone();
+    assert!(bob.decrypt(&bad).is_err());
+    assert_eq!(bob, before);
+    assert_eq!(bob.decrypt(&m).unwrap(), "hello");
+}
+
+#[test]
+fn test_ratchet_session_serde_roundtrip() {
+    let (mut alice, bob) = session_pair();
+    let m = alice.encrypt("persist me").unwrap();
+    let json = serde_json::to_string(&bob).unwrap();
+    let root_key = serde_json::from_str::<serde_json::Value>(&json).unwrap()["root_key"].as_str().unwrap().to_string();
+    let debug = format!("{bob:?}");
+    assert!(debug.contains("SymmetricKey([REDACTED])") && !debug.contains(&root_key));
+    let mut restored: RatchetSession = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.decrypt(&m).unwrap(), "persist me");
+    let reply = restored.encrypt("ok").unwrap();
+    assert_eq!(alice.decrypt(&reply).unwrap(), "ok");
+}
@@ -0,0 +1,60 @@
+use ren_sdk::crypto::recipients::*;
+use ren_sdk::crypto::*;
+
+fn roster(names: &[&str]) -> (Vec<Recipient>, Vec<KeyPair>) {
+    let kps: Vec<KeyPair> = names.iter().map(|_| generate_key_pair(false).unwrap()).collect();
+    let members = names.iter().zip(&kps).map(|(id, kp)| Recipient { id: id.to_string(), public_key: kp.public_key }).collect();
+    (members, kps)
+}
+
+#[test]
+fn test_wrap_for_recipients_each_finds_own_entry() {
+    let (members, kps) = roster(&["alice", "bob", "carol"]);
+    let key = generate_message_encryption_key().unwrap();
+    for keys in [wrap_for_recipients(&key, &members).unwrap(), wrap_for_recipients_shared(&key, &members).unwrap()] {
+        assert_eq!(keys.recipients.len(), 3);
+        let json = serde_json::to_string(&keys).unwrap();
+        let keys: RecipientKeys = serde_json::from_str(&json).unwrap();
+        for (m, kp) in members.iter().zip(&kps) {
+            assert_eq!(unwrap_for_recipient(&keys, &m.id, &kp.private_key).unwrap().to_bytes(), key.to_bytes());
+        }
+        assert!(matches!(unwrap_for_recipient(&keys, "dave", &kps[0].private_key), Err(CryptoError::Recipients(_))));
+        assert!(unwrap_for_recipient(&keys, "bob", &kps[0].private_key).is_err());
+    }
+}
+
+#[test]
+fn test_per_recipient_entries_match_wrap_symmetric_key() {
+    let (members, kps) = roster(&["alice"]);
+    let key = generate_message_encryption_key().unwrap();
+    let keys = wrap_for_recipients(&key, &members).unwrap();
+    assert_eq!(keys.ephemeral_public_key, None);
+    let e = &keys.recipients["alice"];
+    let wrapped = WrappedKey {
+        version: WRAPPED_KEY_VERSION,
+        wrapped_key: e.wrapped_key.clone(),
+        ephemeral_public_key: e.ephemeral_public_key.unwrap(),
+        nonce: e.nonce,
+    };
+    let unwrapped = unwrap_symmetric_key(&wrapped, &kps[0].private_key).unwrap();
+    assert_eq!(unwrapped.to_bytes(), key.to_bytes());
+}
+
+#[test]
+fn test_shared_ephemeral_entries_are_bound_to_recipient() {
+    let (members, kps) = roster(&["alice", "bob"]);
+    let key = generate_message_encryption_key().unwrap();
+    let mut keys = wrap_for_recipients_shared(&key, &members).unwrap();
+    assert!(keys.ephemeral_public_key.is_some());
+    assert!(keys.recipients.values().all(|e| e.ephemeral_public_key.is_none()));
+
+    // Чужая запись под своим id не расшифровывается.
+    let bobs = keys.recipients["bob"].clone();
+    keys.recipients.insert("alice".into(), bobs);
+    assert!(matches!(unwrap_for_recipient(&keys, "alice", &kps[0].private_key), Err(CryptoError::AuthenticationFailed)));
+
+    let dup = vec![members[0].clone(), members[0].clone()];
+    assert!(matches!(wrap_for_recipients_shared(&key, &dup), Err(CryptoError::Recipients(_))));
+    keys.version = 2;
+    assert!(matches!(unwrap_for_recipient(&keys, "bob", &kps[1].private_key), Err(CryptoError::UnsupportedVersion(2))));
+}
@@ -0,0 +1,159 @@
+use ren_sdk::crypto::*;
+use ren_sdk::crypto::sender_keys::*;
+use ren_sdk::crypto::recipients::Recipient;
+
+struct Member {
+    keys: KeyPair,
+    session: GroupSession,
+}
+
+fn setup(ids: &[&str]) -> Vec<Member> {
+    let keys: Vec<KeyPair> = ids.iter().map(|_| generate_key_pair(false).unwrap()).collect();
+    let roster: Vec<Recipient> = ids
+        .iter()
+        .zip(&keys)
+        .map(|(id, k)| Recipient { id: id.to_string(), public_key: k.public_key })
+        .collect();
+    let mut members: Vec<Member> = ids
+        .iter()
+        .zip(keys)
+        .map(|(id, keys)| Member { session: GroupSession::new("g1", id, &roster).unwrap(), keys })
+        .collect();
+    let dists: Vec<SenderKeyDistribution> =
+        members.iter().flat_map(|m| m.session.distribute(&m.keys.private_key).unwrap()).collect();
+    deliver(&mut members, &dists);
+    members
+}
+
+fn deliver(members: &mut [Member], dists: &[SenderKeyDistribution]) {
+    for d in dists {
+        let m = members.iter_mut().find(|m| m.session.own_id == d.recipient_id).unwrap();
+        m.session.process_distribution(d, &m.keys.private_key).unwrap();
+    }
+}
+
+#[test]
+fn test_sender_keys_three_members_exchange() {
+    let mut g = setup(&["alice", "bob", "carol"]);
+    let msg = g[0].session.encrypt("hello group").unwrap();
+    assert_eq!(g[1].session.decrypt(&msg).unwrap(), "hello group");
+    assert_eq!(g[2].session.decrypt(&msg).unwrap(), "hello group");
+
+    let reply = g[2].session.encrypt("hi alice").unwrap();
+    assert_eq!(g[0].session.decrypt(&reply).unwrap(), "hi alice");
+    assert_eq!(g[1].session.decrypt(&reply).unwrap(), "hi alice");
+}
+
+#[test]
+fn test_sender_keys_out_of_order_and_replay() {
+    let mut g = setup(&["alice", "bob"]);
+    let m0 = g[0].session.encrypt("m0").unwrap();
+    let m1 = g[0].session.encrypt("m1").unwrap();
+    assert_eq!(g[1].session.decrypt(&m1).unwrap(), "m1");
+    assert_eq!(g[1].session.decrypt(&m0).unwrap(), "m0");
+    assert!(g[1].session.decrypt(&m0).is_err());
+}
+
+#[test]
+fn test_sender_keys_replayed_old_distribution_rejected() {
+    let mut g = setup(&["alice", "bob"]);
+    let old = g[0].session.distribute(&g[0].keys.private_key).unwrap();
+    let m0 = g[0].session.encrypt("m0").unwrap();
+    assert_eq!(g[1].session.decrypt(&m0).unwrap(), "m0");
+
+    // та же цепочка, но более ранняя итерация: ключ m0 не возвращается
+    let bob = &mut g[1];
+    let res = bob.session.process_distribution(&old[0], &bob.keys.private_key);
+    assert!(matches!(res, Err(CryptoError::StaleSenderKey(_))));
+    assert!(g[1].session.decrypt(&m0).is_err());
+
+    // после ротации старый key_id тоже отклоняется
+    let alice = &mut g[0];
+    let dists = alice.session.remove_member("carol", &alice.keys.private_key).unwrap();
+    deliver(&mut g, &dists);
+    let bob = &mut g[1];
+    let res = bob.session.process_distribution(&old[0], &bob.keys.private_key);
+    assert!(matches!(res, Err(CryptoError::StaleSenderKey(_))));
+    let m1 = g[0].session.encrypt("m1").unwrap();
+    assert_eq!(g[1].session.decrypt(&m1).unwrap(), "m1");
+}
+
+#[test]
+fn test_sender_keys_forged_signature_rejected() {
+    let mut g = setup(&["alice", "bob"]);
+    let mut msg = g[0].session.encrypt("pay 10").unwrap();
+    msg.iteration += 1;
+    assert!(matches!(g[1].session.decrypt(&msg), Err(CryptoError::InvalidSignature)));
+}
+
+#[test]
+fn test_sender_keys_distribution_from_non_member_rejected() {
+    let mut g = setup(&["alice", "bob"]);
+    let mallory = generate_key_pair(false).unwrap();
+    let roster = vec![
+        Recipient { id: "alice".into(), public_key: mallory.public_key },
+        Recipient { id: "bob".into(), public_key: g[1].keys.public_key },
+    ];
+    // Mallory выдаёт себя за alice, но bob проверяет рассылку по настоящему ключу alice
+    let fake = GroupSession::new("g1", "alice", &roster).unwrap();
+    let dist = fake.create_distribution("bob", &mallory.private_key).unwrap();
+    let bob = &mut g[1];
+    assert!(bob.session.process_distribution(&dist, &bob.keys.private_key).is_err());
+}
+
+#[test]
+fn test_sender_keys_member_removal_triggers_rekey() {
+    let mut g = setup(&["alice", "bob", "carol"]);
+    let before = g[0].session.encrypt("before").unwrap();
+    assert_eq!(g[2].session.decrypt(&before).unwrap(), "before");
+
+    // carol удалена: alice и bob ротируют свои цепочки и рассылают их оставшимся
+    let mut dists = Vec::new();
+    for m in g.iter_mut().take(2) {
+        dists.extend(m.session.remove_member("carol", &m.keys.private_key).unwrap());
+    }
+    assert!(dists.iter().all(|d| d.recipient_id != "carol"));
+    let (remaining, carol) = g.split_at_mut(2);
+    deliver(remaining, &dists);
+    assert_eq!(remaining[0].session.own_key_id(), 1);
+
+    let after = remaining[0].session.encrypt("after").unwrap();
+    assert_eq!(remaining[1].session.decrypt(&after).unwrap(), "after");
+    assert!(carol[0].session.decrypt(&after).is_err());
+    assert!(remaining[1].session.decrypt(&before).is_err());
+}
+
+#[test]
+fn test_sender_keys_key_id_does_not_wrap() {
+    let g = setup(&["alice", "bob", "carol"]);
+    let mut json = serde_json::to_value(&g[0].session).unwrap();
+    json["own"]["key_id"] = u32::MAX.into();
+    let mut alice: GroupSession = serde_json::from_value(json).unwrap();
+    let before = alice.clone();
+    let res = alice.remove_member("carol", &g[0].keys.private_key);
+    assert!(matches!(res, Err(CryptoError::SenderKeyIdExhausted(_))));
+    assert_eq!(alice, before);
+}
+
+#[test]
+fn test_sender_keys_add_member_and_serde_roundtrip() {
+    let mut g = setup(&["alice", "bob"]);
+    let dave_keys = generate_key_pair(false).unwrap();
+    let dave = Recipient { id: "dave".into(), public_key: dave_keys.public_key };
+    let mut roster = vec![dave.clone()];
+    roster.extend(g.iter().map(|m| Recipient { id: m.session.own_id.clone(), public_key: m.keys.public_key }));
+    let mut dave_session = GroupSession::new("g1", "dave", &roster).unwrap();
+
+    let alice = &mut g[0];
+    let old = alice.session.encrypt("old").unwrap();
+    let dist = alice.session.add_member(dave, &alice.keys.private_key).unwrap();
+    dave_session.process_distribution(&dist, &dave_keys.private_key).unwrap();
+
+    let json = serde_json::to_string(&dave_session).unwrap();
+    let mut restored: GroupSession = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, dave_session);
+
+    let msg = alice.session.encrypt("welcome dave").unwrap();
+    assert_eq!(restored.decrypt(&msg).unwrap(), "welcome dave");
+    assert!(restored.decrypt(&old).is_err());
+}
@@ -0,0 +1,189 @@
+use ren_sdk::crypto::stream::*;
+use ren_sdk::crypto::*;
+
+fn sample(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 % 251) as u8).collect()
+}
+
+fn encrypt(data: &[u8], key: &AeadKey, chunk_size: u32) -> (EncryptedStream, Vec<u8>) {
+    let mut ct = Vec::new();
+    let info = encrypt_stream(data, &mut ct, key, "video.mp4", "video/mp4", chunk_size).unwrap();
+    (info, ct)
+}
+
+#[test]
+fn test_stream_roundtrip_across_chunk_boundaries() {
+    let key = generate_message_encryption_key().unwrap();
+    for len in [0, 1, 63, 64, 65, 128, 1000] {
+        let data = sample(len);
+        let (info, ct) = encrypt(&data, &key, 64);
+        assert_eq!(info.plaintext_len, len as u64);
+        assert_eq!(info.chunk_count as usize, len.div_ceil(64).max(1));
+        assert_eq!(ct.len() as u64, info.ciphertext_len());
+
+        let mut out = Vec::new();
+        assert_eq!(decrypt_stream(ct.as_slice(), &mut out, &key, &info).unwrap(), len as u64);
+        assert_eq!(out, data);
+    }
+}
+
+#[test]
+fn test_stream_truncation_detected() {
+    let key = generate_message_encryption_key().unwrap();
+    let (info, ct) = encrypt(&sample(256), &key, 64);
+    let chunk = 64 + STREAM_TAG_LEN;
+
+    // Обрезка ровно по границе чанка: последний оставшийся чанк не помечен как последний.
+    let truncated = &ct[..3 * chunk];
+    assert!(matches!(decrypt_stream(truncated, &mut Vec::new(), &key, &info), Err(CryptoError::AuthenticationFailed)));
+    // Даже с подогнанными метаданными.
+    let forged = EncryptedStream { chunk_count: 3, plaintext_len: 192, ..info.clone() };
+    assert!(decrypt_stream(truncated, &mut Vec::new(), &key, &forged).is_err());
+
+    assert!(decrypt_stream(&ct[..ct.len() - 1], &mut Vec::new(), &key, &info).is_err());
+    assert!(decrypt_stream(&[][..], &mut Vec::new(), &key, &info).is_err());
+}
+
+#[test]
+fn test_stream_reorder_and_tamper_detected() {
+    let key = generate_message_encryption_key().unwrap();
+    let (info, ct) = encrypt(&sample(256), &key, 64);
+    let chunk = 64 + STREAM_TAG_LEN;
+
+    let mut swapped = ct.clone();
+    swapped[..chunk].copy_from_slice(&ct[chunk..2 * chunk]);
+    swapped[chunk..2 * chunk].copy_from_slice(&ct[..chunk]);
+    assert!(decrypt_stream(swapped.as_slice(), &mut Vec::new(), &key, &info).is_err());
+
+    let mut flipped = ct.clone();
+    flipped[100] ^= 1;
+    assert!(decrypt_stream(flipped.as_slice(), &mut Vec::new(), &key, &info).is_err());
+
+    let other = generate_message_encryption_key().unwrap();
+    assert!(decrypt_stream(ct.as_slice(), &mut Vec::new(), &other, &info).is_err());
+
+    let wrong_size = EncryptedStream { chunk_size: 32, ..info.clone() };
+    assert!(decrypt_stream(ct.as_slice(), &mut Vec::new(), &key, &wrong_size).is_err());
+}
+
+#[test]
+fn test_stream_metadata_serde_and_validation() {
+    let key = generate_message_encryption_key().unwrap();
+    let (info, ct) = encrypt(b"attachment", &key, DEFAULT_CHUNK_SIZE);
+    let json = serde_json::to_string(&info).unwrap();
+    let restored: EncryptedStream = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, info);
+    assert_eq!(restored.filename, "video.mp4");
+
+    let mut out = Vec::new();
+    decrypt_stream(ct.as_slice(), &mut out, &key, &restored).unwrap();
+    assert_eq!(out, b"attachment");
+
+    assert!(matches!(encrypt_stream(&b"x"[..], Vec::new(), &key, "a", "b", 0), Err(CryptoError::Stream(_))));
+    let bad_count = EncryptedStream { chunk_count: 2, ..info.clone() };
+    assert!(matches!(decrypt_stream(ct.as_slice(), &mut Vec::new(), &key, &bad_count), Err(CryptoError::Stream(_))));
+
+    // размер чанка из чужих метаданных ограничен до выделения буферов
+    let too_big = MAX_CHUNK_SIZE + 1;
+    assert!(matches!(encrypt_stream(&b"x"[..], Vec::new(), &key, "a", "b", too_big), Err(CryptoError::Stream(_))));
+    let huge = EncryptedStream { chunk_size: u32::MAX, ..info };
+    assert!(matches!(decrypt_stream(ct.as_slice(), &mut Vec::new(), &key, &huge), Err(CryptoError::Stream(_))));
+    let res = SeekableStreamReader::new(std::io::Cursor::new(&ct), &key, &huge);
+    assert!(matches!(res, Err(CryptoError::Stream(_))));
+}
+
+#[cfg(feature = "native")]
+#[tokio::test]
+async fn test_stream_async_matches_sync_format() {
+    let key = generate_message_encryption_key().unwrap();
+    let data = sample(500);
+
+    let mut ct = Vec::new();
+    let info = encrypt_stream_async(data.as_slice(), &mut ct, &key, "a.bin", "application/octet-stream", 64).await.unwrap();
+    assert_eq!(info.chunk_count, 8);
+
+    let mut out = Vec::new();
+    decrypt_stream(ct.as_slice(), &mut out, &key, &info).unwrap();
+    assert_eq!(out, data);
+
+    let (info, ct) = encrypt(&data, &key, 64);
+    let mut out = Vec::new();
+    decrypt_stream_async(ct.as_slice(), &mut out, &key, &info).await.unwrap();
+    assert_eq!(out, data);
+}
+
+#[test]
+fn test_seekable_reader_random_access() {
+    use std::io::{Cursor, Read, Seek, SeekFrom};
+
+    let key = generate_message_encryption_key().unwrap();
+    let data = sample(1000);
+    let (info, ct) = encrypt(&data, &key, 64);
+    let mut reader = SeekableStreamReader::new(Cursor::new(ct.clone()), &key, &info).unwrap();
+    assert_eq!(reader.len(), 1000);
+
+    for (pos, len) in [(0u64, 10usize), (60, 10), (500, 200), (990, 10), (128, 64)] {
+        reader.seek(SeekFrom::Start(pos)).unwrap();
+        let mut buf = vec![0u8; len];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, &data[pos as usize..pos as usize + len]);
+    }
+    assert_eq!(reader.seek(SeekFrom::End(-5)).unwrap(), 995);
+    let mut tail = Vec::new();
+    reader.read_to_end(&mut tail).unwrap();
+    assert_eq!(tail, &data[995..]);
+    assert!(reader.seek(SeekFrom::Current(-2000)).is_err());
+
+    // Обрезанный шифртекст отклоняется сразу, подменённый чанк — при чтении.
+    assert!(SeekableStreamReader::new(Cursor::new(ct[..ct.len() - 80].to_vec()), &key, &info).is_err());
+    let mut tampered = ct.clone();
+    tampered[3 * 80 + 5] ^= 1;
+    let mut reader = SeekableStreamReader::new(Cursor::new(tampered), &key, &info).unwrap();
+    reader.seek(SeekFrom::Start(100)).unwrap();
+    assert!(reader.read_exact(&mut [0u8; 10]).is_ok());
+    reader.seek(SeekFrom::Start(200)).unwrap();
+    assert_eq!(reader.read_exact(&mut [0u8; 10]).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_decrypt_stream_range_reads_only_covering_chunks() {
+    let key = generate_message_encryption_key().unwrap();
+    let data = sample(1000);
+    let (info, ct) = encrypt(&data, &key, 64);
+
+    let range = stream_range(&info, 100, 100).unwrap();
+    assert_eq!(range.first_chunk, 1);
+    assert_eq!(range.ciphertext_offset, 80);
+    assert_eq!(range.ciphertext_len, 3 * 80);
+    let slice = &ct[range.ciphertext_offset as usize..(range.ciphertext_offset + range.ciphertext_len) as usize];
+    assert_eq!(decrypt_stream_range(slice, &key, &info, 100, 100).unwrap(), &data[100..200]);
+
+    // Диапазон до конца файла и за его пределами.
+    let range = stream_range(&info, 950, 500).unwrap();
+    let slice = &ct[range.ciphertext_offset as usize..];
+    assert_eq!(decrypt_stream_range(slice, &key, &info, 950, 500).unwrap(), &data[950..]);
+    assert_eq!(stream_range(&info, 2000, 10).unwrap().ciphertext_len, 0);
+    assert!(decrypt_stream_range(&[], &key, &info, 2000, 10).unwrap().is_empty());
+
+    assert!(matches!(decrypt_stream_range(&slice[1..], &key, &info, 950, 500), Err(CryptoError::Stream(_))));
+    let bad = EncryptedStream { chunk_count: 3, ..info };
+    assert!(matches!(stream_range(&bad, 0, 10), Err(CryptoError::Stream(_))));
+}
+
+#[cfg(feature = "ffi")]
+#[test]
+fn test_ffi_stream_range_shims() {
+    use base64::{engine::general_purpose, Engine as _};
+    let key = generate_message_encryption_key().unwrap();
+    let data = sample(1000);
+    let (info, ct) = encrypt(&data, &key, 64);
+    let info_json = serde_json::to_string(&info).unwrap();
+    let key_b64 = general_purpose::STANDARD.encode(key.to_bytes());
+
+    let range = ren_sdk::stream_range_json(&info_json, 300, 50).unwrap();
+    assert_eq!(range, stream_range(&info, 300, 50).unwrap());
+    let slice = &ct[range.ciphertext_offset as usize..(range.ciphertext_offset + range.ciphertext_len) as usize];
+    assert_eq!(ren_sdk::decrypt_stream_range_b64(slice, &key_b64, &info_json, 300, 50).unwrap(), &data[300..350]);
+    assert!(ren_sdk::decrypt_stream_range_b64(slice, "AAAA", &info_json, 300, 50).is_err());
+    assert!(matches!(ren_sdk::stream_range_json("{}", 0, 1), Err(CryptoError::Json(_))));
+}
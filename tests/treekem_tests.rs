@@ -0,0 +1,223 @@
+use ren_sdk::crypto::identity::*;
+use ren_sdk::crypto::treekem::*;
+use ren_sdk::crypto::*;
+
+fn key_package(id: &str) -> KeyPackageBundle {
+    generate_key_package(id, &generate_identity_key_pair().unwrap()).unwrap()
+}
+
+// alice создаёт группу и добавляет остальных одним коммитом.
+fn setup(ids: &[&str]) -> Vec<TreeKemGroup> {
+    let mut creator = TreeKemGroup::create("g1", key_package(ids[0])).unwrap();
+    let bundles: Vec<KeyPackageBundle> = ids[1..].iter().map(|id| key_package(id)).collect();
+    let kps: Vec<KeyPackage> = bundles.iter().map(|b| b.key_package.clone()).collect();
+    let (_, welcome) = creator.add_members(&kps).unwrap();
+    let mut groups = vec![creator];
+    groups.extend(bundles.into_iter().map(|b| TreeKemGroup::join(&welcome, b).unwrap()));
+    groups
+}
+
+fn apply(groups: &mut [TreeKemGroup], committer: usize, commit: &TreeKemCommit) {
+    for (i, g) in groups.iter_mut().enumerate() {
+        if i != committer {
+            g.process_commit(commit).unwrap();
+        }
+    }
+}
+
+fn assert_converged(groups: &[TreeKemGroup]) {
+    for g in &groups[1..] {
+        assert_eq!(g.epoch(), groups[0].epoch());
+        assert_eq!(g.epoch_authenticator(), groups[0].epoch_authenticator());
+        assert_eq!(g.members(), groups[0].members());
+    }
+}
+
+#[test]
+fn test_treekem_create_add_and_exchange() {
+    let mut g = setup(&["alice", "bob", "carol"]);
+    assert_converged(&g);
+    assert_eq!(g[0].epoch(), 1);
+
+    let dave = key_package("dave");
+    let (commit, welcome) = g[1].add_members(std::slice::from_ref(&dave.key_package)).unwrap();
+    apply(&mut g, 1, &commit);
+    g.push(TreeKemGroup::join(&welcome, dave).unwrap());
+    assert_converged(&g);
+    assert_eq!(g[0].members().len(), 4);
+
+    for sender in 0..g.len() {
+        let msg = g[sender].encrypt("hello group").unwrap();
+        for (i, r) in g.iter_mut().enumerate() {
+            if i != sender {
+                assert_eq!(r.decrypt(&msg).unwrap(), "hello group");
+            }
+        }
+    }
+}
+
+#[test]
+fn test_treekem_remove_member_locks_out() {
+    let mut g = setup(&["alice", "bob", "carol", "dave"]);
+    let commit = g[0].remove_members(&["bob"]).unwrap();
+    let mut bob = g.remove(1);
+    apply(&mut g, 0, &commit);
+    assert_converged(&g);
+    assert!(matches!(bob.process_commit(&commit), Err(CryptoError::TreeKem(_))));
+
+    let msg = g[2].encrypt("without bob").unwrap();
+    assert_eq!(g[0].decrypt(&msg).unwrap(), "without bob");
+    assert!(bob.decrypt(&msg).is_err());
+
+    // Новый участник занимает освободившийся лист.
+    let erin = key_package("erin");
+    let (commit, welcome) = g[1].add_members(std::slice::from_ref(&erin.key_package)).unwrap();
+    apply(&mut g, 1, &commit);
+    let erin = TreeKemGroup::join(&welcome, erin).unwrap();
+    assert_eq!(erin.own_leaf_index(), 1);
+    g.push(erin);
+    assert_converged(&g);
+}
+
+#[test]
+fn test_treekem_update_advances_epoch() {
+    let mut g = setup(&["alice", "bob", "carol"]);
+    let old_msg = g[0].encrypt("old epoch").unwrap();
+    let before = g[0].epoch_authenticator().to_string();
+
+    let commit = g[2].update().unwrap();
+    apply(&mut g, 2, &commit);
+    assert_converged(&g);
+    assert_ne!(g[0].epoch_authenticator(), before);
+    assert!(matches!(g[1].decrypt(&old_msg), Err(CryptoError::TreeKem(_))));
+
+    let msg = g[1].encrypt("new epoch").unwrap();
+    assert_eq!(g[0].decrypt(&msg).unwrap(), "new epoch");
+    assert_eq!(g[2].decrypt(&msg).unwrap(), "new epoch");
+}
+
+#[test]
+fn test_treekem_proposals_committed_by_other_member() {
+    let mut g = setup(&["alice", "bob", "carol"]);
+    let dave = key_package("dave");
+    let proposal = g[1].propose(Proposal::Add { key_package: dave.key_package.clone() }).unwrap();
+    g[0].receive_proposal(&proposal).unwrap();
+    g[2].receive_proposal(&proposal).unwrap();
+
+    let (commit, welcome) = g[0].commit_pending().unwrap();
+    apply(&mut g, 0, &commit);
+    g.push(TreeKemGroup::join(&welcome.unwrap(), dave).unwrap());
+    assert_converged(&g);
+
+    let mut forged = g[2].propose(Proposal::Remove { leaf_index: 0 }).unwrap();
+    forged.sender = 1;
+    assert!(matches!(g[0].receive_proposal(&forged), Err(CryptoError::InvalidSignature)));
+}
+
+#[test]
+fn test_treekem_commit_includes_pending_proposals() {
+    let mut g = setup(&["alice", "bob", "carol"]);
+    let proposal = g[1].propose(Proposal::Remove { leaf_index: 2 }).unwrap();
+    g[0].receive_proposal(&proposal).unwrap();
+    assert_eq!(g[0].pending_proposals().len(), 1);
+
+    // add_members не теряет принятое предложение: carol удаляется тем же коммитом
+    let dave = key_package("dave");
+    let (commit, welcome) = g[0].add_members(std::slice::from_ref(&dave.key_package)).unwrap();
+    assert_eq!(commit.proposals.len(), 2);
+    assert!(g[0].pending_proposals().is_empty());
+    g[1].process_commit(&commit).unwrap();
+    assert!(matches!(g[2].process_commit(&commit), Err(CryptoError::TreeKem(_))));
+    g.truncate(2);
+    g.push(TreeKemGroup::join(&welcome, dave).unwrap());
+    assert_converged(&g);
+    assert_eq!(g[0].members().len(), 3);
+    assert!(g[0].members().iter().all(|(_, m)| m != "carol"));
+
+    g[0].propose(Proposal::Remove { leaf_index: 1 }).unwrap();
+    g[0].discard_pending_proposals();
+    assert!(g[0].update().unwrap().proposals.is_empty());
+}
+
+#[test]
+fn test_treekem_self_remove_proposal_does_not_block_commits() {
+    let mut g = setup(&["alice", "bob", "carol"]);
+    // bob предлагает удалить alice: alice не ставит его в очередь и может коммитить дальше
+    let proposal = g[1].propose(Proposal::Remove { leaf_index: 0 }).unwrap();
+    assert!(matches!(g[0].receive_proposal(&proposal), Err(CryptoError::TreeKem(_))));
+    assert!(g[0].pending_proposals().is_empty());
+    let commit = g[0].update().unwrap();
+    apply(&mut g, 0, &commit);
+    assert_converged(&g);
+
+    // carol сама просит её удалить: у неё предложение не в очереди, коммитит bob
+    let leave = g[2].propose(Proposal::Remove { leaf_index: 2 }).unwrap();
+    assert!(g[2].pending_proposals().is_empty());
+    g[1].receive_proposal(&leave).unwrap();
+    let (commit, _) = g[1].commit_pending().unwrap();
+    g[0].process_commit(&commit).unwrap();
+    g.truncate(2);
+    assert_converged(&g);
+    assert_eq!(g[0].members().len(), 2);
+}
+
+#[test]
+fn test_treekem_tree_shape_checked_on_deserialize() {
+    let g = setup(&["alice", "bob", "carol"]);
+    let mut json = serde_json::to_value(&g[0]).unwrap();
+    let tree: RatchetTree = serde_json::from_value(json["tree"].clone()).unwrap();
+    assert_eq!(tree.leaf_count(), 4);
+
+    for nodes in [
+        serde_json::json!([]),
+        serde_json::json!([null, null]),
+        serde_json::json!([null, null, null, null, null, null]),
+        serde_json::json!([null, json["tree"]["nodes"][0].clone(), null]),
+        serde_json::json!([json["tree"]["nodes"][1].clone(), null, null]),
+    ] {
+        assert!(serde_json::from_value::<RatchetTree>(serde_json::json!({ "nodes": nodes })).is_err());
+    }
+
+    // внутренний узел со ссылкой на лист вне своего поддерева
+    let i = json["tree"]["nodes"].as_array().unwrap().iter().position(|n| n["type"] == "parent").unwrap();
+    json["tree"]["nodes"][i]["unmerged_leaves"] = serde_json::json!([1000]);
+    assert!(serde_json::from_value::<TreeKemGroup>(json).is_err());
+}
+
+#[test]
+fn test_treekem_tampered_commit_rejected() {
+    let mut g = setup(&["alice", "bob", "carol"]);
+    let commit = g[0].update().unwrap();
+
+    let mut bad_tag = commit.clone();
+    bad_tag.confirmation_tag = g[1].epoch_authenticator().to_string();
+    let before = g[1].clone();
+    assert!(g[1].process_commit(&bad_tag).is_err());
+    assert_eq!(g[1], before);
+
+    let mut bad_sig = commit.clone();
+    bad_sig.proposals.push(Proposal::Remove { leaf_index: 2 });
+    assert!(matches!(g[1].process_commit(&bad_sig), Err(CryptoError::InvalidSignature)));
+
+    g[1].process_commit(&commit).unwrap();
+    assert!(matches!(g[1].process_commit(&commit), Err(CryptoError::TreeKem(_))));
+}
+
+#[test]
+fn test_treekem_out_of_order_serde_and_exporter() {
+    let mut g = setup(&["alice", "bob"]);
+    let m1 = g[0].encrypt("one").unwrap();
+    let m2 = g[0].encrypt("two").unwrap();
+    assert_eq!(g[1].decrypt(&m2).unwrap(), "two");
+    assert_eq!(g[1].decrypt(&m1).unwrap(), "one");
+    assert!(g[1].decrypt(&m1).is_err());
+
+    let json = serde_json::to_string(&g[1]).unwrap();
+    let mut restored: TreeKemGroup = serde_json::from_str(&json).unwrap();
+    let m3 = g[0].encrypt("three").unwrap();
+    assert_eq!(restored.decrypt(&m3).unwrap(), "three");
+
+    let a = g[0].export_secret("call", b"room-1", 32).unwrap();
+    assert_eq!(a, g[1].export_secret("call", b"room-1", 32).unwrap());
+    assert_ne!(a, g[1].export_secret("call", b"room-2", 32).unwrap());
+}
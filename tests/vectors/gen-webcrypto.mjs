@@ -0,0 +1,62 @@
+// Генерирует секцию `webcrypto` для interop.json через WebCrypto (`crypto.subtle`) так же, как web-клиент:
+// AES-256-GCM с IV 12 байт; обёртка ключа — эфемерный ECDH P-256, deriveBits(256) -> HKDF-SHA256
+// (salt пустой, info "ren-sdk-wrap") -> AES-256-GCM. Ключи P-256 — Base64(SPKI) / Base64(PKCS#8).
+//
+//   node tests/vectors/gen-webcrypto.mjs > webcrypto.json
+//
+// Требуется Node.js 20+ (глобальный `crypto.subtle`).
+
+const subtle = globalThis.crypto.subtle;
+const b64 = (buf) => Buffer.from(buf instanceof ArrayBuffer ? new Uint8Array(buf) : buf).toString("base64");
+const random = (len) => globalThis.crypto.getRandomValues(new Uint8Array(len));
+const utf8 = (s) => new TextEncoder().encode(s);
+
+const PLAINTEXTS = [
+  "",
+  "hello",
+  "Привет, мир! 👋",
+  "The quick brown fox jumps over the lazy dog. Съешь же ещё этих мягких французских булок, да выпей чаю.",
+];
+
+async function aesGcmEncrypt(rawKey, iv, plaintext) {
+  const key = await subtle.importKey("raw", rawKey, "AES-GCM", false, ["encrypt"]);
+  return subtle.encrypt({ name: "AES-GCM", iv }, key, plaintext);
+}
+
+async function encryptMessage(plaintext) {
+  const key = random(32);
+  const iv = random(12);
+  const ciphertext = await aesGcmEncrypt(key, iv, utf8(plaintext));
+  return { key: b64(key), nonce: b64(iv), plaintext, ciphertext: b64(ciphertext) };
+}
+
+async function wrapSymmetricKey() {
+  const ecdh = { name: "ECDH", namedCurve: "P-256" };
+  const receiver = await subtle.generateKey(ecdh, true, ["deriveBits"]);
+  const ephemeral = await subtle.generateKey(ecdh, true, ["deriveBits"]);
+  const shared = await subtle.deriveBits({ name: "ECDH", public: receiver.publicKey }, ephemeral.privateKey, 256);
+  const ikm = await subtle.importKey("raw", shared, "HKDF", false, ["deriveBits"]);
+  const wrapKey = await subtle.deriveBits(
+    { name: "HKDF", hash: "SHA-256", salt: new Uint8Array(0), info: utf8("ren-sdk-wrap") },
+    ikm,
+    256,
+  );
+  const key = random(32);
+  const iv = random(12);
+  const wrapped = await aesGcmEncrypt(wrapKey, iv, key);
+  return {
+    key: b64(key),
+    receiver_private_key: b64(await subtle.exportKey("pkcs8", receiver.privateKey)),
+    receiver_public_key: b64(await subtle.exportKey("spki", receiver.publicKey)),
+    ephemeral_public_key: b64(await subtle.exportKey("spki", ephemeral.publicKey)),
+    nonce: b64(iv),
+    wrapped_key: b64(wrapped),
+  };
+}
+
+const section = {
+  source: `WebCrypto (crypto.subtle), Node.js ${process.version}, tests/vectors/gen-webcrypto.mjs`,
+  encrypt_message: await Promise.all(PLAINTEXTS.map(encryptMessage)),
+  wrap_symmetric_key: [await wrapSymmetricKey(), await wrapSymmetricKey()],
+};
+console.log(JSON.stringify(section, null, 2));
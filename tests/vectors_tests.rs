@@ -0,0 +1,70 @@
+use ren_sdk::crypto::*;
+use ren_sdk::{TEST_VECTORS_VERSION, TestVectors, generate_test_vectors};
+
+// Векторы в `tests/vectors/` созданы `ren-cli gen-vectors` (секция `webcrypto` — `gen-webcrypto.mjs`); web и mobile клиенты проверяют по ним свои реализации.
+fn load(name: &str) -> TestVectors {
+    let path = format!("{}/tests/vectors/{name}", env!("CARGO_MANIFEST_DIR"));
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn test_published_vectors_verify() {
+    let vectors = load("interop.json");
+    assert_eq!(vectors.version, TEST_VECTORS_VERSION);
+    assert!(!vectors.encrypt_data.is_empty() && !vectors.encrypt_message.is_empty() && !vectors.wrap_symmetric_key.is_empty());
+    vectors.verify().unwrap();
+
+    // Ожидаемые значения читаются и обычным API, без повторного шифрования.
+    let v = &vectors.encrypt_message[1];
+    let key = AeadKey::from_bytes(&base64_decode(&v.key)).unwrap();
+    assert_eq!(decrypt_message(&v.ciphertext, &v.nonce, &key).unwrap(), v.plaintext);
+}
+
+#[test]
+fn test_generated_vectors_roundtrip_through_json() {
+    let vectors = generate_test_vectors(5).unwrap();
+    assert_eq!(vectors.encrypt_data.len(), 5);
+    let json = serde_json::to_string(&vectors).unwrap();
+    let parsed: TestVectors = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, vectors);
+    parsed.verify().unwrap();
+}
+
+#[test]
+fn test_tampered_vectors_are_reported() {
+    let mut vectors = load("interop.json");
+    vectors.encrypt_data[0].nonce = vectors.encrypt_data[1].nonce.clone();
+    assert!(matches!(vectors.verify(), Err(CryptoError::TestVector(e)) if e == "encrypt_data[0]: expected"));
+
+    let mut vectors = load("interop.json");
+    vectors.wrap_symmetric_key[2].ephemeral_public_key = vectors.wrap_symmetric_key[2].receiver_public_key.clone();
+    assert!(matches!(vectors.verify(), Err(CryptoError::TestVector(e)) if e == "wrap_symmetric_key[2]: ephemeral_public_key"));
+
+    let mut vectors = load("interop.json");
+    vectors.version = 2;
+    assert!(matches!(vectors.verify(), Err(CryptoError::UnsupportedVersion(2))));
+}
+
+// Секция `webcrypto` создана WebCrypto (`tests/vectors/gen-webcrypto.mjs`), а не этим крейтом.
+#[test]
+fn test_webcrypto_vectors_decrypt() {
+    let vectors = load("interop.json");
+    let web = vectors.webcrypto.as_ref().expect("webcrypto section");
+    assert!(web.source.starts_with("WebCrypto"));
+    assert!(!web.encrypt_message.is_empty() && !web.wrap_symmetric_key.is_empty());
+    vectors.verify().unwrap();
+
+    let v = &web.encrypt_message[2];
+    let key = AeadKey::from_bytes(&base64_decode(&v.key)).unwrap();
+    assert_eq!(webcrypto::decrypt_message(&v.ciphertext, &v.nonce, &key).unwrap(), v.plaintext);
+
+    let mut tampered = vectors.clone();
+    let web = tampered.webcrypto.as_mut().unwrap();
+    web.wrap_symmetric_key[0].receiver_public_key = web.wrap_symmetric_key[1].receiver_public_key.clone();
+    assert!(matches!(tampered.verify(), Err(CryptoError::TestVector(e)) if e == "webcrypto.wrap_symmetric_key[0]: receiver_public_key"));
+}
+
+fn base64_decode(b64: &str) -> Vec<u8> {
+    use base64::Engine as _;
+    base64::engine::general_purpose::STANDARD.decode(b64).unwrap()
+}
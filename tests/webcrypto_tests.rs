@@ -0,0 +1,68 @@
+#![cfg(feature = "crypto_p256_aesgcm")]
+
+use ren_sdk::crypto::envelope::{CipherSuite, Envelope};
+use ren_sdk::crypto::webcrypto;
+use ren_sdk::crypto::*;
+
+#[test]
+fn test_aes_gcm_matches_nist_vector() {
+    // GCM spec, test case 14: K = 0^256, IV = 0^96, P = 0^128.
+    let key = AeadKey::from_bytes(&[0u8; 32]).unwrap();
+    let combined = "AAAAAAAAAAAAAAAAzqdAPU1ga24HTsXTuvOdGNDRyKeZmWvwJluYtdSKuRk=";
+    assert_eq!(webcrypto::decrypt_data(combined, &key).unwrap(), "\0".repeat(16));
+    assert!(decrypt_data(combined, &key).is_err()); // ChaCha20-Poly1305 такой шифртекст не примет
+
+    let em = webcrypto::encrypt_message("привет", &key).unwrap();
+    assert_eq!(webcrypto::decrypt_message(&em.ciphertext, &em.nonce, &key).unwrap(), "привет");
+    let blob = webcrypto::encrypt_data("hello", &key).unwrap();
+    assert_eq!(webcrypto::decrypt_data(&blob, &key).unwrap(), "hello");
+    let other = generate_message_encryption_key().unwrap();
+    assert!(matches!(webcrypto::decrypt_message(&em.ciphertext, &em.nonce, &other), Err(CryptoError::AuthenticationFailed)));
+}
+
+#[test]
+fn test_p256_keys_use_spki_and_pkcs8() {
+    let kp = webcrypto::generate_key_pair().unwrap();
+    // DER-заголовок SPKI для ecPublicKey/prime256v1 с несжатой точкой — как у WebCrypto `exportKey("spki")`.
+    assert!(kp.public_key.starts_with("MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAE"));
+    let sk = webcrypto::import_private_key_pkcs8_b64(kp.private_key.expose_secret()).unwrap();
+    assert_eq!(webcrypto::export_public_key_spki_b64(&sk.public_key()).unwrap(), kp.public_key);
+    let pk = webcrypto::import_public_key_spki_b64(&kp.public_key).unwrap();
+    assert_eq!(pk, sk.public_key());
+    assert!(matches!(webcrypto::import_public_key_spki_b64("AAAA"), Err(CryptoError::InvalidKeyEncoding(_))));
+    assert!(!format!("{kp:?}").contains(kp.private_key.expose_secret()));
+}
+
+#[test]
+fn test_p256_wrap_unwrap() {
+    let receiver = webcrypto::generate_key_pair().unwrap();
+    let key = generate_message_encryption_key().unwrap();
+    let wrapped = webcrypto::wrap_symmetric_key(&key, &receiver.public_key).unwrap();
+    assert_eq!(wrapped.version, WRAPPED_KEY_VERSION);
+    let wrapped: webcrypto::P256WrappedKey = serde_json::from_str(&serde_json::to_string(&wrapped).unwrap()).unwrap();
+    let unwrapped = webcrypto::unwrap_symmetric_key(&wrapped, receiver.private_key.expose_secret()).unwrap();
+    assert_eq!(unwrapped.to_bytes(), key.to_bytes());
+
+    let stranger = webcrypto::generate_key_pair().unwrap();
+    assert!(webcrypto::unwrap_symmetric_key(&wrapped, stranger.private_key.expose_secret()).is_err());
+    let future = webcrypto::P256WrappedKey { version: 9, ..wrapped };
+    let res = webcrypto::unwrap_symmetric_key(&future, receiver.private_key.expose_secret());
+    assert!(matches!(res, Err(CryptoError::UnsupportedVersion(9))));
+}
+
+#[test]
+fn test_envelope_suite_selects_algorithm() {
+    let key = generate_message_encryption_key().unwrap();
+    let blob = webcrypto::encrypt_data_envelope("hello", &key, b"k1").unwrap();
+    let env = Envelope::from_b64(&blob).unwrap();
+    assert_eq!(env.suite, CipherSuite::Aes256Gcm);
+    assert_eq!(env.suite.id(), 2);
+    // Алгоритм выбирается по suite id, поэтому конверт читают оба набора функций.
+    assert_eq!(decrypt_data(&blob, &key).unwrap(), "hello");
+    assert_eq!(webcrypto::decrypt_data(&blob, &key).unwrap(), "hello");
+    assert_eq!(webcrypto::decrypt_data(&encrypt_data_envelope("hi", &key, b"k1").unwrap(), &key).unwrap(), "hi");
+
+    let mut tampered = env.clone();
+    tampered.suite = CipherSuite::ChaCha20Poly1305;
+    assert!(ren_sdk::open_envelope(&tampered, &key, &[]).is_err());
+}
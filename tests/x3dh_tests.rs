@@ -0,0 +1,95 @@
+use ren_sdk::crypto::*;
+use ren_sdk::crypto::identity::*;
+use ren_sdk::crypto::x3dh::*;
+
+#[test]
+fn test_x3dh_initiate_accept_shared_secret() {
+    let alice = generate_identity_key_pair().unwrap();
+    let mut bob = PreKeyStore::new(generate_identity_key_pair().unwrap(), 2).unwrap();
+    let bundle = bob.bundle();
+    assert!(bundle.one_time_prekey.is_some());
+
+    let (alice_out, msg) = x3dh_initiate(&alice, &bundle, b"hello bob").unwrap();
+    let (bob_out, plaintext) = bob.accept(&msg).unwrap();
+
+    assert_eq!(plaintext, b"hello bob");
+    assert_eq!(alice_out.shared_secret.to_bytes(), bob_out.shared_secret.to_bytes());
+    assert_eq!(alice_out.associated_data, bob_out.associated_data);
+    assert_eq!(bob.one_time_prekeys.len(), 1);
+}
+
+#[test]
+fn test_x3dh_one_time_prekey_cannot_be_reused() {
+    let alice = generate_identity_key_pair().unwrap();
+    let mut bob = PreKeyStore::new(generate_identity_key_pair().unwrap(), 1).unwrap();
+    let (_, msg) = x3dh_initiate(&alice, &bob.bundle(), b"first").unwrap();
+    bob.accept(&msg).unwrap();
+    assert!(matches!(bob.accept(&msg), Err(CryptoError::UnknownPreKey(_))));
+}
+
+#[test]
+fn test_x3dh_without_one_time_prekey() {
+    let alice = generate_identity_key_pair().unwrap();
+    let mut bob = PreKeyStore::new(generate_identity_key_pair().unwrap(), 0).unwrap();
+    let bundle = bob.bundle();
+    assert!(bundle.one_time_prekey_id.is_none());
+    let (alice_out, msg) = x3dh_initiate(&alice, &bundle, b"").unwrap();
+    let (bob_out, _) = bob.accept(&msg).unwrap();
+    assert_eq!(alice_out.shared_secret.to_bytes(), bob_out.shared_secret.to_bytes());
+}
+
+#[test]
+fn test_x3dh_tampered_identity_key_fails() {
+    let alice = generate_identity_key_pair().unwrap();
+    let mallory = generate_identity_key_pair().unwrap();
+    let mut bob = PreKeyStore::new(generate_identity_key_pair().unwrap(), 1).unwrap();
+    let (_, mut msg) = x3dh_initiate(&alice, &bob.bundle(), b"hi").unwrap();
+    msg.identity_key = mallory.public_key;
+    assert!(bob.accept(&msg).is_err());
+    // неудачная попытка не должна сжигать одноразовый пре-ключ
+    assert_eq!(bob.one_time_prekeys.len(), 1);
+}
+
+#[test]
+fn test_x3dh_previous_signed_prekey_accepted_after_rotation() {
+    let alice = generate_identity_key_pair().unwrap();
+    let mut bob = PreKeyStore::new(generate_identity_key_pair().unwrap(), 2).unwrap();
+    let (alice_out, in_flight) = x3dh_initiate(&alice, &bob.bundle(), b"sent before rotation").unwrap();
+    bob.rotate_signed_prekey().unwrap();
+    assert_ne!(bob.bundle().signed_prekey_id, in_flight.signed_prekey_id);
+
+    let (bob_out, plaintext) = bob.accept(&in_flight).unwrap();
+    assert_eq!(plaintext, b"sent before rotation");
+    assert_eq!(alice_out.shared_secret.to_bytes(), bob_out.shared_secret.to_bytes());
+
+    // после завершения льготного периода (или второй ротации) старый SPK не принимается
+    let (_, late) = x3dh_initiate(&alice, &PreKeyBundle { one_time_prekey_id: None, one_time_prekey: None, ..bob.bundle() }, b"x").unwrap();
+    bob.rotate_signed_prekey().unwrap();
+    bob.accept(&late).unwrap();
+    bob.rotate_signed_prekey().unwrap();
+    assert!(matches!(bob.accept(&late), Err(CryptoError::UnknownPreKey(_))));
+    bob.discard_previous_signed_prekey();
+    assert!(bob.previous_signed_prekey.is_none());
+}
+
+#[test]
+fn test_prekey_store_serde_roundtrip() {
+    let store = PreKeyStore::new(generate_identity_key_pair().unwrap(), 3).unwrap();
+    let json = serde_json::to_string(&store).unwrap();
+    let back: PreKeyStore = serde_json::from_str(&json).unwrap();
+    assert_eq!(store, back);
+
+    // хранилища, сохранённые до появления previous_signed_prekey, читаются
+    let mut old = serde_json::to_value(&store).unwrap();
+    old.as_object_mut().unwrap().remove("previous_signed_prekey");
+    assert_eq!(serde_json::from_value::<PreKeyStore>(old).unwrap(), store);
+}
+
+#[test]
+fn test_x3dh_rejects_bundle_with_forged_signed_prekey() {
+    let alice = generate_identity_key_pair().unwrap();
+    let bob = PreKeyStore::new(generate_identity_key_pair().unwrap(), 1).unwrap();
+    let mut bundle = bob.bundle();
+    bundle.signed_prekey = generate_key_pair(false).unwrap().public_key;
+    assert!(matches!(x3dh_initiate(&alice, &bundle, b"hi"), Err(CryptoError::InvalidSignature)));
+}